@@ -0,0 +1,29 @@
+//! Compare a loop of a million iterations run by `do`, which the context evaluates directly
+//! by updating a single frame, with the same loop by a named `let`, which calls a closure
+//! with a new frame for each iteration.
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use thesis_interpreter::{Interpreter, Term};
+
+const ITERATIONS: i64 = 1_000_000;
+
+fn sum_loops(c: &mut Criterion) {
+    let direct = format!("(do ((i 0 (+ i 1)) (acc 0 (+ acc i))) ((= i {ITERATIONS}) acc))");
+    let desugared = format!("(let loop ((i 0) (acc 0)) ($if (= i {ITERATIONS}) acc (loop (+ i 1) (+ acc i))))");
+    let expected = Term::int(ITERATIONS * (ITERATIONS - 1) / 2);
+
+    let mut group = c.benchmark_group("do_loop");
+    // Every iteration of a sample runs the whole loop, which takes seconds.
+    group.sample_size(10).measurement_time(Duration::from_secs(45));
+    for (name, program) in [("direct", &direct), ("named_let", &desugared)] {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(interpreter.eval_str(name, program).unwrap(), expected);
+        group.bench_function(name, |b| b.iter(|| interpreter.eval_str(name, program).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sum_loops);
+criterion_main!(benches);
@@ -1,20 +1,40 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Write;
 
+use crate::error::{Error, ErrorKind, ReportStyle};
+use crate::parser::SrcInfo;
 use crate::{if_or, seq};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Arg {
-    id: (&'static str, char), // (Id, ShortId)
+    id: (Cow<'static, str>, char), // (Id, ShortId)
     optional: bool,
     /// Determine whether to stop parsing the rest args.
     interrupt: bool,
     parameterized: Parameter,
     prefix: char,
     info: (&'static str, &'static str), // (Description, Details)
+    /// The accepted values of the parameter, where an empty slice accepts anything.
+    choices: &'static [&'static str],
+    /// Splits the parameter into a list of values, each of which is checked against the choices.
+    separator: Option<char>,
+    /// Whether the arg may be given several times, with its parameters joined by the separator.
+    repeatable: bool,
 }
 
 impl Arg {
     pub fn new(id: &'static str) -> Self {
+        Self::with_id(Cow::Borrowed(id))
+    }
+
+    /// Make an arg whose id is only known at runtime, like one read from a config.
+    pub fn owned(id: String) -> Self {
+        Self::with_id(Cow::Owned(id))
+    }
+
+    fn with_id(id: Cow<'static, str>) -> Self {
+        let optional = id.starts_with("--");
         let mut this = Self {
             id: (id, '\0'),
             optional: false,
@@ -22,9 +42,12 @@ impl Arg {
             parameterized: Parameter::No,
             prefix: '\0',
             info: ("", ""),
+            choices: &[],
+            separator: None,
+            repeatable: false,
         };
-        if id.starts_with("--") {
-            if id.len() < 4 {
+        if optional {
+            if this.id.0.len() < 4 {
                 panic!("Error: The id must has more than 4 characters.")
             }
             this.prefix = '-';
@@ -38,14 +61,33 @@ impl Arg {
     pub fn parameterize(mut self, parameterized: Parameter) -> Self {
         self.parameterized = parameterized;
         if self.prefix == '\0' {
-            match parameterized {
-                Parameter::Optional(_) => self.optional = true,
-                _ => {}
+            if let Parameter::Optional(_) | Parameter::Computed(_) = parameterized {
+                self.optional = true
             }
         }
         self
     }
 
+    pub fn get_id(&self) -> &str {
+        &self.id.0
+    }
+
+    pub fn get_short_id(&self) -> Option<char> {
+        if_or!(self.id.1 != '\0', Some(self.id.1), None)
+    }
+
+    pub fn get_parameter(&self) -> Parameter {
+        self.parameterized
+    }
+
+    pub fn is_interrupt(&self) -> bool {
+        self.interrupt
+    }
+
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
     pub fn get_default(&self) -> String {
         match self.parameterized {
             Parameter::Optional(s) => s.into(),
@@ -69,6 +111,34 @@ impl Arg {
         seq!(self.info.1 = content, self)
     }
 
+    pub fn choices(mut self, choices: &'static [&'static str]) -> Self {
+        seq!(self.choices = choices, self)
+    }
+
+    pub fn separated_by(mut self, separator: char) -> Self {
+        seq!(self.separator = Some(separator), self)
+    }
+
+    pub fn get_separator(&self) -> Option<char> {
+        self.separator
+    }
+
+    /// The values of a parameter, split by the separator if there is one.
+    pub fn split_parameter<'a>(&self, parameter: &'a str) -> Vec<&'a str> {
+        match self.separator {
+            Some(separator) => parameter.split(separator).collect(),
+            None => vec![parameter]
+        }
+    }
+
+    pub fn repeatable(mut self) -> Self {
+        seq!(self.repeatable = true, self)
+    }
+
+    pub fn is_repeatable(&self) -> bool {
+        self.repeatable
+    }
+
     pub fn interrupt(mut self) -> Self {
         seq!(self.interrupt = true, self)
     }
@@ -78,14 +148,14 @@ impl Arg {
         match self.parameterized {
             No => "".into(),
             Optional(default) => parameter.unwrap_or(&default.to_string()).clone(),
-            Required => parameter
+            Required | Computed(_) => parameter
                 .unwrap_or_else(|| panic!("Error: Parameter not found."))
                 .clone(),
         }
     }
 
     pub fn help(&self) -> String {
-        let id = self.id.0;
+        let id = &self.id.0;
         let short_id = {
             if self.id.1 != '\0' {
                 format!(", -{}", self.id.1)
@@ -94,7 +164,7 @@ impl Arg {
             }
         };
         let info_0 = self.info.0;
-        let info_1 = if self.info.1 == "" { "".into() } else {
+        let info_1 = if self.info.1.is_empty() { "".into() } else {
             "\n      ".to_owned() + self.info.1
         };
         format!(
@@ -102,6 +172,7 @@ impl Arg {
             {
                 match self.parameterized {
                     Parameter::Optional(default) => format!("[default: {default}]"),
+                    Parameter::Computed(_) => "[default: computed]".to_string(),
                     _ => "".to_string(),
                 }
             }
@@ -109,23 +180,103 @@ impl Arg {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The parameters matched from a command line, by the ids of their args without the "--",
+/// in the order they were given, followed by the defaults of the args left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Matches(Vec<(String, String)>);
+
+impl Matches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.0.iter().find(|(id, _)| id == key).map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Set the parameter of the key, which keeps its place if it was already matched.
+    pub fn insert(&mut self, key: String, value: String) {
+        match self.0.iter_mut().find(|(id, _)| *id == key) {
+            Some((_, old)) => *old = value,
+            None => self.0.push((key, value))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter().map(|(key, value)| (key, value))
+    }
+}
+
+impl std::ops::Index<&str> for Matches {
+    type Output = String;
+
+    fn index(&self, key: &str) -> &String {
+        self.get(key).unwrap_or_else(|| panic!("Error: No parameter of '{key}' was matched."))
+    }
+}
+
+impl<'a> IntoIterator for &'a Matches {
+    type Item = (&'a String, &'a String);
+    type IntoIter = Box<dyn Iterator<Item = (&'a String, &'a String)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<const N: usize> From<[(String, String); N]> for Matches {
+    fn from(pairs: [(String, String); N]) -> Self {
+        let mut matches = Self::new();
+        pairs.into_iter().for_each(|(key, value)| matches.insert(key, value));
+        matches
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum Parameter {
     No,
     Optional(&'static str),
     Required,
+    /// Like `Optional`, but the default is derived from the other parameters, once they're matched.
+    Computed(fn(&Matches) -> String),
 }
 
+impl PartialEq for Parameter {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Parameter::No, Parameter::No) | (Parameter::Required, Parameter::Required) => true,
+            (Parameter::Optional(a), Parameter::Optional(b)) => a == b,
+            (Parameter::Computed(f), Parameter::Computed(g)) => std::ptr::fn_addr_eq(*f, *g),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Parameter {}
+
 impl From<Parameter> for u8 {
     fn from(value: Parameter) -> Self {
         match value {
             Parameter::No => 0u8,
             Parameter::Optional(_) => 1u8,
-            Parameter::Required => 2u8,
+            Parameter::Required | Parameter::Computed(_) => 2u8,
         }
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Command {
     exec_name: &'static str,
     help_content: &'static str,
@@ -146,36 +297,100 @@ impl Command {
     }
 
     pub fn add_arg(&mut self, arg: Arg) {
+        if arg.repeatable && arg.separator.is_none() {
+            panic!("Error: A repeatable arg '{}' needs a separator to join its parameters.", arg.id.0)
+        }
         if arg.prefix != '\0' {
-            self.args.insert(arg.id.0.into(), arg);
-            self.added_arg_names.push(arg.id.0.into());
+            self.added_arg_names.push(arg.id.0.to_string());
             if arg.id.1 != '\0' {
-                self.args.insert(format!("-{}", arg.id.1), arg);
+                self.args.insert(format!("-{}", arg.id.1), arg.clone());
             }
+            self.args.insert(arg.id.0.to_string(), arg);
         } else {
-            if arg.optional == true && self.pos_args.last().is_some_and(|arg| !arg.optional) {
+            if arg.interrupt {
+                panic!("Error: A positional arg '{}' cannot interrupt the parsing.", arg.id.0)
+            }
+            if arg.optional && self.pos_args.last().is_some_and(|arg| !arg.optional) {
                 panic!("Error: Cannot add a optional argument after a required one.")
             }
             self.pos_args.push(arg);
         }
     }
 
-    pub fn match_with(&self, args: Vec<String>) -> Result<HashMap<String, String>, String> {
+    /// Look up a registered arg by its id, short id (like `-h`) or positional name.
+    pub fn arg(&self, name: &str) -> Option<&Arg> {
+        self.args.get(name).or_else(|| self.pos_args.iter().find(|arg| arg.id.0 == name))
+    }
+
+    pub fn positional_args(&self) -> &[Arg] {
+        &self.pos_args
+    }
+
+    /// Synthesize a source from the command line, so that errors can point into it.
+    pub fn command_line(&self, args: &[String]) -> SrcInfo {
+        let mut text = self.exec_name.to_string();
+        for arg in args {
+            text += " ";
+            text += arg;
+        }
+        SrcInfo::new("<command-line>".to_string(), text)
+    }
+
+    /// The span of `args[index]` in the synthesized command line source.
+    fn arg_span(&self, args: &[String], index: usize) -> std::ops::Range<usize> {
+        let start = args[..index].iter()
+            .fold(self.exec_name.chars().count() + 1, |start, arg| start + arg.chars().count() + 1);
+        start..(start + args[index].chars().count())
+    }
+
+    fn command_failed(&self, args: &[String], index: usize, message: String, label: String) -> Error {
+        let span = self.arg_span(args, index);
+        Error::new(ErrorKind::CommandFailed)
+            .with_message(message)
+            .with_span(span.clone())
+            .return_error(&self.command_line(args), (1, span.start + 1, span.start).into(), label)
+    }
+
+    /// Print an error returned by `match_with` to stderr, rendering a report over the command line if it has one.
+    pub fn print_error(&self, args: &[String], err: Error) {
+        match err.report {
+            // There is nowhere left to report a failure to write the report itself.
+            Some(_) => { let _ = err.write_report(&self.command_line(args), &ReportStyle::default(), std::io::stderr()); }
+            None => eprintln!("{}", err.message())
+        }
+    }
+
+    pub fn match_with(&self, args: Vec<String>) -> Result<Matches, Error> {
         let mut expect_flag: u8 = 0;
-        let mut pos_parameters: Vec<String> = vec![];
-        let mut results: HashMap<String, String> = HashMap::new();
+        let mut pos_parameters: Vec<(usize, String)> = vec![];
+        let mut results = Matches::new();
+        // The index in the args of each parameter given, by which the matches are ordered.
+        let mut given: HashMap<String, usize> = HashMap::new();
         for (i, val) in args.iter().enumerate() {
-            if expect_flag == 1 || expect_flag == 2 {
-                seq!(expect_flag = 0, break)
+            // Skip the parameter consumed by the previous flag.
+            if (expect_flag == 1 || expect_flag == 2) && i > 0 {
+                seq!(expect_flag = 0, continue)
             };
             match self.args.get(val) {
                 Some(arg) => {
-                    if !results.contains_key(&arg.id.0[1..]) {
+                    if !results.contains_key(&arg.id.0[1..]) || arg.repeatable {
+                        let parameter = arg.try_get_parameter(args.get(i + 1));
+                        let invalid = arg.split_parameter(&parameter).into_iter()
+                            .find(|value| !arg.choices.contains(value));
+                        if let (false, true, Some(value)) = (arg.choices.is_empty(), i + 1 < args.len(), invalid) {
+                            return Err(self.command_failed(&args, i + 1,
+                                format!("Invalid value '{value}' for '{}', expected one of: {}.",
+                                    arg.id.0, arg.choices.join(", ")),
+                                "Invalid value here.".to_string()))
+                        }
+                        let previous = results.get(&arg.id.0[2..]).filter(|_| arg.repeatable);
+                        let parameter = match (previous, arg.separator) {
+                            (Some(previous), Some(separator)) => format!("{previous}{separator}{parameter}"),
+                            _ => parameter
+                        };
                         // Note: The key for insertion has no "--".
-                        results.insert(
-                            arg.id.0[2..].to_string(),
-                            arg.try_get_parameter(args.get(i + 1)),
-                        );
+                        given.entry(arg.id.0[2..].to_string()).or_insert(i);
+                        results.insert(arg.id.0[2..].to_string(), parameter);
                         if_or!(arg.interrupt, return Ok(results));
                         expect_flag = arg.parameterized.into();
                         continue;
@@ -183,7 +398,7 @@ impl Command {
                         panic!("Error: Duplicate parameter of '{}' was found.", arg.id.0)
                     }
                 }
-                None => pos_parameters.push(val.clone()),
+                None => pos_parameters.push((i, val.clone())),
             }
         }
         if expect_flag == 2 {
@@ -200,23 +415,34 @@ impl Command {
         for arg in &self.pos_args {
             if_or!(
                 !arg.optional,
-                seq!(required_pos_arg += 1, required_arg_id = arg.id.0),
-                seq!(results.insert(arg.id.0.into(), arg.get_default()), ())
+                seq!(required_pos_arg += 1, required_arg_id = &arg.id.0),
+                if let Parameter::Optional(_) = arg.parameterized {
+                    results.insert(arg.id.0.to_string(), arg.get_default());
+                }
             );
             if used_pos_arg >= pos_param_len {
                 continue;
             }
-            results.insert(
-                arg.id.0.into(),
-                core::mem::take(&mut pos_parameters[used_pos_arg]),
-            );
+            let (i, parameter) = core::mem::take(&mut pos_parameters[used_pos_arg]);
+            given.insert(arg.id.0.to_string(), i);
+            results.insert(arg.id.0.to_string(), parameter);
             used_pos_arg += 1;
         }
+        results.0.sort_by_key(|(key, _)| given.get(key).copied().unwrap_or(usize::MAX));
         if used_pos_arg < required_pos_arg {
-            return Err(
+            return Err(Error::new(ErrorKind::CommandFailed).with_message(
                 format!("Error: Required argument '{}' was not found.",
                 required_arg_id
-            ));
+            )));
+        }
+        // Computed defaults come last, so that they can see every parameter given.
+        let flags = self.added_arg_names.iter().map(|name| (&self.args[name], &name[2..]));
+        let positionals = self.pos_args.iter().map(|arg| (arg, &arg.id.0[..]));
+        for (arg, key) in flags.chain(positionals) {
+            if let (Parameter::Computed(compute), false) = (arg.parameterized, results.contains_key(key)) {
+                let value = compute(&results);
+                results.insert(key.to_string(), value);
+            }
         }
         Ok(results)
     }
@@ -236,30 +462,29 @@ impl Command {
             // Don't use direct iteration to make sure
             // the output order follows the the argument order
             for arg_id in &self.added_arg_names {
-                let arg = self.args[arg_id];
+                let arg = &self.args[arg_id];
                 string += format!("\n   {}", arg.help()).as_str();
             }
             string
         };
         let exec_name = self.exec_name;
         let help_content = self.help_content;
-        println!(
+        // The help may be piped into a pager which quits early, so a failed write isn't a panic.
+        let _ = writeln!(std::io::stdout(),
             r#"Usage: {exec_name} [options]{pos_args}
       {help_content}
 
 Options:{arg_helps}"#
-        )
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Arg, Command, Parameter::*};
+    use super::{Arg, Command, Matches, Parameter::*};
 
     #[test]
     fn command_match_with_1() {
-        use std::collections::HashMap;
-
         let mut command = Command::new("cli-test", "");
         command.add_arg(
             Arg::new("--help")
@@ -268,13 +493,13 @@ mod tests {
                 .interrupt(),
         );
         command.add_arg(Arg::new("--version").short_id('v').interrupt());
-        let mut map: HashMap<String, String>;
+        let mut map: Matches;
         map = command.match_with(vec!["--help".into(), "test".into()]).unwrap();
-        assert_eq!(map, HashMap::from([("help".into(), "test".into())]));
+        assert_eq!(map, Matches::from([("help".into(), "test".into())]));
         map = command.match_with(vec!["--help".into()]).unwrap();
-        assert_eq!(map, HashMap::from([("help".into(), "\"\"".into())]));
+        assert_eq!(map, Matches::from([("help".into(), "\"\"".into())]));
         map = command.match_with(vec!["--version".into(), "--help".into()]).unwrap();
-        assert_eq!(map, HashMap::from([("version".into(), "".into())]));
+        assert_eq!(map, Matches::from([("version".into(), "".into())]));
     }
 
     #[test]
@@ -282,6 +507,133 @@ mod tests {
         let mut command = Command::new("cli-test", "");
         command.add_arg(Arg::new("--version").short_id('v').interrupt());
         command.add_arg(Arg::new("script"));
-        assert_eq!(command.match_with(vec![]).unwrap_err(), "Error: Required argument 'script' was not found.");
+        assert_eq!(command.match_with(vec![]).unwrap_err().message(), "Error: Required argument 'script' was not found.");
+    }
+
+    #[test]
+    fn command_inspect_args() {
+        let mut command = Command::new("cli-test", "");
+        command.add_arg(Arg::new("--help").short_id('h').interrupt());
+        command.add_arg(Arg::new("--output").parameterize(Required));
+        command.add_arg(Arg::new("script").parameterize(Optional("-")));
+
+        let cloned = command.clone();
+        let help = cloned.arg("--help").unwrap();
+        assert_eq!(help.get_id(), "--help");
+        assert_eq!(help.get_short_id(), Some('h'));
+        assert_eq!(help.get_parameter(), No);
+        assert!(help.is_interrupt());
+        assert_eq!(cloned.arg("-h").unwrap().get_id(), "--help");
+
+        let output = cloned.arg("--output").unwrap();
+        assert_eq!(output.get_short_id(), None);
+        assert_eq!(output.get_parameter(), Required);
+        assert!(!output.is_interrupt());
+
+        assert_eq!(cloned.positional_args().len(), 1);
+        let script = cloned.arg("script").unwrap();
+        assert_eq!(script.get_parameter(), Optional("-"));
+        assert!(script.is_optional());
+        assert!(cloned.arg("--missing").is_none());
+    }
+
+    #[test]
+    fn command_invalid_choice() {
+        use crate::error::ErrorKind;
+
+        let mut command = Command::new("cli-test", "");
+        command.add_arg(Arg::new("--target").parameterize(Optional("ast")).choices(&["ast"]));
+        command.add_arg(Arg::new("script"));
+        let args: Vec<String> = vec!["--target".into(), "json".into(), "a.ths".into()];
+        let err = command.match_with(args.clone()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandFailed);
+        assert_eq!(&command.command_line(&args).text[err.span().clone()], "json");
+        assert!(err.report.is_some());
+    }
+
+    #[test]
+    fn command_separated_choices() {
+        let mut command = Command::new("cli-test", "");
+        command.add_arg(Arg::new("--target").parameterize(Optional("ast")).choices(&["ast", "json"]).separated_by(','));
+        command.add_arg(Arg::new("script"));
+        let args: Vec<String> = vec!["--target".into(), "ast,json".into(), "a.ths".into()];
+        let map = command.match_with(args).unwrap();
+        let target = command.arg("--target").unwrap();
+        assert_eq!(target.split_parameter(&map["target"]), vec!["ast", "json"]);
+        let args: Vec<String> = vec!["--target".into(), "ast,xml".into(), "a.ths".into()];
+        assert!(command.match_with(args).unwrap_err().message().contains("'xml'"));
+    }
+
+    #[test]
+    fn command_owned_ids() {
+        let names: Vec<String> = ["--profile", "--jobs", "input"].iter().map(|name| name.to_string()).collect();
+        let mut command = Command::new("cli-test", "");
+        command.add_arg(Arg::owned(names[0].clone()).short_id('p'));
+        command.add_arg(Arg::owned(names[1].clone()).parameterize(Optional("1")));
+        command.add_arg(Arg::owned(names[2].clone()));
+        assert_eq!(command.arg("-p").unwrap().get_id(), "--profile");
+        let args: Vec<String> = vec!["-p".into(), "--jobs".into(), "4".into(), "a.ths".into()];
+        let map = command.match_with(args).unwrap();
+        assert_eq!(map["profile"], "");
+        assert_eq!(map["jobs"], "4");
+        assert_eq!(map["input"], "a.ths");
+    }
+
+    #[test]
+    fn command_computed_default() {
+        let mut command = Command::new("cli-test", "");
+        command.add_arg(Arg::new("--output").parameterize(Computed(|matches| {
+            std::path::Path::new(&matches["script"]).with_extension("ast").display().to_string()
+        })));
+        command.add_arg(Arg::new("script"));
+        let map = command.match_with(vec!["src/a.ths".into()]).unwrap();
+        assert_eq!(map["output"], "src/a.ast");
+        let args: Vec<String> = vec!["--output".into(), "out.json".into(), "src/a.ths".into()];
+        let map = command.match_with(args).unwrap();
+        assert_eq!(map["output"], "out.json");
+        assert!(command.arg("--output").unwrap().help().contains("[default: computed]"));
+    }
+
+    #[test]
+    fn command_repeatable() {
+        let mut command = Command::new("cli-test", "");
+        command.add_arg(Arg::new("--include").short_id('I').parameterize(Required).separated_by(':').repeatable());
+        command.add_arg(Arg::new("script"));
+        let args: Vec<String> = vec!["-I".into(), "lib".into(), "a.ths".into(), "--include".into(), "vendor:std".into()];
+        let map = command.match_with(args).unwrap();
+        let include = command.arg("-I").unwrap();
+        assert!(include.is_repeatable());
+        assert_eq!(include.split_parameter(&map["include"]), vec!["lib", "vendor", "std"]);
+        assert_eq!(map["script"], "a.ths");
+    }
+
+    #[test]
+    fn command_match_order() {
+        let mut command = Command::new("cli-test", "");
+        command.add_arg(Arg::new("--output").short_id('o').parameterize(Required));
+        command.add_arg(Arg::new("--target").parameterize(Optional("ast")));
+        command.add_arg(Arg::new("--time"));
+        command.add_arg(Arg::new("--width").parameterize(Computed(|_| "80".to_string())));
+        command.add_arg(Arg::new("script").parameterize(Optional("-")));
+        let args: Vec<String> = ["--time", "a.ths", "--target", "json", "-o", "out.json"]
+            .iter().map(|arg| arg.to_string()).collect();
+        let map = command.match_with(args).unwrap();
+        // The parameters given come in the order of the args, and the defaults after them.
+        let keys: Vec<&str> = map.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["time", "script", "target", "output", "width"]);
+        let pairs: Vec<(&String, &String)> = (&map).into_iter().collect();
+        assert_eq!(pairs[1], (&"script".to_string(), &"a.ths".to_string()));
+        assert_eq!((map.len(), map["target"].as_str()), (5, "json"));
+
+        let map = command.match_with(vec!["-o".into(), "x".into()]).unwrap();
+        let keys: Vec<&str> = map.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["output", "script", "width"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot interrupt")]
+    fn command_positional_interrupt() {
+        let mut command = Command::new("test", "");
+        command.add_arg(Arg::new("script").interrupt());
     }
 }
@@ -1,46 +1,124 @@
+use std::io::Write;
 use std::process::exit;
 
-use ariadne::{Fmt, Label, Report, ReportBuilder, ReportKind, Source};
+use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
 
 use crate::seq;
+use crate::evaluation::Term;
 use crate::parser::{SourcePos, SrcInfo};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorKind {
     InvalidSyntax,
     FreeIdentifier,
-    TypeMismatch
+    TypeMismatch,
+    ExpansionLimitExceeded,
+    DivisionByZero,
+    ArityMismatch,
+    StepLimitExceeded,
+    CommandFailed,
+    IoError,
+    EncodingError,
+    /// An object raised by the program, like the error objects made by `error`.
+    Raised,
+    /// An argument of the right type, but which can't be used, like a closed port.
+    InvalidArgument,
+    /// The application of a value which isn't a procedure, like `(5 1 2)`.
+    NotCallable,
+    /// An exact integer result out of the range of exact integers, like `(* 4611686018427387904 2)`.
+    IntegerOverflow,
+    /// Reductions nested deeper than the limit, like a recursion which never ends outside tail position.
+    StackOverflow,
+    /// A binding hiding an outer one of the same name, which is only reported as a warning.
+    Shadowing
 }
 
 impl ErrorKind {
-    pub fn to_error_code(&self) -> &str {
-        match &self {
+    pub fn to_error_code(self) -> &'static str {
+        match self {
             Self::InvalidSyntax => "E01",
             Self::FreeIdentifier => "E02",
-            Self::TypeMismatch => "E03"
+            Self::TypeMismatch => "E03",
+            Self::ExpansionLimitExceeded => "E04",
+            Self::DivisionByZero => "E05",
+            Self::ArityMismatch => "E06",
+            Self::StepLimitExceeded => "E07",
+            Self::CommandFailed => "E08",
+            Self::IoError => "E09",
+            Self::EncodingError => "E10",
+            Self::Raised => "E11",
+            Self::InvalidArgument => "E12",
+            Self::NotCallable => "E13",
+            Self::IntegerOverflow => "E14",
+            Self::StackOverflow => "E15",
+            Self::Shadowing => "W01"
         }
     }
 }
 
+/// Whether a diagnostic stops the evaluation, or is only reported alongside it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning
+}
+
+pub type ReportSpan = (String, std::ops::Range<usize>);
+
+/// How errors are rendered, so that embedders can apply their own diagnostic scheme.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportStyle {
+    /// Maps a kind of error to the code shown in its header, like `E01`.
+    pub code: fn(ErrorKind) -> String,
+    pub header_color: Color,
+    pub label_color: Color,
+    /// The color of both the header and the labels of warnings.
+    pub warning_color: Color
+}
+
+impl Default for ReportStyle {
+    fn default() -> Self {
+        Self { code: |kind| kind.to_error_code().to_string(), header_color: Color::Red, label_color: Color::Red, warning_color: Color::Yellow }
+    }
+}
+
+/// Where an error is reported, kept until the report is rendered with a style.
+#[derive(Debug, Clone)]
+pub struct PendingReport {
+    src_id: String,
+    offset: usize,
+    label: String
+}
+
 #[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
+    severity: Severity,
     message: String,
     span: std::ops::Range<usize>,
-    labels: Vec<Label<(String, std::ops::Range<usize>)>>,
-    pub(crate) report: Option<ReportBuilder<'static, (String, std::ops::Range<usize>)>>
+    labels: Vec<Label<ReportSpan>>,
+    pub(crate) report: Option<Box<PendingReport>>,
+    /// The object raised by the program, which is passed to its exception handlers.
+    payload: Option<Box<Term>>
 }
 
 impl Error {
     pub fn new(kind: ErrorKind) -> Self {
-        Self { kind, message: "".to_string(), span: 0..0, labels: vec![], report: None }
+        Self { kind, severity: Severity::Error, message: "".to_string(), span: 0..0, labels: vec![], report: None, payload: None }
     }
 
     pub fn kind(&self) -> ErrorKind { self.kind }
 
+    pub fn severity(&self) -> Severity { self.severity }
+
     pub fn message(&self) -> &String { &self.message }
 
-    pub fn with_label(mut self, label: Label<(String, std::ops::Range<usize>)>) -> Self {
+    pub fn span(&self) -> &std::ops::Range<usize> { &self.span }
+
+    pub fn payload(&self) -> Option<&Term> { self.payload.as_deref() }
+
+    pub fn with_label(mut self, label: Label<ReportSpan>) -> Self {
         seq!(self.labels.push(label), self)
     }
 
@@ -52,54 +130,58 @@ impl Error {
         seq!(self.span = span, self)
     }
 
-    pub fn return_error(mut self, src: &SrcInfo, pos: SourcePos, label: String) -> Self {
-        // let kind = format!("{:?}", self.kind);
-        // To make it appear like rust-style error.
-        print!("{}", "error".fg(ariadne::Color::Red));
-
-        let mut builder = 
-        Report::build(ReportKind::Custom("\x08", ariadne::Color::Red), &src.id, pos.i())
-            .with_code(self.kind.to_error_code())
-            .with_message(self.message())
-            .with_label(
-                Label::new((src.id.clone(), self.span.clone()))
-                    .with_message(label)
-                    .with_color(ariadne::Color::Red)
-            );
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        seq!(self.severity = severity, self)
+    }
 
-        for label in &self.labels {
-            builder = builder.with_label(label.clone());
-        }
+    pub fn with_payload(mut self, payload: Term) -> Self {
+        seq!(self.payload = Some(Box::new(payload)), self)
+    }
 
-        self.report = Some(builder);
+    pub fn return_error(mut self, src: &SrcInfo, pos: SourcePos, label: String) -> Self {
+        self.report = Some(Box::new(PendingReport { src_id: src.id.clone(), offset: pos.i(), label }));
         self
     }
 
     pub fn report_error(self, src: &SrcInfo, pos: SourcePos, label: String) -> ! {
-        // let kind = format!("{:?}", self.kind);
+        self.return_error(src, pos, label).print_report(src, &ReportStyle::default());
+        exit(1)
+    }
+
+    /// Render the report of the error to the writer, falling back to the start of the source
+    /// if the error wasn't given a position.
+    pub fn write_report<W: Write>(&self, src: &SrcInfo, style: &ReportStyle, mut writer: W) -> std::io::Result<()> {
+        let fallback = PendingReport { src_id: src.id.clone(), offset: 0, label: "".to_string() };
+        let pending = self.report.as_deref().unwrap_or(&fallback);
+        let (header, header_color, label_color) = match self.severity {
+            Severity::Error => ("error", style.header_color, style.label_color),
+            Severity::Warning => ("warning", style.warning_color, style.warning_color)
+        };
         // To make it appear like rust-style error.
-        print!("{}", "error".fg(ariadne::Color::Red));
+        write!(writer, "{}", header.fg(header_color))?;
 
-        let mut builder = 
-        Report::build(ReportKind::Custom("\x08", ariadne::Color::Red), &src.id, pos.i())
-            .with_code(self.kind.to_error_code())
+        let mut builder =
+        Report::build(ReportKind::Custom("\x08", header_color), &pending.src_id, pending.offset)
+            .with_code((style.code)(self.kind))
             .with_message(self.message())
             .with_label(
-                Label::new((src.id.clone(), self.span.clone()))
-                    .with_message(label)
-                    .with_color(ariadne::Color::Red)
+                Label::new((pending.src_id.clone(), self.span.clone()))
+                    .with_message(&pending.label)
+                    .with_color(label_color)
             );
 
-        for label in self.labels {
-            builder = builder.with_label(label);
+        for label in &self.labels {
+            builder = builder.with_label(label.clone());
         }
 
         builder
             .finish()
-            .print((src.id.clone(), Source::from(&src.text)))
-            .unwrap();
-        
-        exit(1)
+            .write((src.id.clone(), Source::from(&src.text)), writer)
+    }
+
+    pub fn print_report(&self, src: &SrcInfo, style: &ReportStyle) {
+        // There is nowhere left to report a failure to write the report itself.
+        let _ = self.write_report(src, style, std::io::stdout());
     }
 }
 
@@ -113,17 +195,43 @@ impl core::error::Error for Error {}
 
 impl<S: Into<String>> From<(ErrorKind, S)> for Error {
     fn from(value: (ErrorKind, S)) -> Self {
-        Self { kind: value.0, message: value.1.into(), span: 0..0, labels: vec![], report: None }
+        Self { kind: value.0, severity: Severity::Error, message: value.1.into(), span: 0..0, labels: vec![], report: None, payload: None }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Error;
+    use crate::parser::SrcInfo;
+    use super::{Error, ErrorKind, ReportStyle};
 
     #[test]
     fn error_to_string() {
         use super::ErrorKind::*;
         assert_eq!(Error::new(InvalidSyntax).to_string(), "InvalidSyntax: ");
     }
+
+    fn render(err: &Error, src: &SrcInfo, style: &ReportStyle) -> String {
+        let mut buf = vec![];
+        err.write_report(src, style, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn error_report_style() {
+        let src = SrcInfo::new("test-style", "(+ 1 #t)");
+        let err = Error::new(ErrorKind::TypeMismatch)
+            .with_message("Expected a number.".to_string())
+            .with_span(5..7)
+            .return_error(&src, (1, 6, 5).into(), "Not a number.".to_string());
+        let default = render(&err, &src, &ReportStyle::default());
+        assert!(default.contains("[E03]"));
+        let style = ReportStyle {
+            code: |kind| format!("THS-{kind:?}"),
+            ..ReportStyle::default()
+        };
+        let custom = render(&err, &src, &style);
+        assert!(custom.contains("[THS-TypeMismatch]"));
+        assert!(!custom.contains("E03"));
+        assert!(custom.contains("Expected a number."));
+    }
 }
@@ -1,14 +1,250 @@
+use std::cell::RefCell;
+use std::collections::LinkedList;
 use std::fmt::Debug;
+use std::rc::Rc;
 
-use crate::error::Error;
-use super::term::Term;
-use super::context::Context;
+use crate::error::{Error, ErrorKind};
+use crate::if_or;
+use crate::syntax::Symbol;
+use super::term::{Term, TermAccess, TermValue};
+use super::context::{Context, Env};
+use super::prelude::Arity;
 
-pub trait Combiner {}
+pub type NativeFnPtr = fn(&mut Context, &[Term]) -> Result<Term, Error>;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NativeFn {
-    func: Box<fn(Term, Context) -> Result<Term, Error>>
+    func: Box<NativeFnPtr>,
+    /// The name and the arity of a builtin, by which its arguments are counted before each call.
+    arity: Option<(&'static str, Arity)>
+}
+
+impl NativeFn {
+    pub fn new(func: NativeFnPtr) -> Self {
+        Self { func: Box::new(func), arity: None }
+    }
+
+    pub fn with_arity(mut self, name: &'static str, arity: Arity) -> Self {
+        self.arity = Some((name, arity));
+        self
+    }
+
+    /// Check the number of arguments against the arity of the builtin, if it was given one.
+    pub fn check_arity(&self, args: &[Term]) -> Result<(), Error> {
+        self.arity.map_or(Ok(()), |(name, arity)| arity.check(name, args))
+    }
+
+    pub fn call(&self, ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+        (self.func)(ctx, args)
+    }
+
+    /// The address of the function, which is what tells builtins apart.
+    pub fn id(&self) -> usize {
+        *self.func as usize
+    }
+}
+
+/// A procedure made by `$lambda`, closing over the environment it was made in.
+#[derive(Clone)]
+pub struct Lambda(Rc<LambdaData>);
+
+struct LambdaData {
+    /// The required parameters.
+    formals: Vec<Symbol>,
+    /// The parameter bound to the list of remaining arguments, if any.
+    rest: Option<Symbol>,
+    body: Vec<Term>,
+    env: Rc<RefCell<Env>>
+}
+
+impl Lambda {
+    /// Make a procedure from formals like `(a b)`, `(a b . rest)` or `args`.
+    pub fn new(formals: &Term, body: Vec<Term>, env: Rc<RefCell<Env>>) -> Result<Self, Error> {
+        if body.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidSyntax)
+                .with_message("'$lambda' requires a body.".to_string()))
+        }
+        let symbol_of = |term: &Term| match (term as &dyn TermAccess<Symbol>).try_access() {
+            // Only a symbol yet to be resolved is a reference, rather than a value.
+            Ok(symbol) if !term.has_value() => Ok(symbol.clone()),
+            _ => Err(Error::new(ErrorKind::InvalidSyntax)
+                .with_message("The formals of '$lambda' must be symbols.".to_string()))
+        };
+        let (formals, rest) = match formals.value {
+            _ if formals.is_branch() => {
+                let mut symbols = formals.sub_terms.iter().map(symbol_of).collect::<Result<Vec<_>, _>>()?;
+                match symbols.iter().position(|symbol| symbol.as_ref() == ".") {
+                    Some(dot) if dot + 2 == symbols.len() => {
+                        let rest = symbols.pop();
+                        symbols.pop();
+                        (symbols, rest)
+                    }
+                    Some(_) => return Err(Error::new(ErrorKind::InvalidSyntax)
+                        .with_message("'.' must precede the last formal of '$lambda'.".to_string())),
+                    None => (symbols, None)
+                }
+            }
+            TermValue::Nil => (vec![], None),
+            _ => (vec![], Some(symbol_of(formals)?))
+        };
+        Ok(Self(Rc::new(LambdaData { formals, rest, body, env })))
+    }
+
+    pub fn body(&self) -> &[Term] {
+        &self.0.body
+    }
+
+    /// Whether the procedure takes the number of arguments.
+    pub fn accepts(&self, count: usize) -> bool {
+        match self.0.rest {
+            Some(_) => count >= self.0.formals.len(),
+            None => count == self.0.formals.len()
+        }
+    }
+
+    /// The number of arguments the procedure takes, like `2` or `at least 1`.
+    pub fn arity(&self) -> String {
+        format!("{}{}", if_or!(self.0.rest.is_some(), "at least ", ""), self.0.formals.len())
+    }
+
+    /// A new environment binding the formals to the arguments, whose parent is the closure's.
+    pub fn bind(&self, args: LinkedList<Term>) -> Result<Rc<RefCell<Env>>, Error> {
+        let data = &self.0;
+        if !self.accepts(args.len()) {
+            return Err(Error::new(ErrorKind::ArityMismatch)
+                .with_message(format!("{self:?} expects {} argument(s), but got {}.", self.arity(), args.len())))
+        }
+        let mut env = Env::with_parent(data.env.clone());
+        let mut args = args.into_iter();
+        for formal in &data.formals {
+            env.insert(formal.as_ref(), args.next().unwrap());
+        }
+        if let Some(ref rest) = data.rest {
+            let rest_args = args.rev().fold(Term::nil(), |tail, head| Term::cons(head, tail));
+            env.insert(rest.as_ref(), rest_args);
+        }
+        Ok(Rc::new(RefCell::new(env)))
+    }
+}
+
+impl Lambda {
+    /// The address of the procedure, shared by its aliases and by no other live procedure.
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl PartialEq for Lambda {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Debug for Lambda {
+    // The environment is left out, as it may well contain the procedure itself.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<lambda (")?;
+        let formals: Vec<&str> = self.0.formals.iter().map(|symbol| symbol.as_ref().as_str()).collect();
+        write!(f, "{}", formals.join(" "))?;
+        match self.0.rest {
+            Some(ref rest) if formals.is_empty() => write!(f, ". {rest})>"),
+            Some(ref rest) => write!(f, " . {rest})>"),
+            None => write!(f, ")>")
+        }
+    }
+}
+
+/// A procedure made by `$case-lambda`, which is applied as the first of its clauses that takes
+/// the number of arguments.
+#[derive(Clone)]
+pub struct CaseLambda(Rc<Vec<Lambda>>);
+
+impl CaseLambda {
+    pub fn new(clauses: Vec<Lambda>) -> Self {
+        Self(Rc::new(clauses))
+    }
+
+    /// The first clause which takes the number of arguments.
+    pub fn select(&self, count: usize) -> Result<Lambda, Error> {
+        match self.0.iter().find(|clause| clause.accepts(count)) {
+            Some(clause) => Ok(clause.clone()),
+            None if self.0.is_empty() => Err(Error::new(ErrorKind::ArityMismatch)
+                .with_message(format!("{self:?} has no clauses, so it takes no arguments, but got {count}."))),
+            None => {
+                let arities: Vec<String> = self.0.iter().map(Lambda::arity).collect();
+                Err(Error::new(ErrorKind::ArityMismatch)
+                    .with_message(format!("{self:?} expects {} argument(s), but got {count}.", arities.join(" or "))))
+            }
+        }
+    }
+
+    /// The address of the procedure, shared by its aliases and by no other live procedure.
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl PartialEq for CaseLambda {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Debug for CaseLambda {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<case-lambda")?;
+        for clause in self.0.iter() {
+            // The formals of each clause, without the `#<lambda` and `>` around them.
+            let clause = format!("{clause:?}");
+            write!(f, " {}", &clause["#<lambda ".len()..clause.len() - 1])?;
+        }
+        write!(f, ">")
+    }
+}
+
+/// A procedure made by `make-parameter`, which returns the value it's currently bound to.
+#[derive(Clone)]
+pub struct Parameter(Rc<ParameterData>);
+
+struct ParameterData {
+    value: RefCell<Term>,
+    /// The procedure applied to every value before it's bound, if any.
+    converter: Option<Term>
+}
+
+impl Parameter {
+    /// A parameter bound to the value, which is expected to be converted already.
+    pub fn new(value: Term, converter: Option<Term>) -> Self {
+        Self(Rc::new(ParameterData { value: RefCell::new(value), converter }))
+    }
+
+    pub fn value(&self) -> Term {
+        self.0.value.borrow().clone()
+    }
+
+    pub fn converter(&self) -> Option<&Term> {
+        self.0.converter.as_ref()
+    }
+
+    /// Bind the parameter to a new value, returning the one it was bound to.
+    pub fn replace(&self, value: Term) -> Term {
+        self.0.value.replace(value)
+    }
+
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl PartialEq for Parameter {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Debug for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<parameter>")
+    }
 }
 
-impl Combiner for NativeFn {}
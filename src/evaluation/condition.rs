@@ -0,0 +1,145 @@
+use std::collections::LinkedList;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::error::{Error, ErrorKind};
+use crate::syntax::Symbol;
+use super::term::{Term, TermValue, UnitValue};
+
+/// A type of condition made by `define-condition-type`, whose fields follow those of its parent.
+#[derive(Clone)]
+pub struct ConditionType(Rc<ConditionTypeData>);
+
+struct ConditionTypeData {
+    name: Symbol,
+    parent: Option<ConditionType>,
+    /// The fields of the parent, followed by the fields of the type itself.
+    fields: Vec<Symbol>
+}
+
+impl ConditionType {
+    pub fn new(name: Symbol, parent: Option<ConditionType>, own_fields: Vec<Symbol>) -> Self {
+        let mut fields = parent.as_ref().map_or_else(Vec::new, |parent| parent.0.fields.clone());
+        fields.extend(own_fields);
+        Self(Rc::new(ConditionTypeData { name, parent, fields }))
+    }
+
+    /// The root of the condition types, with a message and irritants, which the error objects
+    /// made by `error` and the interpreter itself are instances of.
+    pub fn error_object() -> Self {
+        thread_local! {
+            static ERROR_OBJECT: ConditionType = ConditionType::new(
+                "error-object".into(), None, vec!["message".into(), "irritants".into()]);
+        }
+        ERROR_OBJECT.with(ConditionType::clone)
+    }
+
+    pub fn name(&self) -> &Symbol {
+        &self.0.name
+    }
+
+    pub fn fields(&self) -> &[Symbol] {
+        &self.0.fields
+    }
+
+    pub fn field_index(&self, field: &str) -> Option<usize> {
+        self.0.fields.iter().position(|symbol| symbol.as_ref() == field)
+    }
+
+    /// Whether the type is this one, or inherits from it.
+    pub fn is_subtype_of(&self, other: &ConditionType) -> bool {
+        let mut current = Some(self);
+        while let Some(condition_type) = current {
+            if condition_type == other { return true }
+            current = condition_type.0.parent.as_ref();
+        }
+        false
+    }
+
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    /// The type of the value, if it's a condition.
+    pub fn of(value: &TermValue) -> Option<ConditionType> {
+        match value {
+            TermValue::Condition(condition) => Some(condition.condition_type.clone()),
+            TermValue::ErrorObject(_) => Some(ConditionType::error_object()),
+            _ => None
+        }
+    }
+}
+
+impl PartialEq for ConditionType {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Debug for ConditionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<condition-type {}>", self.0.name)
+    }
+}
+
+/// A procedure generated by `define-condition-type` for one of its types.
+#[derive(Clone, PartialEq)]
+pub struct ConditionProcedure {
+    name: Symbol,
+    condition_type: ConditionType,
+    kind: ConditionProcedureKind
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionProcedureKind {
+    /// Make a condition from its arguments, given the index of the field each one is for.
+    Constructor(Vec<usize>),
+    Predicate,
+    Accessor(usize)
+}
+
+impl ConditionProcedure {
+    pub fn new(name: Symbol, condition_type: ConditionType, kind: ConditionProcedureKind) -> Self {
+        Self { name, condition_type, kind }
+    }
+
+    /// The fields which aren't given to the constructor are left unspecified.
+    pub fn call(&self, args: LinkedList<Term>) -> Result<Term, Error> {
+        let arity = match self.kind {
+            ConditionProcedureKind::Constructor(ref indices) => indices.len(),
+            _ => 1
+        };
+        if args.len() != arity {
+            return Err(Error::new(ErrorKind::ArityMismatch)
+                .with_message(format!("'{}' expects {arity} argument(s), but got {}.", self.name, args.len())))
+        }
+        match self.kind {
+            ConditionProcedureKind::Constructor(ref indices) => {
+                let mut fields = vec![Term::from(UnitValue::Ignore); self.condition_type.fields().len()];
+                for (&index, arg) in indices.iter().zip(args) {
+                    fields[index] = arg;
+                }
+                Ok(Term::condition(self.condition_type.clone(), fields))
+            }
+            ConditionProcedureKind::Predicate => {
+                let arg = args.front().unwrap();
+                Ok(Term::boolean(ConditionType::of(&arg.value)
+                    .is_some_and(|condition_type| condition_type.is_subtype_of(&self.condition_type))))
+            }
+            ConditionProcedureKind::Accessor(index) => match args.front().unwrap().value {
+                TermValue::Condition(ref condition)
+                    if condition.condition_type.is_subtype_of(&self.condition_type) => Ok(condition.fields[index].clone()),
+                ref value => Err(Error::new(ErrorKind::TypeMismatch)
+                    .with_message(format!("'{}' expects a condition of the type {}, but got the {} {value}.",
+                        self.name, self.condition_type.name(), value.type_name())))
+            }
+        }
+    }
+}
+
+impl Debug for ConditionProcedure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<procedure {}>", self.name)
+    }
+}
+
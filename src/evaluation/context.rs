@@ -1,81 +1,1173 @@
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, LinkedList};
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use crate::error::{Error, ErrorKind};
-use crate::parser::SrcInfo;
-use crate::syntax::Symbol;
+use crate::parser::{SrcInfo, SyntacticParser};
+use crate::syntax::{Node, Symbol, SymbolCase};
+use super::combiner::{CaseLambda, Lambda, Parameter};
+use super::expander::Macro;
+use super::lint::shadowing_warnings;
+use super::port::Port;
+use super::prelude::install_prelude;
 use super::term::{Term, *};
 
-#[derive(Debug)]
+pub const DEFAULT_MAX_MACRO_EXPANSION_DEPTH: usize = 1024;
+/// How deep the reductions may nest, which is shallow enough for the stack of the main thread.
+pub const DEFAULT_MAX_REDUCTION_DEPTH: usize = 1000;
+
+/// A shared destination for the output of evaluation.
+#[derive(Clone)]
+pub struct Sink(Rc<RefCell<dyn Write>>);
+
+impl Sink {
+    pub fn new<W: Write + 'static>(writer: W) -> Self {
+        Self(Rc::new(RefCell::new(writer)))
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+
+    pub fn write_str(&self, s: &str) -> std::io::Result<()> {
+        let mut writer = self.0.borrow_mut();
+        writer.write_all(s.as_bytes())?;
+        writer.flush()
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+impl std::fmt::Debug for Sink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sink")
+    }
+}
+
+/// A shared origin of the input read during evaluation.
+#[derive(Clone)]
+pub struct Source(Rc<RefCell<dyn BufRead>>);
+
+impl Source {
+    pub fn new<R: BufRead + 'static>(reader: R) -> Self {
+        Self(Rc::new(RefCell::new(reader)))
+    }
+
+    pub fn stdin() -> Self {
+        // Stdin is only locked while reading, as every context holds a source of its own.
+        Self::new(std::io::BufReader::new(std::io::stdin()))
+    }
+
+    /// Read a line without its line ending, or `None` at the end of the input.
+    pub fn read_line(&self) -> std::io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.0.borrow_mut().read_line(&mut line)? == 0 { return Ok(None) }
+        if line.ends_with('\n') { line.pop(); }
+        if line.ends_with('\r') { line.pop(); }
+        Ok(Some(line))
+    }
+
+    /// Read a byte, or `None` at the end of the input.
+    pub fn read_u8(&self) -> std::io::Result<Option<u8>> {
+        let byte = self.peek_u8()?;
+        if byte.is_some() { self.0.borrow_mut().consume(1) }
+        Ok(byte)
+    }
+
+    /// The next byte, without reading it, or `None` at the end of the input.
+    pub fn peek_u8(&self) -> std::io::Result<Option<u8>> {
+        Ok(self.0.borrow_mut().fill_buf()?.first().copied())
+    }
+
+    /// Read up to the count of bytes, which are fewer only at the end of the input.
+    pub fn read_bytes(&self, count: usize) -> std::io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        (&mut *self.0.borrow_mut()).take(count as u64).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl std::fmt::Debug for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Source")
+    }
+}
+
+/// An entry of the stack of exception handlers, innermost last.
+#[derive(Debug, Clone)]
+pub(crate) enum Handler {
+    /// A handler installed by `with-exception-handler`.
+    Procedure(Term),
+    /// The body of a `guard`, which catches whatever is raised in it before any outer handler.
+    Guard
+}
+
+/// What the last term of a lambda body reduces to, where the application of another lambda
+/// is left to the caller, so that tail calls don't grow the stack.
+enum Tail {
+    Value(Term),
+    Call(Lambda, LinkedList<Term>)
+}
+
+/// The name of the symbol in the front of a combination, or `""` if the front is anything else.
+fn operator_name(term: &Term) -> &str {
+    match term.sub_terms.front().map(|front| (front as &dyn TermAccess<Symbol>).try_access()) {
+        Some(Ok(symbol)) => symbol.as_ref(),
+        _ => ""
+    }
+}
+
+/// The prelude, with the parameters of the current ports.
+fn initial_env(current_input: &Parameter, current_output: &Parameter) -> Env {
+    let mut env = Env::new();
+    install_prelude(&mut env);
+    env.insert(&"current-input-port".to_string(), Term::from(current_input.clone()));
+    env.insert(&"current-output-port".to_string(), Term::from(current_output.clone()));
+    env
+}
+
+#[derive(Debug, Clone)]
 pub struct Context {
-    pub(crate) env: Env,
-    src: Rc<RefCell<SrcInfo>>
+    pub(crate) env: Rc<RefCell<Env>>,
+    src: Rc<RefCell<SrcInfo>>,
+    pub(crate) input: Source,
+    pub(crate) output: Sink,
+    /// The parameters bound to `current-input-port` and `current-output-port`, whose ports are
+    /// read and written by default.
+    pub(crate) current_input: Parameter,
+    pub(crate) current_output: Parameter,
+    /// The names of the combiners whose applications are written to the output.
+    traced: HashSet<String>,
+    max_macro_expansion_depth: usize,
+    /// The maximum number of reduction steps of an evaluation, where 0 means unlimited.
+    step_limit: usize,
+    steps: usize,
+    max_reduction_depth: usize,
+    /// The number of reductions in progress, each inside the one before.
+    depth: usize,
+    /// Whether the sources evaluated are checked for bindings shadowing outer ones.
+    warn_shadowing: bool,
+    /// The warnings reported so far, shared by the contexts cloned from this one.
+    warnings: Rc<RefCell<Vec<Error>>>,
+    /// The exception handlers of the current dynamic extent, shared like the warnings.
+    pub(crate) handlers: Rc<RefCell<Vec<Handler>>>,
+    /// The directories searched in order for a file loaded by a relative path.
+    include_paths: Rc<Vec<PathBuf>>,
+    /// How the symbols of the sources read are normalized.
+    symbol_case: SymbolCase
 }
 
 impl Context {
     pub fn new(src: Rc<RefCell<SrcInfo>>) -> Self {
-        Self { env: Env::new(), src }
+        let (input, output) = (Source::stdin(), Sink::stdout());
+        let current_input = Parameter::new(Term::from(Port::input(input.clone())), None);
+        let current_output = Parameter::new(Term::from(Port::output(output.clone())), None);
+        Self {
+            env: Rc::new(RefCell::new(initial_env(&current_input, &current_output))),
+            src,
+            input,
+            output,
+            current_input,
+            current_output,
+            traced: HashSet::new(),
+            max_macro_expansion_depth: DEFAULT_MAX_MACRO_EXPANSION_DEPTH,
+            step_limit: 0,
+            steps: 0,
+            max_reduction_depth: DEFAULT_MAX_REDUCTION_DEPTH,
+            depth: 0,
+            warn_shadowing: false,
+            warnings: Rc::new(RefCell::new(vec![])),
+            handlers: Rc::new(RefCell::new(vec![])),
+            include_paths: Rc::new(vec![]),
+            symbol_case: SymbolCase::default()
+        }
+    }
+
+    /// A context of the same ports and settings, whose environment is back to its initial state.
+    pub fn reset(&self) -> Self {
+        Self {
+            env: Rc::new(RefCell::new(self.initial_env())),
+            traced: HashSet::new(),
+            steps: 0,
+            depth: 0,
+            warnings: Rc::new(RefCell::new(vec![])),
+            handlers: Rc::new(RefCell::new(vec![])),
+            ..self.clone()
+        }
+    }
+
+    /// The environment of the context before anything is bound in it.
+    pub fn initial_env(&self) -> Env {
+        initial_env(&self.current_input, &self.current_output)
+    }
+
+    pub fn with_output(mut self, output: Sink) -> Self {
+        self.current_output.replace(Term::from(Port::output(output.clone())));
+        self.output = output;
+        self
+    }
+
+    pub fn output(&self) -> &Sink {
+        &self.output
+    }
+
+    pub fn with_input(mut self, input: Source) -> Self {
+        self.current_input.replace(Term::from(Port::input(input.clone())));
+        self.input = input;
+        self
+    }
+
+    pub fn input(&self) -> &Source {
+        &self.input
+    }
+
+    pub fn with_shadowing_warnings(mut self, enabled: bool) -> Self {
+        self.warn_shadowing = enabled;
+        self
+    }
+
+    pub fn with_include_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.include_paths = Rc::new(paths);
+        self
+    }
+
+    pub fn with_symbol_case(mut self, symbol_case: SymbolCase) -> Self {
+        self.symbol_case = symbol_case;
+        self
+    }
+
+    pub fn symbol_case(&self) -> SymbolCase {
+        self.symbol_case
+    }
+
+    /// Find a file given by a relative path in the include paths, and then in the current
+    /// directory. An absolute path is taken as it is.
+    pub fn resolve_path(&self, path: &Path) -> Result<PathBuf, Error> {
+        if path.is_absolute() { return Ok(path.to_path_buf()) }
+        let candidates: Vec<PathBuf> = self.include_paths.iter()
+            .map(|dir| dir.join(path))
+            .chain(std::iter::once(path.to_path_buf()))
+            .collect();
+        candidates.iter().find(|candidate| candidate.is_file()).cloned().ok_or_else(|| {
+            let searched: Vec<String> = candidates.iter().map(|candidate| format!("'{}'", candidate.display())).collect();
+            Error::new(ErrorKind::IoError)
+                .with_message(format!("Failed to find '{}', searched {}.", path.display(), searched.join(", ")))
+        })
+    }
+
+    /// Report a diagnostic which doesn't stop the evaluation.
+    pub fn warn(&self, warning: Error) {
+        self.warnings.borrow_mut().push(warning)
+    }
+
+    /// Take the warnings reported since the last call.
+    pub fn take_warnings(&self) -> Vec<Error> {
+        self.warnings.take()
+    }
+
+    /// Run the enabled checks over a source about to be evaluated.
+    pub fn check_source(&self, src: &SrcInfo) {
+        if self.warn_shadowing {
+            shadowing_warnings(src).into_iter().for_each(|warning| self.warn(warning));
+        }
+    }
+
+    /// Write every application of the combiner bound to `name` to the output, with its result.
+    pub fn trace(&mut self, name: &str) {
+        self.traced.insert(name.to_string());
+    }
+
+    pub fn untrace(&mut self, name: &str) -> bool {
+        self.traced.remove(name)
+    }
+
+    pub fn with_step_limit(mut self, limit: usize) -> Self {
+        self.step_limit = limit;
+        self
+    }
+
+    pub fn with_max_macro_expansion_depth(mut self, depth: usize) -> Self {
+        self.max_macro_expansion_depth = depth;
+        self
+    }
+
+    pub fn with_max_reduction_depth(mut self, depth: usize) -> Self {
+        self.max_reduction_depth = depth;
+        self
+    }
+
+    /// Ensure a form is shallow enough to be handed to the macro expander.
+    pub fn check_expansion_depth(&self, node: &Node) -> Result<(), Error> {
+        let term_depth = node.depth();
+        if term_depth <= self.max_macro_expansion_depth {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::ExpansionLimitExceeded)
+                .with_message(format!(
+                    "The form is nested {term_depth} levels deep, exceeding the expansion limit of {}.",
+                    self.max_macro_expansion_depth)))
+        }
+    }
+
+    /// Expand every macro form in `node`, so that the evaluator never sees one.
+    /// The operator of each list is expanded repeatedly until it no longer names
+    /// a macro, and then its sub-forms are expanded in turn.
+    pub fn macroexpand_all(&self, node: Node) -> Result<Node, Error> {
+        self.check_expansion_depth(&node)?;
+        let mut node = node;
+        let mut expansions = 0usize;
+        while let Some(transformer) = self.macro_of(&node) {
+            expansions += 1;
+            if expansions > self.max_macro_expansion_depth {
+                return Err(Error::new(ErrorKind::ExpansionLimitExceeded)
+                    .with_message(format!(
+                        "The form was still a macro call after {} expansions.",
+                        self.max_macro_expansion_depth)))
+            }
+            node = transformer.expand(&node)?;
+            self.check_expansion_depth(&node)?;
+        }
+        match node {
+            Node::List(list, bracket) => Ok(Node::List(
+                list.into_iter()
+                    .map(|sub_node| self.macroexpand_all(sub_node))
+                    .collect::<Result<Vec<_>, _>>()?, bracket)),
+            node => Ok(node)
+        }
+    }
+
+    fn macro_of(&self, node: &Node) -> Option<Macro> {
+        match node {
+            Node::List(list, _) => match list.first() {
+                Some(Node::Symbol(name)) => self.env.borrow().lookup_macro(name.as_ref()),
+                _ => None
+            },
+            _ => None
+        }
     }
 
     // TODO: Add complete reduction implementation
-    pub fn eval(&mut self, mut term: Term) -> Result<(), Error> {
-        if !term.is_branch() {
-            self.reduce_leaf(&mut term)
+    pub fn eval(&mut self, term: Term) -> Result<(), Error> {
+        self.eval_to_value(term).map(drop)
+    }
+
+    /// Evaluate the term, returning the value it reduces to.
+    pub fn eval_to_value(&mut self, mut term: Term) -> Result<Term, Error> {
+        self.steps = 0;
+        self.reduce(&mut term)?;
+        Ok(term)
+    }
+
+    /// Evaluate the top-level forms of a file one at a time, reading and parsing each only when
+    /// the iterator gets to it. A relative path is resolved by `resolve_path`.
+    ///
+    /// An error in one form is yielded in its place, and the following forms are still
+    /// evaluated, except after an error which leaves the evaluation in doubt.
+    pub fn eval_file_incremental<'a>(&'a mut self, path: &Path) -> impl Iterator<Item = Result<Term, Error>> + 'a {
+        let file = self.resolve_path(path).and_then(|path| match std::fs::File::open(&path) {
+            Ok(file) => Ok((path, file)),
+            Err(err) => Err(Error::new(ErrorKind::IoError)
+                .with_message(format!("Failed to read '{}': {err}", path.display())))
+        });
+        let (src, mut parser, mut failure) = match file {
+            Ok((path, file)) => {
+                let src = Rc::new(RefCell::new(SrcInfo::new(path.display().to_string(), String::new())));
+                let parser = SyntacticParser::from_reader(src.clone(), std::io::BufReader::new(file))
+                    .with_symbol_case(self.symbol_case);
+                (Some(src), Some(parser), None)
+            }
+            Err(err) => (None, None, Some(err))
+        };
+        std::iter::from_fn(move || {
+            if let Some(err) = failure.take() { return Some(Err(err)) }
+            let Some(form) = parser.as_mut()?.parse_one() else {
+                // The bindings are only checked for shadowing once the whole file has been read.
+                parser = None;
+                if let Some(src) = src.as_ref() { self.check_source(&src.borrow()) }
+                return None
+            };
+            let result = form.and_then(|form| {
+                let term = self.macroexpand_all(form)?.into();
+                self.eval_to_value(term)
+            });
+            if let Err(ErrorKind::DivisionByZero | ErrorKind::StepLimitExceeded | ErrorKind::StackOverflow) =
+                result.as_ref().map_err(Error::kind) {
+                parser = None;
+            }
+            Some(result)
+        })
+    }
+
+    fn count_step(&mut self) -> Result<(), Error> {
+        self.steps += 1;
+        if self.step_limit != 0 && self.steps > self.step_limit {
+            return Err(Error::new(ErrorKind::StepLimitExceeded)
+                .with_message(format!("The evaluation exceeded the limit of {} steps.", self.step_limit)))
+        }
+        Ok(())
+    }
+
+    /// Reduce the term in place.
+    pub fn reduce(&mut self, term: &mut Term) -> Result<(), Error> {
+        self.nested(|ctx| if !term.is_branch() {
+            ctx.reduce_leaf(term)
         } else {
-            self.reduce_branch(&mut term)
+            ctx.reduce_branch(term)
+        })
+    }
+
+    /// Reduce a term which is left as it is, like the body of a procedure or a loop, to its
+    /// value. Only the special forms, which take their operands apart, are reduced from a copy
+    /// of the term, and the rest is looked up and applied without copying the term first.
+    fn reduce_ref(&mut self, term: &Term) -> Result<Term, Error> {
+        self.nested(|ctx| match operator_name(term) {
+            _ if !term.is_branch() => match (term as &dyn TermAccess<Symbol>).try_access() {
+                Ok(symbol) => {
+                    let mut bound = ctx.lookup_symbol(symbol)?;
+                    bound.set_has_value();
+                    Ok(bound)
+                }
+                Err(_) => Ok(term.clone())
+            },
+            "$if" => match ctx.select_if(term)? {
+                Some(branch) => ctx.reduce_ref(branch),
+                None => Ok(Term::from(UnitValue::Ignore))
+            },
+            name if matches!(name, "$define" | "$lambda" | "$case-lambda" | "$while" | "$do")
+                || ctx.traced.contains(name) => {
+                let mut copy = term.clone();
+                ctx.reduce_branch(&mut copy)?;
+                Ok(copy)
+            }
+            _ => {
+                let mut operands = ctx.reduce_operands(&term.sub_terms)?;
+                let operator = operands.pop_front().unwrap();
+                ctx.apply(operator, operands)
+            }
+        })
+    }
+
+    /// Count a reduction nested in the current one for the duration of `reduce`.
+    fn nested<T>(&mut self, reduce: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        self.count_step()?;
+        // The limit is reported before the stack of the thread itself overflows, which aborts.
+        if self.depth >= self.max_reduction_depth {
+            return Err(Error::new(ErrorKind::StackOverflow)
+                .with_message(format!("The evaluation nested deeper than the limit of {} reductions.",
+                    self.max_reduction_depth)))
         }
+        self.depth += 1;
+        let result = reduce(self);
+        self.depth -= 1;
+        result
     }
 
     pub fn reduce_leaf(&mut self, term: &mut Term) -> Result<(), Error> {
-        let name: String;
-        match (term as &mut dyn TermAccess<Symbol>).try_access() {
-            Ok(symbol) => name = symbol.to_string(),
+        let value = match (term as &dyn TermAccess<Symbol>).try_access() {
+            Ok(symbol) => self.lookup_symbol(symbol)?.value,
             Err(_) => return Ok(()),
-        }
-        match self.env.lookup(&name) {
-            Some(ref mut term_ref) => {
-                // TODO
-                term.value_ref = term_ref.value_ref.clone();
-                Ok(())
-            },
+        };
+        term.value = value;
+        term.set_has_value();
+        Ok(())
+    }
+
+    /// The term bound to the symbol, which is looked up by its normalized name, but reported
+    /// as it was spelled.
+    fn lookup_symbol(&self, symbol: &Symbol) -> Result<Term, Error> {
+        match self.env.borrow().lookup(symbol.as_ref()) {
+            Some(bound) if matches!(bound.value, TermValue::Unit(UnitValue::Unassigned)) =>
+                Err(Error::new(ErrorKind::FreeIdentifier)
+                    .with_message(format!("'{symbol}' is used before it is defined."))
+                    .return_error(&self.src.borrow(), (0, 0, 0).into(),
+                        "".to_string())),
+            Some(bound) => Ok(bound),
             None => Err(Error::new(ErrorKind::FreeIdentifier)
-                .with_message(format!("Failed to resolve '{name}'."))
+                .with_message(format!("Failed to resolve '{symbol}'."))
                 .return_error(&self.src.borrow(), (0, 0, 0).into(), 
                     "".to_string()))
         }
     }
 
+    /// Reduce every sub-term, then apply the combiner in the front to the rest.
     pub fn reduce_branch(&mut self, term: &mut Term) -> Result<(), Error> {
-        if term.is_branch() {
-            let front = term.sub_terms.front_mut().unwrap();
-            // TODO: Complete reduction.
-            match self.reduce_leaf(front) {
-                Err(err) => return Err(err),
-                _ => {}
+        match operator_name(term) {
+            "$define" => return self.reduce_define(term),
+            "$if" => return self.reduce_if(term),
+            "$lambda" => return self.reduce_lambda(term),
+            "$case-lambda" => return self.reduce_case_lambda(term),
+            "$while" => return self.reduce_while(term),
+            "$do" => return self.eval_do(term),
+            _ => ()
+        }
+        // The name is only copied for a traced call, as the operator is replaced by its value.
+        let traced = match operator_name(term) {
+            name if self.traced.contains(name) => Some(name.to_string()),
+            _ => None
+        };
+
+        let mut operands = self.reduce_operands(&term.sub_terms)?;
+        let operator = operands.pop_front().unwrap();
+        let Some(special_form) = traced else {
+            *term = self.apply(operator, operands)?;
+            return Ok(())
+        };
+        let call = operands.iter().fold(special_form.clone(), |call, arg| call + " " + &arg.to_string());
+        let _ = writeln!(self.output, "[trace] ({call})");
+        *term = self.apply(operator, operands)?;
+        let _ = writeln!(self.output, "[trace] {special_form} => {term}");
+        Ok(())
+    }
+
+    /// Reduce the operator and the operands of a combination, each of which must be a single
+    /// value, as the multiple values of `values` can only be taken apart by their consumers.
+    fn reduce_operands(&mut self, sub_terms: &LinkedList<Term>) -> Result<LinkedList<Term>, Error> {
+        let mut operands = LinkedList::new();
+        for sub_term in sub_terms {
+            let value = self.reduce_ref(sub_term)?;
+            if let TermValue::Values(MultipleValues(ref values)) = value.value {
+                return Err(Error::new(ErrorKind::ArityMismatch)
+                    .with_message(format!("Expected a single value, but got {} values; \
+                        use 'receive' or 'let-values' to bind them.", values.len())))
+            }
+            operands.push_back(value);
+        }
+        Ok(operands)
+    }
+
+    /// Apply a combiner to arguments which are already reduced.
+    pub fn apply(&mut self, operator: Term, operands: LinkedList<Term>) -> Result<Term, Error> {
+        match operator.value {
+            TermValue::PrimitiveFn(func) => {
+                let args: Vec<Term> = operands.into_iter().collect();
+                // The arguments of every builtin are counted here, so the builtins don't count them.
+                func.check_arity(&args)?;
+                func.call(self, &args)
+            }
+            TermValue::Lambda(mut lambda) => {
+                // Reduce the body in the new environment, and then the bodies of the lambdas it
+                // calls in tail position in turn, restoring the current environment afterwards.
+                let saved = self.env.clone();
+                let mut operands = operands;
+                let result = loop {
+                    match lambda.bind(operands).and_then(|env| {
+                        self.env = env;
+                        self.reduce_body(lambda.body())
+                    }) {
+                        Ok(Tail::Call(callee, args)) => (lambda, operands) = (callee, args),
+                        Ok(Tail::Value(value)) => break Ok(value),
+                        Err(err) => break Err(err)
+                    }
+                };
+                self.env = saved;
+                result
+            }
+            TermValue::CaseLambda(procedure) => {
+                let clause = procedure.select(operands.len())?;
+                self.apply(Term::from(clause), operands)
             }
+            TermValue::ConditionProcedure(procedure) => procedure.call(operands),
+            TermValue::RecordProcedure(procedure) => procedure.call(operands),
+            TermValue::Parameter(parameter) if operands.is_empty() => Ok(parameter.value()),
+            TermValue::Parameter(_) => Err(Error::new(ErrorKind::ArityMismatch)
+                .with_message(format!("A parameter expects no arguments, but got {}.", operands.len()))),
+            value => {
+                let type_name = value.type_name();
+                let article = if type_name.starts_with(['a', 'e', 'i', 'o', 'u']) { "an" } else { "a" };
+                Err(Error::new(ErrorKind::NotCallable)
+                    .with_message(format!("'{value}' is {article} {type_name}, not a procedure.")))
+            }
+        }
+    }
+
+    /// Reduce the terms in order, returning the result of the last one, unless it's a call
+    /// to a lambda.
+    fn reduce_body(&mut self, body: &[Term]) -> Result<Tail, Error> {
+        let (last, init) = body.split_last().unwrap();
+        for term in init {
+            self.reduce_ref(term)?;
         }
+        self.reduce_tail(last)
+    }
+
+    /// Reduce a term in tail position, following the branch taken by `$if`, and leaving the
+    /// application of a lambda as a tail call. Traced calls are applied right away, to be
+    /// written with their result.
+    fn reduce_tail(&mut self, mut term: &Term) -> Result<Tail, Error> {
+        loop {
+            if !term.is_branch() { break }
+            match operator_name(term) {
+                "$if" => {
+                    self.count_step()?;
+                    match self.select_if(term)? {
+                        Some(branch) => term = branch,
+                        None => return Ok(Tail::Value(Term::from(UnitValue::Ignore)))
+                    }
+                }
+                "$define" | "$lambda" | "$case-lambda" | "$while" | "$do" => break,
+                name if self.traced.contains(name) => break,
+                _ => {
+                    self.count_step()?;
+                    let mut operands = self.reduce_operands(&term.sub_terms)?;
+                    let operator = operands.pop_front().unwrap();
+                    return match operator.value {
+                        TermValue::Lambda(lambda) => Ok(Tail::Call(lambda, operands)),
+                        TermValue::CaseLambda(procedure) => Ok(Tail::Call(procedure.select(operands.len())?, operands)),
+                        _ => self.apply(operator, operands).map(Tail::Value)
+                    }
+                }
+            }
+        }
+        self.reduce_ref(term).map(Tail::Value)
+    }
+
+    /// `($define name expression)` binds the name in the current environment,
+    /// `($define (name formals...) body...)` binds a procedure, and `($define name)` binds the name
+    /// to no value yet, so that it shadows the enclosing scopes but can't be used until defined.
+    fn reduce_define(&mut self, term: &mut Term) -> Result<(), Error> {
+        let invalid = || Error::new(ErrorKind::InvalidSyntax)
+            .with_message("'$define' must be of the form '($define name expression)'.".to_string());
+        let mut operands = core::mem::take(&mut term.sub_terms);
+        operands.pop_front();
+        let target = operands.pop_front().ok_or_else(invalid)?;
+        let (name, value) = if target.is_branch() {
+            // The procedure form, where the target is the name followed by the formals.
+            let mut formals = target.sub_terms;
+            let name = formals.pop_front().unwrap();
+            let formals = if formals.is_empty() { Term::nil() } else { Term::list(formals.into_iter().collect()) };
+            (name, Term::from(Lambda::new(&formals, operands.into_iter().collect(), self.env.clone())?))
+        } else {
+            let value = match (operands.pop_front(), operands.is_empty()) {
+                (None, _) => Term::from(UnitValue::Unassigned),
+                (Some(mut value), true) => {
+                    self.reduce(&mut value)?;
+                    value
+                }
+                (Some(_), false) => return Err(invalid())
+            };
+            (target, value)
+        };
+        match (&name as &dyn TermAccess<Symbol>).try_access() {
+            Ok(symbol) if !name.has_value() => self.env.borrow_mut().insert(symbol.as_ref(), value),
+            _ => return Err(invalid())
+        };
+        *term = Term::from(UnitValue::Ignore);
         Ok(())
     }
+
+    /// `($lambda formals body...)` makes a procedure closing over the current environment.
+    fn reduce_lambda(&mut self, term: &mut Term) -> Result<(), Error> {
+        let mut operands = core::mem::take(&mut term.sub_terms);
+        operands.pop_front();
+        let formals = operands.pop_front().ok_or_else(|| Error::new(ErrorKind::InvalidSyntax)
+            .with_message("'$lambda' requires formals and a body.".to_string()))?;
+        let lambda = Lambda::new(&formals, operands.into_iter().collect(), self.env.clone())?;
+        *term = Term::from(lambda);
+        Ok(())
+    }
+
+    /// `($case-lambda (formals body...)...)` makes a procedure of a lambda for each clause,
+    /// closing over the current environment.
+    fn reduce_case_lambda(&mut self, term: &mut Term) -> Result<(), Error> {
+        let mut clauses = core::mem::take(&mut term.sub_terms);
+        clauses.pop_front();
+        let lambdas = clauses.into_iter()
+            .map(|clause| {
+                if !clause.is_branch() {
+                    return Err(Error::new(ErrorKind::InvalidSyntax)
+                        .with_message("'$case-lambda' must be of the form '($case-lambda (formals body...)...)'.".to_string()))
+                }
+                let mut parts = clause.sub_terms;
+                let formals = parts.pop_front().unwrap();
+                Lambda::new(&formals, parts.into_iter().collect(), self.env.clone())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        *term = Term::from(CaseLambda::new(lambdas));
+        Ok(())
+    }
+
+    /// `($if test consequent [alternative])` reduces the consequent unless the test is `#f`.
+    fn reduce_if(&mut self, term: &mut Term) -> Result<(), Error> {
+        *term = match self.select_if(term)? {
+            Some(branch) => self.reduce_ref(branch)?,
+            None => Term::from(UnitValue::Ignore)
+        };
+        Ok(())
+    }
+
+    /// Reduce the test of an `$if`, returning the branch it selects, still to be reduced, or
+    /// `None` if the test is `#f` and there is no alternative.
+    fn select_if<'a>(&mut self, term: &'a Term) -> Result<Option<&'a Term>, Error> {
+        let mut operands = term.sub_terms.iter().skip(1);
+        if operands.len() != 2 && operands.len() != 3 {
+            return Err(Error::new(ErrorKind::InvalidSyntax)
+                .with_message("'$if' requires a test, a consequent and an optional alternative.".to_string()))
+        }
+        let test = self.reduce_ref(operands.next().unwrap())?;
+        let consequent = operands.next();
+        Ok(if !test.is_false() { consequent } else { operands.next() })
+    }
+
+    /// `($while test body...)` reduces the body repeatedly as long as the test is not `#f`.
+    fn reduce_while(&mut self, term: &mut Term) -> Result<(), Error> {
+        let mut operands = core::mem::take(&mut term.sub_terms);
+        operands.pop_front();
+        let test = operands.pop_front().ok_or_else(|| Error::new(ErrorKind::InvalidSyntax)
+            .with_message("'$while' requires a test.".to_string()))?;
+        loop {
+            if self.reduce_ref(&test)?.is_false() { break }
+            for body in &operands {
+                self.reduce_ref(body)?;
+            }
+        }
+        *term = Term::from(UnitValue::Ignore);
+        Ok(())
+    }
+
+    /// `($do ((name init [step])...) (test result...) command...)` binds the names to the inits,
+    /// and until the test is not `#f`, reduces the commands and rebinds the names to their steps,
+    /// all of which are reduced before any name is rebound. At last it reduces the results.
+    ///
+    /// The names are bound in a single environment which is updated in place, rather than a new
+    /// one for each iteration, unless a closure made during an iteration keeps it, in which case
+    /// the next iteration updates a copy of it.
+    fn eval_do(&mut self, term: &mut Term) -> Result<(), Error> {
+        let invalid = || Error::new(ErrorKind::InvalidSyntax)
+            .with_message("'$do' must be of the form '($do ((name init [step])...) (test result...) command...)'.".to_string());
+        let mut operands = core::mem::take(&mut term.sub_terms);
+        operands.pop_front();
+        let (bindings, clause) = match (operands.pop_front(), operands.pop_front()) {
+            (Some(bindings), Some(clause)) if clause.is_branch() => (bindings, clause),
+            _ => return Err(invalid())
+        };
+        let bindings = match bindings.value {
+            _ if bindings.is_branch() => bindings.sub_terms,
+            TermValue::Nil => LinkedList::new(),
+            _ => return Err(invalid())
+        };
+        let (mut names, mut inits, mut steps) = (vec![], vec![], vec![]);
+        for binding in bindings {
+            if !binding.is_branch() || !(2..=3).contains(&binding.sub_terms.len()) { return Err(invalid()) }
+            let mut parts = binding.sub_terms.into_iter();
+            let name = parts.next().unwrap();
+            match (&name as &dyn TermAccess<Symbol>).try_access() {
+                Ok(symbol) if !name.has_value() => names.push(symbol.as_ref().clone()),
+                _ => return Err(invalid())
+            }
+            inits.push(parts.next().unwrap());
+            if let Some(step) = parts.next() { steps.push((names.len() - 1, step)) }
+        }
+        let mut results = clause.sub_terms;
+        let test = results.pop_front().unwrap();
+
+        // The inits are reduced in the current environment, before any name is bound.
+        let mut frame = Env::with_parent(self.env.clone());
+        for (name, mut init) in names.iter().zip(inits) {
+            self.reduce(&mut init)?;
+            frame.insert(name, init);
+        }
+        let saved = core::mem::replace(&mut self.env, Rc::new(RefCell::new(frame)));
+        let result = self.iterate_do(&names, &test, &steps, &operands, &results);
+        self.env = saved;
+        *term = result?;
+        Ok(())
+    }
+
+    fn iterate_do(&mut self, names: &[String], test: &Term, steps: &[(usize, Term)], commands: &LinkedList<Term>,
+        results: &LinkedList<Term>) -> Result<Term, Error> {
+        loop {
+            if !self.reduce_ref(test)?.is_false() { break }
+            for command in commands {
+                self.reduce_ref(command)?;
+            }
+            let mut values = Vec::with_capacity(steps.len());
+            for (index, step) in steps {
+                values.push((*index, self.reduce_ref(step)?));
+            }
+            // A closure made during the iteration keeps the bindings it saw.
+            if Rc::strong_count(&self.env) > 1 {
+                let copy = self.env.borrow().clone();
+                self.env = Rc::new(RefCell::new(copy));
+            }
+            let mut frame = self.env.borrow_mut();
+            for (index, value) in values {
+                frame.insert(&names[index], value);
+            }
+        }
+        let mut value = Term::from(UnitValue::Ignore);
+        for result in results {
+            value = self.reduce_ref(result)?;
+        }
+        Ok(value)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Env {
-    bindings: HashMap<String, Term>
+    bindings: HashMap<String, Term>,
+    macros: HashMap<String, Macro>,
+    parent: Option<Rc<RefCell<Env>>>
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Env::new()
+    }
 }
 
-// TODO: Implement linked environments.
 impl Env {
     pub fn new() -> Self {
-        Self { bindings: HashMap::new() }
+        Self { bindings: HashMap::new(), macros: HashMap::new(), parent: None }
     }
 
-    pub fn lookup(&mut self, name: &String) -> Option<&mut Term> {
-        self.bindings.get_mut(name)
+    /// An empty environment whose lookups fall back to `parent`.
+    pub fn with_parent(parent: Rc<RefCell<Env>>) -> Self {
+        Self { bindings: HashMap::new(), macros: HashMap::new(), parent: Some(parent) }
+    }
+
+    pub fn lookup_macro(&self, name: &String) -> Option<Macro> {
+        match self.macros.get(name) {
+            Some(transformer) => Some(*transformer),
+            None => self.parent.as_ref().and_then(|parent| parent.borrow().lookup_macro(name))
+        }
+    }
+
+    pub fn insert_macro(&mut self, name: &String, transformer: Macro) -> Option<Macro> {
+        self.macros.insert(name.to_string(), transformer)
+    }
+
+    /// Look the name up in this environment, and then in its ancestors.
+    pub fn lookup(&self, name: &String) -> Option<Term> {
+        if let Some(term) = self.get(name) {
+            return Some(term.clone())
+        }
+        let mut env = self.parent.clone();
+        while let Some(current) = env {
+            if let Some(term) = current.borrow().get(name) {
+                return Some(term.clone())
+            }
+            env = current.borrow().parent.clone();
+        }
+        None
+    }
+
+    /// The binding in this environment alone. The few bindings of a call are compared directly,
+    /// which is cheaper than hashing the name for each environment on the way to the prelude.
+    fn get(&self, name: &String) -> Option<&Term> {
+        if self.bindings.len() > 4 {
+            return self.bindings.get(name)
+        }
+        self.bindings.iter().find_map(|(key, term)| (key == name).then_some(term))
     }
 
     pub fn insert(&mut self, name: &String, term: Term) -> Option<Term> {
         self.bindings.insert(name.to_string(), term)
     }
+
+    /// Compare the environment as an earlier snapshot against `other`, returning
+    /// `(name, old, new)` for every binding which was changed, added or removed.
+    /// The result is ordered by name.
+    pub fn diff(&self, other: &Env) -> Vec<(Symbol, Option<Term>, Option<Term>)> {
+        let mut changes = vec![];
+        for (name, old) in &self.bindings {
+            match other.bindings.get(name) {
+                Some(new) if new == old => {},
+                new => changes.push((Symbol::from(name.as_str()), Some(old.clone()), new.cloned()))
+            }
+        }
+        for (name, new) in &other.bindings {
+            if !self.bindings.contains_key(name) {
+                changes.push((Symbol::from(name.as_str()), None, Some(new.clone())));
+            }
+        }
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use crate::parser::SrcInfo;
+    use crate::share;
+    use crate::syntax::Node;
+    use crate::error::Error;
+    use super::{Context, Env};
+    use super::super::expander::Macro;
+    use super::super::term::{Term, UnitValue};
+
+    /// `(my-and)` => `#t`, `(my-and a)` => `a`, `(my-and a b ...)` => `($if a (my-and b ...) #f)`
+    fn my_and(form: &Node) -> Result<Node, Error> {
+        let list: &Vec<Node> = form.as_ref();
+        Ok(match &list[1..] {
+            [] => true.into(),
+            [only] => only.clone(),
+            [first, rest @ ..] => {
+                let mut tail = vec!["my-and".into()];
+                tail.extend(rest.iter().cloned());
+                Node::list(vec!["$if".into(), first.clone(), Node::list(tail), false.into()])
+            }
+        })
+    }
+
+    #[test]
+    fn context_check_expansion_depth() {
+        let ctx = Context::new(share!(SrcInfo::new("test", ""))).with_max_macro_expansion_depth(1);
+        assert!(ctx.check_expansion_depth(&Node::list(vec!["a".into(), "b".into()])).is_ok());
+        let err = ctx.check_expansion_depth(&Node::list(vec![Node::list(vec!["a".into()])])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ExpansionLimitExceeded);
+    }
+
+    #[test]
+    fn context_macroexpand_all() {
+        let ctx = Context::new(share!(SrcInfo::new("test", "")));
+        ctx.env.borrow_mut().insert_macro(&"my-and".to_string(), Macro::new(my_and));
+
+        let expanded = ctx.macroexpand_all(
+            Node::list(vec!["display".into(), Node::list(vec!["my-and".into(), "a".into(), "b".into(), "c".into()])])
+        ).unwrap();
+        assert_eq!(expanded,
+            Node::list(vec!["display".into(),
+                Node::list(vec!["$if".into(), "a".into(),
+                    Node::list(vec!["$if".into(), "b".into(), "c".into(), false.into()]),
+                    false.into()])
+            ])
+        );
+        assert_eq!(ctx.macroexpand_all(Node::list(vec!["my-and".into()])).unwrap(), true.into());
+    }
+
+    #[test]
+    fn context_macroexpand_all_limit() {
+        fn forever(form: &Node) -> Result<Node, Error> { Ok(form.clone()) }
+        let ctx = Context::new(share!(SrcInfo::new("test", ""))).with_max_macro_expansion_depth(8);
+        ctx.env.borrow_mut().insert_macro(&"forever".to_string(), Macro::new(forever));
+        let err = ctx.macroexpand_all(Node::list(vec!["forever".into()])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ExpansionLimitExceeded);
+    }
+
+    #[test]
+    fn context_step_limit() {
+        use crate::parser::SyntacticParser;
+        let src = share!(SrcInfo::new("test", "($while 1 (gcd 4 6))"));
+        let mut parser = SyntacticParser::new(src.clone());
+        parser.try_parse().unwrap();
+        let form = parser.tree().as_ref()[0].clone();
+
+        let mut ctx = Context::new(src.clone()).with_step_limit(100);
+        let err = ctx.eval(form.clone().into()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::StepLimitExceeded);
+
+        // The counter restarts for every evaluation.
+        let mut ctx = Context::new(src).with_step_limit(4);
+        assert!(ctx.eval(Node::list(vec!["gcd".into(), 4.into(), 6.into()]).into()).is_ok());
+        assert!(ctx.eval(Node::list(vec!["gcd".into(), 4.into(), 6.into()]).into()).is_ok());
+    }
+
+    #[test]
+    fn context_reduce_leaf() {
+        let mut ctx = Context::new(share!(SrcInfo::new("test", "")));
+        let mut env = Env::new();
+        super::super::prelude::install_prelude(&mut env);
+        let mut term = Term::symbol("gcd");
+        assert!(!term.has_value());
+        ctx.reduce_leaf(&mut term).unwrap();
+        assert!(term.has_value() && !term.is_branch());
+        assert_eq!(term, env.lookup(&"gcd".to_string()).unwrap());
+
+        let mut free = Term::symbol("undefined");
+        assert_eq!(ctx.reduce_leaf(&mut free).unwrap_err().kind(), ErrorKind::FreeIdentifier);
+    }
+
+    #[test]
+    fn context_lambda() {
+        use crate::parser::SyntacticParser;
+        let eval = |text: &str| {
+            let src = share!(SrcInfo::new("test", text));
+            let mut parser = SyntacticParser::new(src.clone());
+            parser.try_parse().unwrap();
+            let mut term: Term = parser.tree().as_ref()[0].clone().into();
+            Context::new(src).reduce(&mut term).map(|_| term)
+        };
+        assert_eq!(eval("(($lambda (x) x) 1)").unwrap(), Term::int(1));
+        // The inner procedure closes over `x`, after the outer call has returned.
+        assert_eq!(eval("((($lambda (x) ($lambda (y) (gcd x y))) 12) 18)").unwrap(), Term::int(6));
+        assert_eq!(eval("(($lambda (x) ($lambda (x) x) x) 2)").unwrap(), Term::int(2));
+        assert_eq!(eval("(($lambda args args))").unwrap(), Term::nil());
+        assert_eq!(eval("(($lambda (x) x))").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(($lambda (x) y) 1)").unwrap_err().kind(), ErrorKind::FreeIdentifier);
+        assert_eq!(eval("($lambda (1) 1)").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval("($lambda (x))").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+
+        // Applying a value which isn't a procedure names its type.
+        let err = eval("(5 1 2)").unwrap_err();
+        assert_eq!((err.kind(), err.message().as_str()), (ErrorKind::NotCallable, "'5' is an int, not a procedure."));
+        assert_eq!(eval("(($lambda (x) (x)) 'f')").unwrap_err().message(), r#"'"f"' is a string, not a procedure."#);
+    }
+
+    #[test]
+    fn context_native_fn_binds() {
+        use super::super::combiner::NativeFn;
+        // A builtin is given the context of its caller, so it can bind names in its environment.
+        fn bind_answer(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+            ctx.env.borrow_mut().insert(&"answer".to_string(), args[0].clone());
+            Ok(Term::from(UnitValue::Ignore))
+        }
+        let mut ctx = Context::new(share!(SrcInfo::new("test", "")));
+        ctx.env.borrow_mut().insert(&"bind-answer".to_string(), Term::from(NativeFn::new(bind_answer)));
+        ctx.eval(Node::list(vec!["bind-answer".into(), 42.into()]).into()).unwrap();
+        assert_eq!(ctx.eval_to_value(Node::from("answer").into()).unwrap(), Term::int(42));
+
+        // Inside a lambda, the binding is local to its call.
+        let local = Node::list(vec![Node::list(vec![
+            "$lambda".into(), Node::list(vec![]), Node::list(vec!["bind-answer".into(), 1.into()]), "answer".into()
+        ])]);
+        assert_eq!(ctx.eval_to_value(local.into()).unwrap(), Term::int(1));
+        assert_eq!(ctx.eval_to_value(Node::from("answer").into()).unwrap(), Term::int(42));
+    }
+
+    #[test]
+    fn context_tail_calls() {
+        use crate::parser::SyntacticParser;
+        let eval = |text: &str| {
+            let src = share!(SrcInfo::new("test", text));
+            let mut parser = SyntacticParser::new(src.clone());
+            parser.try_parse().unwrap();
+            let mut ctx = Context::new(src);
+            let mut result = Ok(Term::from(UnitValue::Ignore));
+            for form in parser.tree().as_ref() {
+                result = ctx.eval_to_value(ctx.macroexpand_all(form.clone()).unwrap().into());
+            }
+            result
+        };
+        // Calls in tail position, through `$if`, run in constant stack space, even between
+        // different procedures, which would overflow the stack of a test thread otherwise.
+        let src = "($define (even? n) ($if (= n 0) #t (odd? (- n 1)))) \
+            ($define (odd? n) ($if (= n 0) #f (even? (- n 1)))) (even? 50000)";
+        assert_eq!(eval(src).unwrap(), Term::boolean(true));
+        assert_eq!(eval("($define (f n) ($if (> n 0) (f (- n 1)))) (f 50000)").unwrap(), Term::from(UnitValue::Ignore));
+        // A call which isn't in tail position still returns to its caller.
+        assert_eq!(eval("($define (f n) ($if (= n 0) 0 (+ 1 (f (- n 1))))) (f 100)").unwrap(), Term::int(100));
+        assert_eq!(eval("($define (f n) ($if (= n 0) 0 (f 0)) 1) (f 5)").unwrap(), Term::int(1));
+        assert_eq!(eval("($define (f) (g)) (f)").unwrap_err().kind(), ErrorKind::FreeIdentifier);
+    }
+
+    #[test]
+    fn context_eval_to_value() {
+        let mut ctx = Context::new(share!(SrcInfo::new("test", "")));
+        ctx.env.borrow_mut().insert(&"x".to_string(), Term::int(7));
+        assert_eq!(ctx.eval_to_value(Term::symbol("x")).unwrap(), Term::int(7));
+        assert_eq!(ctx.eval_to_value(Term::int(1)).unwrap(), Term::int(1));
+        let call = Term::list(vec![Term::symbol("gcd"), Term::int(12), Term::symbol("x")]);
+        assert_eq!(ctx.eval_to_value(call).unwrap(), Term::int(1));
+        let call = Term::list(vec![Term::symbol("quotient"), Term::symbol("x"), Term::int(2)]);
+        assert_eq!(ctx.eval_to_value(call).unwrap(), Term::int(3));
+        let call = Term::list(vec![Term::symbol("+"), Term::int(1), Term::int(2)]);
+        assert_eq!(ctx.eval_to_value(call).unwrap(), Term::int(3));
+        assert_eq!(ctx.eval_to_value(Term::symbol("y")).unwrap_err().kind(), ErrorKind::FreeIdentifier);
+    }
+
+    #[test]
+    fn context_define() {
+        use crate::parser::SyntacticParser;
+        let src = share!(SrcInfo::new("test", "($define x 4) ($define (f n) (gcd n 6)) (f x) ($define 1 2)"));
+        let mut parser = SyntacticParser::new(src.clone());
+        parser.try_parse().unwrap();
+        let mut ctx = Context::new(src);
+        let results: Vec<_> = parser.tree().as_ref().iter()
+            .map(|node| { let mut term: Term = node.clone().into(); ctx.reduce(&mut term).map(|_| term) })
+            .collect();
+        assert_eq!(results[0].as_ref().unwrap(), &Term::from(UnitValue::Ignore));
+        assert_eq!(ctx.env.borrow().lookup(&"x".to_string()), Some(Term::int(4)));
+        assert_eq!(results[2].as_ref().unwrap(), &Term::int(2));
+        assert_eq!(results[3].as_ref().unwrap_err().kind(), ErrorKind::InvalidSyntax);
+    }
+
+    #[test]
+    fn context_eval_file_incremental() {
+        let path = std::env::temp_dir().join(format!("thesis-incremental-{}.ths", std::process::id()));
+        std::fs::write(&path, "($define x 12) (gcd x 18) #;(skipped) missing (not #f) (quotient 1 0) 2").unwrap();
+        let mut ctx = Context::new(share!(SrcInfo::new("test", "")));
+        let results: Vec<_> = ctx.eval_file_incremental(&path).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().unwrap(), &Term::from(UnitValue::Ignore));
+        assert_eq!(results[1].as_ref().unwrap(), &Term::int(6));
+        // A free identifier doesn't stop the evaluation, but a division by zero does.
+        assert_eq!(results[2].as_ref().unwrap_err().kind(), ErrorKind::FreeIdentifier);
+        assert_eq!(results[3].as_ref().unwrap(), &Term::boolean(true));
+        assert_eq!(results[4].as_ref().unwrap_err().kind(), ErrorKind::DivisionByZero);
+
+        // The forms may span lines, and so may the strings in them.
+        std::fs::write(&path, "(string-contains\n  \"a\nb\" \"b\")\n($define (f n)\n  ($if (= n 0) 0 (+ 1 (f (- n 1)))))\n(f 100) 3").unwrap();
+        let mut ctx = Context::new(share!(SrcInfo::new("test", ""))).with_max_reduction_depth(50);
+        let results: Vec<_> = ctx.eval_file_incremental(&path).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &Term::int(2));
+        // Nesting too deep stops the evaluation, like a division by zero.
+        assert_eq!(results[2].as_ref().unwrap_err().kind(), ErrorKind::StackOverflow);
+
+        // A line which isn't UTF-8 ends the reading, but not the forms before it.
+        std::fs::write(&path, b"1\n\xff 2\n3").unwrap();
+        let results: Vec<_> = ctx.eval_file_incremental(&path).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &Term::int(1));
+        assert_eq!(results[1].as_ref().unwrap_err().message(),
+            &format!("'{}' is not valid UTF-8: invalid byte sequence at offset 2.", path.display()));
+
+        let missing = std::env::temp_dir().join("thesis-incremental-missing.ths");
+        let results: Vec<_> = ctx.eval_file_incremental(&missing).collect();
+        assert!(matches!(results[..], [Err(ref err)] if err.kind() == ErrorKind::IoError));
+    }
+
+    #[test]
+    fn context_include_paths() {
+        let dir = std::env::temp_dir().join(format!("thesis-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("lib").join("util.ths"), "($define util 42)").unwrap();
+        let empty = dir.join("empty");
+        let mut ctx = Context::new(share!(SrcInfo::new("test", "")))
+            .with_include_paths(vec![empty.clone(), dir.join("lib")]);
+        let results: Vec<_> = ctx.eval_file_incremental(std::path::Path::new("util.ths")).collect();
+        let missing = ctx.resolve_path(std::path::Path::new("missing.ths")).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(ctx.env.borrow().lookup(&"util".to_string()), Some(Term::int(42)));
+
+        // Every directory searched is listed, in order, ending with the current one.
+        assert_eq!(missing.kind(), ErrorKind::IoError);
+        assert_eq!(missing.message(), &format!("Failed to find 'missing.ths', searched '{}', '{}', 'missing.ths'.",
+            empty.join("missing.ths").display(), dir.join("lib").join("missing.ths").display()));
+    }
+
+    #[test]
+    fn env_diff() {
+        let mut old = Env::new();
+        old.insert(&"kept".to_string(), Term::from(1i64));
+        old.insert(&"changed".to_string(), Term::from(2i64));
+        old.insert(&"removed".to_string(), Term::from(3i64));
+        let mut new = Env::new();
+        new.insert(&"kept".to_string(), Term::from(1i64));
+        new.insert(&"changed".to_string(), Term::from(20i64));
+        new.insert(&"added".to_string(), Term::from(4i64));
+
+        assert_eq!(old.diff(&new), vec![
+            ("added".into(), None, Some(Term::from(4i64))),
+            ("changed".into(), Some(Term::from(2i64)), Some(Term::from(20i64))),
+            ("removed".into(), Some(Term::from(3i64)), None),
+        ]);
+        assert!(old.diff(&old).is_empty());
+    }
 }
@@ -0,0 +1,21 @@
+use crate::error::Error;
+use crate::syntax::Node;
+
+/// A transformer receiving a whole macro form (including the operator)
+/// and producing its expansion.
+pub type MacroTransformer = fn(&Node) -> Result<Node, Error>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Macro {
+    transformer: MacroTransformer
+}
+
+impl Macro {
+    pub fn new(transformer: MacroTransformer) -> Self {
+        Self { transformer }
+    }
+
+    pub fn expand(&self, form: &Node) -> Result<Node, Error> {
+        (self.transformer)(form)
+    }
+}
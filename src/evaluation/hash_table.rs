@@ -0,0 +1,227 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::term::{Term, TermValue};
+
+/// How the keys of a hash table are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEquality {
+    /// Compound values are the same only if they are the same object, like with `eq?`.
+    Eq,
+    /// Pairs, vectors, bytevectors and maps are the same if their elements are, like with `equal?`.
+    Equal
+}
+
+/// A key of a hash table, made from a term so that terms which are the same by the equality
+/// of the table make the same key.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashKey {
+    Bool(bool),
+    Int(i64),
+    /// The numerator and the denominator of a ratio.
+    Rational(i64, i64),
+    /// The bits of a float, where both zeros are the same.
+    Float(u64),
+    Str(String),
+    Char(char),
+    Sym(String),
+    Nil,
+    Eof,
+    Unit,
+    /// The elements of a chain of pairs, followed by what its last cdr is.
+    List(Vec<HashKey>, Box<HashKey>),
+    Vector(Vec<HashKey>),
+    Bytevector(Vec<u8>),
+    /// The keys and the values of a map, sorted by key, as the order of the entries doesn't matter.
+    Map(Vec<(HashKey, HashKey)>),
+    /// The address of an object compared by identity, which the table keeps alive along with
+    /// the key, so that no other object can take its address.
+    EqKey(usize)
+}
+
+impl HashKey {
+    /// The key of a term, or `None` if it's a value which can be told apart by neither its
+    /// contents nor its identity, like an error object.
+    pub fn of(term: &Term, equality: KeyEquality) -> Option<HashKey> {
+        Some(match term.value {
+            TermValue::Bool(b) => HashKey::Bool(b),
+            TermValue::Int(n) => HashKey::Int(n),
+            TermValue::Rational(ratio) => HashKey::Rational(ratio.numerator(), ratio.denominator()),
+            TermValue::Float(x) => HashKey::Float(if x == 0.0 { 0 } else { x.to_bits() }),
+            TermValue::Str(ref s) => HashKey::Str(s.clone()),
+            TermValue::Char(ch) => HashKey::Char(ch),
+            TermValue::Sym(ref symbol) => HashKey::Sym(symbol.to_string()),
+            TermValue::Nil => HashKey::Nil,
+            TermValue::Eof => HashKey::Eof,
+            TermValue::Unit(_) => HashKey::Unit,
+            TermValue::Pair(ref pair) if equality == KeyEquality::Equal => {
+                // Follow the cdrs iteratively, so that a long list doesn't overflow the stack.
+                let mut elements = vec![];
+                let mut pair = pair.clone();
+                loop {
+                    let (car, cdr) = pair.borrow().clone();
+                    elements.push(HashKey::of(&car, equality)?);
+                    match cdr.value {
+                        TermValue::Pair(next) => pair = next,
+                        _ => return Some(HashKey::List(elements, Box::new(HashKey::of(&cdr, equality)?)))
+                    }
+                }
+            }
+            TermValue::Vector(ref vector) if equality == KeyEquality::Equal => HashKey::Vector(
+                vector.borrow().iter().map(|element| HashKey::of(element, equality)).collect::<Option<Vec<_>>>()?),
+            TermValue::Map(ref map) if equality == KeyEquality::Equal => {
+                let mut entries = map.entries().iter()
+                    .map(|(key, value)| Some((HashKey::of(key, equality)?, HashKey::of(value, equality)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                entries.sort();
+                HashKey::Map(entries)
+            }
+            TermValue::Bytevector(ref bytes) if equality == KeyEquality::Equal => HashKey::Bytevector(bytes.borrow().clone()),
+            TermValue::Pair(ref pair) => HashKey::EqKey(Rc::as_ptr(pair) as usize),
+            TermValue::Vector(ref vector) => HashKey::EqKey(vector.address()),
+            TermValue::Bytevector(ref bytes) => HashKey::EqKey(Rc::as_ptr(bytes) as usize),
+            TermValue::HashTable(ref table) => HashKey::EqKey(Rc::as_ptr(&table.0) as usize),
+            TermValue::Map(ref map) => HashKey::EqKey(map.id()),
+            TermValue::Lambda(ref lambda) => HashKey::EqKey(lambda.id()),
+            TermValue::CaseLambda(ref procedure) => HashKey::EqKey(procedure.id()),
+            TermValue::PrimitiveFn(ref func) => HashKey::EqKey(func.id()),
+            TermValue::Parameter(ref parameter) => HashKey::EqKey(parameter.id()),
+            TermValue::Port(ref port) => HashKey::EqKey(port.id()),
+            TermValue::ConditionType(ref condition_type) => HashKey::EqKey(condition_type.id()),
+            TermValue::Record(ref record) => HashKey::EqKey(record.id()),
+            TermValue::RecordConstructorDescriptor(ref descriptor) => HashKey::EqKey(descriptor.id()),
+            TermValue::RecordTypeDescriptor(ref record_type) => HashKey::EqKey(record_type.id()),
+            _ => return None
+        })
+    }
+}
+
+struct HashTableData {
+    equality: KeyEquality,
+    entries: RefCell<HashMap<HashKey, (Term, Term)>>
+}
+
+/// A mutable table from keys to values, shared by every alias of it.
+#[derive(Clone)]
+pub struct HashTable(Rc<HashTableData>);
+
+impl HashTable {
+    pub fn new(equality: KeyEquality) -> Self {
+        Self(Rc::new(HashTableData { equality, entries: RefCell::new(HashMap::new()) }))
+    }
+
+    /// The key of the term in this table, by its equality.
+    pub fn key(&self, term: &Term) -> Option<HashKey> {
+        HashKey::of(term, self.0.equality)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.entries.borrow().len()
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<Term> {
+        self.0.entries.borrow().get(key).map(|(_, value)| value.clone())
+    }
+
+    /// Bind the key to the value, keeping the term the key was first inserted with.
+    pub fn insert(&self, key: HashKey, term: Term, value: Term) {
+        self.0.entries.borrow_mut().entry(key).or_insert_with(|| (term, Term::new())).1 = value;
+    }
+
+    pub fn remove(&self, key: &HashKey) -> bool {
+        self.0.entries.borrow_mut().remove(key).is_some()
+    }
+
+    /// The keys and their values, in no particular order.
+    pub fn entries(&self) -> Vec<(HashKey, Term, Term)> {
+        self.0.entries.borrow().iter().map(|(key, (term, value))| (key.clone(), term.clone(), value.clone())).collect()
+    }
+}
+
+impl PartialEq for HashTable {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl std::fmt::Debug for HashTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<hash-table {}>", self.len())
+    }
+}
+
+/// An immutable map from keys to values, compared by `equal?`, as written by a literal like
+/// `{a 1 b 2}`. The entries keep the order their keys were first given in.
+#[derive(Clone)]
+pub struct Map(Rc<MapData>);
+
+#[derive(Clone, Default)]
+struct MapData {
+    entries: Vec<(Term, Term)>,
+    /// The index in the entries of each key.
+    index: HashMap<HashKey, usize>
+}
+
+impl MapData {
+    fn insert(&mut self, term: Term, value: Term) -> Option<()> {
+        let key = HashKey::of(&term, KeyEquality::Equal)?;
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key, self.entries.len());
+                self.entries.push((term, value));
+            }
+        }
+        Some(())
+    }
+}
+
+impl Map {
+    /// A map of the entries, where a key given again replaces the value of the earlier one,
+    /// or `None` if a key can't be told apart from others.
+    pub fn new<I: IntoIterator<Item = (Term, Term)>>(entries: I) -> Option<Self> {
+        let mut data = MapData::default();
+        for (key, value) in entries {
+            data.insert(key, value)?;
+        }
+        Some(Map(Rc::new(data)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.entries.len()
+    }
+
+    pub fn get(&self, key: &Term) -> Option<Term> {
+        let key = HashKey::of(key, KeyEquality::Equal)?;
+        self.0.index.get(&key).map(|&i| self.0.entries[i].1.clone())
+    }
+
+    /// A new map with the key bound to the value, leaving this one as it is.
+    pub fn insert(&self, term: Term, value: Term) -> Option<Map> {
+        let mut data = self.0.as_ref().clone();
+        data.insert(term, value)?;
+        Some(Map(Rc::new(data)))
+    }
+
+    /// The keys and their values, in order.
+    pub fn entries(&self) -> Vec<(Term, Term)> {
+        self.0.entries.clone()
+    }
+
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl std::fmt::Debug for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<map {}>", self.len())
+    }
+}
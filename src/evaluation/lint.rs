@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ariadne::Label;
+
+use crate::error::{Error, ErrorKind, Severity};
+use crate::parser::{LexicalParser, SrcInfo, SyntacticParser};
+
+/// A datum of the source with the spans of its symbols, which the syntax tree doesn't keep.
+enum Datum {
+    List(Vec<Datum>),
+    Atom(String, Range<usize>)
+}
+
+/// Read the top-level data of the source, dropping the ones commented out.
+/// A source with unbalanced delimiters has no data, as it fails to parse anyway.
+fn read(src: &SrcInfo) -> Vec<Datum> {
+    let mut lexer = LexicalParser::new();
+    lexer.parse_str(&src.text);
+    // The data of each open list, with the number of datum comments waiting for a datum.
+    let mut stack: Vec<(Vec<Datum>, usize)> = vec![(vec![], 0)];
+    for (pos, token) in lexer.results() {
+        let datum = match token.as_ref() {
+            "#;" => {
+                stack.last_mut().unwrap().1 += 1;
+                continue
+            }
+            "(" | "[" | "{" => {
+                stack.push((vec![], 0));
+                continue
+            }
+            ")" | "]" | "}" => match stack.pop() {
+                Some((data, _)) if !stack.is_empty() => Datum::List(data),
+                _ => return vec![]
+            },
+            _ => Datum::Atom(token.to_string(), SyntacticParser::token_span(pos, &token))
+        };
+        let (data, skips) = stack.last_mut().unwrap();
+        if *skips > 0 { *skips -= 1 } else { data.push(datum) }
+    }
+    match stack.pop() {
+        Some((data, _)) if stack.is_empty() => data,
+        _ => vec![]
+    }
+}
+
+/// The bindings of the enclosing scopes, from the top level inwards, with their spans.
+struct Scopes<'a> {
+    src: &'a SrcInfo,
+    frames: Vec<HashMap<&'a str, Range<usize>>>,
+    warnings: Vec<Error>
+}
+
+impl<'a> Scopes<'a> {
+    /// Bind the name in the innermost scope, warning if an enclosing scope binds it as well.
+    /// Binding a name again in the same scope only redefines it.
+    fn bind(&mut self, name: &'a str, span: &Range<usize>) {
+        let (frame, enclosing) = self.frames.split_last_mut().unwrap();
+        if frame.contains_key(name) { return }
+        if let Some(outer) = enclosing.iter().rev().find_map(|frame| frame.get(name)) {
+            self.warnings.push(Error::new(ErrorKind::Shadowing)
+                .with_severity(Severity::Warning)
+                .with_message(format!("'{name}' shadows a binding of an enclosing scope."))
+                .with_span(span.clone())
+                .with_label(Label::new((self.src.id.clone(), outer.clone()))
+                    .with_message(format!("The outer '{name}' is bound here."))
+                    .with_order(1))
+                .return_error(self.src, (0, 0, span.start).into(), format!("This '{name}' hides it.")));
+        }
+        frame.insert(name, span.clone());
+    }
+
+    /// Bind the formals of a procedure, like `(a b)`, `(a . rest)` or `args`.
+    fn bind_formals(&mut self, formals: &'a [Datum]) {
+        for formal in formals {
+            if let Datum::Atom(name, span) = formal {
+                if name != "." { self.bind(name, span) }
+            }
+        }
+    }
+
+    fn walk(&mut self, datum: &'a Datum) {
+        let Datum::List(data) = datum else { return };
+        match data.first() {
+            Some(Datum::Atom(head, _)) if head == "$define" => match data.get(1) {
+                Some(Datum::Atom(name, span)) => self.bind(name, span),
+                Some(Datum::List(target)) => {
+                    if let Some(Datum::Atom(name, span)) = target.first() { self.bind(name, span) }
+                    self.frames.push(HashMap::new());
+                    self.bind_formals(&target[1..]);
+                    data[2..].iter().for_each(|datum| self.walk(datum));
+                    self.frames.pop();
+                    return
+                }
+                None => ()
+            },
+            Some(Datum::Atom(head, _)) if head == "$lambda" => {
+                self.frames.push(HashMap::new());
+                match data.get(1) {
+                    Some(Datum::List(formals)) => self.bind_formals(formals),
+                    Some(formal) => self.bind_formals(std::slice::from_ref(formal)),
+                    None => ()
+                }
+                data.iter().skip(2).for_each(|datum| self.walk(datum));
+                self.frames.pop();
+                return
+            }
+            _ => ()
+        }
+        data.iter().for_each(|datum| self.walk(datum));
+    }
+}
+
+/// A warning for every parameter or definition of the source which shadows a binding of an
+/// enclosing scope, labelled with both binding sites.
+pub fn shadowing_warnings(src: &SrcInfo) -> Vec<Error> {
+    let data = read(src);
+    let mut scopes = Scopes { src, frames: vec![HashMap::new()], warnings: vec![] };
+    data.iter().for_each(|datum| scopes.walk(datum));
+    scopes.warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{ErrorKind, ReportStyle, Severity};
+    use crate::parser::SrcInfo;
+    use super::shadowing_warnings;
+
+    #[test]
+    fn shadowing_parameter() {
+        let src = SrcInfo::new("test", "($define x 1)\n($define (f y) ($lambda (x) (gcd x y)))");
+        let warnings = shadowing_warnings(&src);
+        assert_eq!(warnings.len(), 1);
+        let warning = &warnings[0];
+        assert_eq!((warning.kind(), warning.severity()), (ErrorKind::Shadowing, Severity::Warning));
+        assert_eq!(warning.span(), &(39..40));
+        let mut report = vec![];
+        warning.write_report(&src, &ReportStyle::default(), &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.starts_with("\u{1b}[33mwarning"));
+        assert!(report.contains("[W01]"));
+        assert!(report.contains("This 'x' hides it."));
+        assert!(report.contains("The outer 'x' is bound here."));
+
+        // Neither redefining in the same scope, nor binding in separate scopes, shadows.
+        for src in ["($define x 1) ($define x 2)", "($define (f x) ($define x 2) x)",
+            "($lambda (x) x) ($lambda (x) x)", "($define (f x) x) ($define (g x) x)", "#;($define x 1) ($lambda (x) x)"] {
+            assert!(shadowing_warnings(&SrcInfo::new("test", src)).is_empty(), "'{src}' shadows.");
+        }
+        for src in ["($lambda (x) ($lambda x x))", "($lambda (x . y) ($define (g y) y))", "($define (f f) f)"] {
+            assert_eq!(shadowing_warnings(&SrcInfo::new("test", src)).len(), 1, "'{src}' doesn't shadow.");
+        }
+    }
+}
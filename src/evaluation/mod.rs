@@ -1,7 +1,13 @@
 mod combiner;
+mod condition;
+mod hash_table;
 mod term;
 mod context;
+mod expander;
+mod port;
+mod record;
+mod lint;
+pub mod prelude;
 
-pub use combiner::*;
 pub use term::*;
 pub use context::*;
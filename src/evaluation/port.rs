@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::{Error, ErrorKind};
+use super::context::{Sink, Source};
+
+/// What a port reads from or writes to.
+#[derive(Debug)]
+pub enum PortKind {
+    TextInput(Source),
+    TextOutput(Sink),
+    /// Bytes read from a file, which can't be read as characters.
+    BinaryInput(Source),
+    /// Bytes written to a file, which can't be written as characters.
+    BinaryOutput(Sink)
+}
+
+#[derive(Debug)]
+pub struct PortState {
+    kind: PortKind,
+    /// Set once the port is closed, after which it can't be read or written.
+    closed: bool
+}
+
+/// A port, shared by every alias of it.
+#[derive(Debug, Clone)]
+pub struct Port(Rc<RefCell<PortState>>);
+
+impl Port {
+    fn new(kind: PortKind) -> Self {
+        Self(Rc::new(RefCell::new(PortState { kind, closed: false })))
+    }
+
+    pub fn input(source: Source) -> Self {
+        Self::new(PortKind::TextInput(source))
+    }
+
+    pub fn output(sink: Sink) -> Self {
+        Self::new(PortKind::TextOutput(sink))
+    }
+
+    pub fn binary_input(source: Source) -> Self {
+        Self::new(PortKind::BinaryInput(source))
+    }
+
+    pub fn binary_output(sink: Sink) -> Self {
+        Self::new(PortKind::BinaryOutput(sink))
+    }
+
+    pub fn is_input(&self) -> bool {
+        matches!(self.0.borrow().kind, PortKind::TextInput(_) | PortKind::BinaryInput(_))
+    }
+
+    pub fn is_output(&self) -> bool {
+        matches!(self.0.borrow().kind, PortKind::TextOutput(_) | PortKind::BinaryOutput(_))
+    }
+
+    pub fn is_binary(&self) -> bool {
+        matches!(self.0.borrow().kind, PortKind::BinaryInput(_) | PortKind::BinaryOutput(_))
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.0.borrow().closed
+    }
+
+    /// Close the port, which may be closed already.
+    pub fn close(&self) {
+        self.0.borrow_mut().closed = true;
+    }
+
+    /// The source of an open input port.
+    pub fn source(&self) -> Option<Result<Source, Error>> {
+        match self.0.borrow().kind {
+            PortKind::TextInput(ref source) => Some(self.check_open().map(|_| source.clone())),
+            _ => None
+        }
+    }
+
+    /// The sink of an open output port.
+    pub fn sink(&self) -> Option<Result<Sink, Error>> {
+        match self.0.borrow().kind {
+            PortKind::TextOutput(ref sink) => Some(self.check_open().map(|_| sink.clone())),
+            _ => None
+        }
+    }
+
+    /// The source of an open binary input port.
+    pub fn binary_source(&self) -> Option<Result<Source, Error>> {
+        match self.0.borrow().kind {
+            PortKind::BinaryInput(ref source) => Some(self.check_open().map(|_| source.clone())),
+            _ => None
+        }
+    }
+
+    /// The sink of an open binary output port.
+    pub fn binary_sink(&self) -> Option<Result<Sink, Error>> {
+        match self.0.borrow().kind {
+            PortKind::BinaryOutput(ref sink) => Some(self.check_open().map(|_| sink.clone())),
+            _ => None
+        }
+    }
+
+    /// The address of the state of the port, shared by its aliases.
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    fn check_open(&self) -> Result<(), Error> {
+        if self.is_open() { Ok(()) } else {
+            Err(Error::new(ErrorKind::InvalidArgument).with_message("Port is closed".to_string()))
+        }
+    }
+}
+
+impl PartialEq for Port {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::error::{Error, ErrorKind};
+use super::super::context::Context;
+use super::super::term::{MultipleValues, Term, TermValue};
+use super::{Arity::*, Builtin};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("not", Exactly(1), "Whether the value is `#f`.", not),
+    Builtin::new("boolean=?", AtLeast(2), "Whether the booleans are all true or all false.", boolean_eq),
+    Builtin::new("eq?", Exactly(2), "Whether the values are the same object.", eq),
+    Builtin::new("eqv?", Exactly(2), "Whether the values are the same object.", eqv),
+    Builtin::new("equal?", Exactly(2), "Whether the values have the same structure, comparing the elements with `eqv?`.", equal),
+];
+
+fn expect_bool(name: &str, arg: &Term) -> Result<bool, Error> {
+    match arg.value {
+        TermValue::Bool(b) => Ok(b),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a boolean, but got the {} {value}.", value.type_name())))
+    }
+}
+
+// Only `#f` is false, so `(not 0)` and `(not ())` are `#f` as well.
+fn not(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(args[0].is_false().into())
+}
+
+fn boolean_eq(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    // Check the types of all arguments, even after a difference is found.
+    let booleans = args.iter()
+        .map(|arg| expect_bool("boolean=?", arg))
+        .collect::<Result<Vec<bool>, Error>>()?;
+    Ok(booleans.windows(2).all(|pair| pair[0] == pair[1]).into())
+}
+
+/// Pairs, vectors, bytevectors, procedures and ports are the same only if they are the same object, while the other
+/// values are the same if they are equal, as they can't be told apart by mutation.
+pub(super) fn is_eqv(a: &Term, b: &Term) -> bool {
+    match (&a.value, &b.value) {
+        (TermValue::Pair(a), TermValue::Pair(b)) => Rc::ptr_eq(a, b),
+        (TermValue::Vector(a), TermValue::Vector(b)) => Rc::ptr_eq(a, b),
+        (TermValue::Bytevector(a), TermValue::Bytevector(b)) => Rc::ptr_eq(a, b),
+        (TermValue::ErrorObject(a), TermValue::ErrorObject(b)) => Rc::ptr_eq(a, b),
+        (TermValue::Condition(a), TermValue::Condition(b)) => Rc::ptr_eq(a, b),
+        (a, b) => a == b
+    }
+}
+
+/// Compare pairs, vectors, bytevectors, maps and multiple values element by element, iteratively, so that long lists
+/// don't overflow the stack. Two objects compared again are taken to be equal, as any difference between
+/// them is found by the first comparison, so that circular lists and vectors are compared in finite time.
+pub(super) fn is_equal(a: &Term, b: &Term) -> bool {
+    let mut pending = vec![(a.clone(), b.clone())];
+    let mut compared = HashSet::new();
+    while let Some((a, b)) = pending.pop() {
+        match (&a.value, &b.value) {
+            (TermValue::Pair(x), TermValue::Pair(y)) if !compared.insert((x.address(), y.address())) => (),
+            (TermValue::Vector(x), TermValue::Vector(y)) if !compared.insert((x.address(), y.address())) => (),
+            (TermValue::Pair(x), TermValue::Pair(y)) if !Rc::ptr_eq(x, y) => {
+                let (x, y) = (x.borrow(), y.borrow());
+                pending.push((x.1.clone(), y.1.clone()));
+                pending.push((x.0.clone(), y.0.clone()));
+            }
+            (TermValue::Values(MultipleValues(x)), TermValue::Values(MultipleValues(y))) if x.len() == y.len() =>
+                pending.extend(x.iter().cloned().zip(y.iter().cloned()).rev()),
+            (TermValue::Vector(x), TermValue::Vector(y)) if !Rc::ptr_eq(x, y) => {
+                let (x, y) = (x.borrow(), y.borrow());
+                if x.len() != y.len() { return false }
+                pending.extend(x.iter().cloned().zip(y.iter().cloned()).rev());
+            }
+            (TermValue::Bytevector(x), TermValue::Bytevector(y)) => if x != y { return false },
+            // Maps are equal if they have the same keys, whatever their order, with equal values.
+            (TermValue::Map(x), TermValue::Map(y)) if x != y => {
+                if x.len() != y.len() { return false }
+                for (key, value) in x.entries() {
+                    match y.get(&key) {
+                        Some(other) => pending.push((value, other)),
+                        None => return false
+                    }
+                }
+            }
+            _ if is_eqv(&a, &b) => (),
+            _ => return false
+        }
+    }
+    true
+}
+
+// There are no boxed numbers or mutable strings, so `eq?` can't tell more apart than `eqv?`.
+fn eq(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(is_eqv(&args[0], &args[1]).into())
+}
+
+fn eqv(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(is_eqv(&args[0], &args[1]).into())
+}
+
+fn equal(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(is_equal(&args[0], &args[1]).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::{Term, TermValue};
+    use super::super::tests::{boolean, eval};
+
+    #[test]
+    fn not_only_negates_false() {
+        assert!(boolean("(not #f)"));
+        assert!(!boolean("(not #t)"));
+        assert!(!boolean("(not ())"));
+        assert!(!boolean("(not 0)"));
+        assert!(!boolean(r#"(not "")"#));
+        assert!(boolean("(not (not 0))"));
+        assert_eq!(eval("(not)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(not #t #f)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        // `$if` follows the same rule.
+        assert!(boolean("($if 0 #t #f)"));
+        assert!(boolean(r#"($if "" #t #f)"#));
+        assert!(!boolean("($if #f #t #f)"));
+    }
+
+    #[test]
+    fn boolean_equality() {
+        assert!(boolean("(boolean=? #t #t #t)"));
+        assert!(boolean("(boolean=? #f #false)"));
+        assert!(!boolean("(boolean=? #t #f)"));
+        assert!(!boolean("(boolean=? #t #t #f)"));
+        assert_eq!(eval("(boolean=? #t)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(boolean=? #t 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(boolean=? #t #f 0)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn equivalence() {
+        assert!(boolean("(eq? 'a' 'a')"));
+        assert!(boolean("(eqv? 2 2)"));
+        assert!(!boolean("(eqv? 2 2.0)"));
+        assert!(boolean("(eq? () ())"));
+        assert!(boolean("(eq? car car)"));
+        assert!(!boolean("(eq? (list 1) (list 1))"));
+        assert!(boolean("(($lambda (p) (eq? p p)) (list 1))"));
+        assert!(!boolean("(eqv? ($lambda () 1) ($lambda () 1))"));
+        assert!(boolean("(equal? (list 1 (list 2 'b') 3) (list 1 (list 2 'b') 3))"));
+        assert!(!boolean("(equal? (list 1 2) (list 1 2 3))"));
+        assert!(!boolean("(equal? (cons 1 2) (cons 1 2.0))"));
+        assert!(boolean("(equal? (iota 1000) (iota 1000))"));
+        // Vectors which contain themselves are compared without looping.
+        let vectors = "($define u (vector 1 0)) (vector-set! u 1 u) ($define v (vector 1 0)) (vector-set! v 1 v)
+            ($define w (vector 2 0)) (vector-set! w 1 w)";
+        assert!(boolean(&format!("{vectors} (equal? u v)")));
+        assert!(boolean(&format!("{vectors} (equal? u (vector 1 v))")));
+        assert!(!boolean(&format!("{vectors} (equal? u w)")));
+        // An error object is the same object as its aliases, and only as them.
+        let object = Term::error_object("x", vec![]);
+        assert!(super::is_eqv(&object, &object.clone()));
+        assert!(!super::is_eqv(&object, &Term::error_object("x", vec![])));
+        assert_eq!(eval("(equal? 1)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn equal_circular_lists() {
+        // A list of the numbers whose last cdr is its first pair.
+        let circular = |numbers: &[i64]| {
+            let list = numbers.iter().rev().fold(Term::nil(), |tail, &n| Term::cons(Term::int(n), tail));
+            let mut last = list.clone();
+            while let TermValue::Pair(pair) = last.value.clone() {
+                match pair.borrow().1.value {
+                    TermValue::Nil => break,
+                    _ => last = pair.borrow().1.clone()
+                }
+            }
+            let TermValue::Pair(ref pair) = last.value else { unreachable!() };
+            pair.borrow_mut().1 = list.clone();
+            list
+        };
+        assert!(super::is_equal(&circular(&[1, 2]), &circular(&[1, 2])));
+        // The same cycle unrolled twice is the same infinite list.
+        assert!(super::is_equal(&circular(&[1, 2]), &circular(&[1, 2, 1, 2])));
+        assert!(!super::is_equal(&circular(&[1, 2]), &circular(&[1, 3])));
+        let list = Term::cons(Term::int(1), Term::cons(Term::int(2), Term::nil()));
+        assert!(!super::is_equal(&circular(&[1, 2]), &list));
+    }
+}
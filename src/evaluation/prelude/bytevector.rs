@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::{Error, ErrorKind};
+use super::super::context::Context;
+use super::super::term::{BytevectorValue, Term, TermValue, UnitValue};
+use super::vector::expect_index;
+use super::{Arity::*, Builtin, allocation_failed, expect_count, expect_int};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("bytevector", AtLeast(0), "A new bytevector of the bytes.", bytevector),
+    Builtin::new("make-bytevector", Between(1, 2), "A new bytevector of the count copies of the byte, or of 0.", make_bytevector),
+    Builtin::new("bytevector?", Exactly(1), "Whether the value is a bytevector.", is_bytevector),
+    Builtin::new("bytevector-length", Exactly(1), "The number of bytes of the bytevector.", bytevector_length),
+    Builtin::new("bytevector-u8-ref", Exactly(2), "The byte of the bytevector at the index.", bytevector_u8_ref),
+    Builtin::new("bytevector-u8-set!", Exactly(3), "Replace the byte of the bytevector at the index.", bytevector_u8_set),
+];
+
+pub(super) fn expect_bytevector<'a>(name: &str, arg: &'a Term) -> Result<&'a BytevectorValue, Error> {
+    match arg.value {
+        TermValue::Bytevector(ref bytes) => Ok(bytes),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a bytevector, but got the {} {value}.", value.type_name())))
+    }
+}
+
+pub(super) fn expect_byte(name: &str, arg: &Term) -> Result<u8, Error> {
+    let byte = expect_int(name, arg)?;
+    u8::try_from(byte).map_err(|_| Error::new(ErrorKind::InvalidArgument)
+        .with_message(format!("'{name}' expects a byte from 0 to 255, but got {byte}.")))
+}
+
+pub(super) fn bytevector_of(bytes: Vec<u8>) -> Term {
+    Term::from(Rc::new(RefCell::new(bytes)))
+}
+
+fn bytevector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let bytes = args.iter()
+        .map(|arg| expect_byte("bytevector", arg))
+        .collect::<Result<Vec<u8>, Error>>()?;
+    Ok(bytevector_of(bytes))
+}
+
+fn make_bytevector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let count = expect_count("make-bytevector", &args[0])?;
+    let fill = args.get(1).map_or(Ok(0), |arg| expect_byte("make-bytevector", arg))?;
+    let mut bytes = vec![];
+    bytes.try_reserve_exact(count).map_err(|_| allocation_failed("make-bytevector", count))?;
+    bytes.resize(count, fill);
+    Ok(bytevector_of(bytes))
+}
+
+fn is_bytevector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::Bytevector(_)).into())
+}
+
+fn bytevector_length(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::int(expect_bytevector("bytevector-length", &args[0])?.borrow().len() as i64))
+}
+
+fn bytevector_u8_ref(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let bytes = expect_bytevector("bytevector-u8-ref", &args[0])?.borrow();
+    let index = expect_index("bytevector-u8-ref", &args[1], bytes.len())?;
+    Ok(Term::int(bytes[index] as i64))
+}
+
+fn bytevector_u8_set(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let byte = expect_byte("bytevector-u8-set!", &args[2])?;
+    let mut bytes = expect_bytevector("bytevector-u8-set!", &args[0])?.borrow_mut();
+    let index = expect_index("bytevector-u8-set!", &args[1], bytes.len())?;
+    bytes[index] = byte;
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::tests::eval;
+
+    #[test]
+    fn bytevector_basics() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(bytevector 1 2 255)"), "#u8(1 2 255)");
+        assert_eq!(text("(bytevector)"), "#u8()");
+        assert_eq!(text("(make-bytevector 3 7)"), "#u8(7 7 7)");
+        assert_eq!(text("(bytevector-length (make-bytevector 4))"), "4");
+        assert_eq!(text("(list (bytevector? (bytevector)) (bytevector? (vector)) (type-of (bytevector)))"), "(#t #f bytevector)");
+        assert_eq!(text("(bytevector-u8-ref (bytevector 1 2 3) 2)"), "3");
+        // A bytevector is changed in place, for every alias of it.
+        assert_eq!(text("($define b (bytevector 1 2)) ($define c b) (bytevector-u8-set! c 0 9) b"), "#u8(9 2)");
+        assert_eq!(text("(list (equal? (bytevector 1) (bytevector 1)) (eqv? (bytevector 1) (bytevector 1)))"), "(#t #f)");
+
+        assert_eq!(eval("(bytevector 256)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(make-bytevector 2 -1)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(make-bytevector 100000000000000000)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(bytevector-u8-ref (bytevector 1) 1)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(bytevector-u8-set! (bytevector 1) 0 300)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(bytevector-length (vector))").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+}
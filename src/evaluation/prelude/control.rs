@@ -0,0 +1,611 @@
+use std::collections::LinkedList;
+
+use crate::error::{Error, ErrorKind};
+use crate::syntax::{Node, Symbol};
+use super::super::combiner::Parameter;
+use super::super::context::Context;
+use super::super::expander::MacroTransformer;
+use super::super::term::{MultipleValues, Term, TermValue};
+use super::{Arity::*, Builtin, invalid_form};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("values", AtLeast(0), "Return the arguments as multiple values.", values),
+    Builtin::new("call-with-values", Exactly(2),
+        "Call the consumer with the values returned by calling the producer.", call_with_values),
+    Builtin::new("dynamic-wind", Exactly(3),
+        "Call the thunks in order, calling the last one even if the second one fails.", dynamic_wind),
+    Builtin::new("make-parameter", Between(1, 2),
+        "A parameter bound to the value, which is passed through the converter first if there is one.", make_parameter),
+    Builtin::new("with-parameters", AtLeast(1),
+        "Call the thunk with each parameter bound to the converted value after it, restoring them afterwards.", with_parameters),
+    Builtin::new("type-of", Exactly(1), "The name of the type of the value, as a symbol.", type_of),
+];
+
+/// Run `during`, and then `after` whether it succeeds or not, returning the result of `during`
+/// unless `after` fails.
+pub(super) fn wind<T>(ctx: &mut Context, during: impl FnOnce(&mut Context) -> Result<T, Error>,
+    after: impl FnOnce(&mut Context) -> Result<(), Error>) -> Result<T, Error> {
+    let result = during(ctx);
+    after(ctx)?;
+    result
+}
+
+fn dynamic_wind(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let (before, thunk, after) = (args.next().unwrap(), args.next().unwrap(), args.next().unwrap());
+    ctx.apply(before, LinkedList::new())?;
+    wind(ctx,
+        |ctx| ctx.apply(thunk, LinkedList::new()),
+        |ctx| ctx.apply(after, LinkedList::new()).map(drop))
+}
+
+fn make_parameter(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let value = args.next().unwrap();
+    let converter = args.next();
+    let value = match converter {
+        Some(ref converter) => ctx.apply(converter.clone(), LinkedList::from([value]))?,
+        None => value
+    };
+    Ok(Term::from(Parameter::new(value, converter)))
+}
+
+/// `(with-parameters thunk parameter value...)`, which is what `parameterize` expands to.
+/// Every value is converted before any parameter is bound, so that a failing converter leaves
+/// all of them as they were.
+fn with_parameters(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let name = "with-parameters";
+    if args.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::ArityMismatch)
+            .with_message(format!("'{name}' expects a thunk followed by pairs of a parameter and a value.")))
+    }
+    let mut args = args.iter().cloned();
+    let thunk = args.next().unwrap();
+    let mut bindings = vec![];
+    while let (Some(parameter), Some(value)) = (args.next(), args.next()) {
+        let parameter = match parameter.value {
+            TermValue::Parameter(parameter) => parameter,
+            ref value => return Err(Error::new(ErrorKind::TypeMismatch)
+                .with_message(format!("'{name}' expects a parameter, but got the {} {value}.", value.type_name())))
+        };
+        let value = match parameter.converter() {
+            Some(converter) => ctx.apply(converter.clone(), LinkedList::from([value]))?,
+            None => value
+        };
+        bindings.push((parameter, value));
+    }
+    let saved: Vec<Term> = bindings.iter().map(|(parameter, value)| parameter.replace(value.clone())).collect();
+    wind(ctx,
+        |ctx| ctx.apply(thunk, LinkedList::new()),
+        // Restore in reverse, so that a parameter bound twice gets back its value from before both.
+        |_| {
+            bindings.iter().zip(saved).rev().for_each(|((parameter, _), value)| drop(parameter.replace(value)));
+            Ok(())
+        })
+}
+
+fn type_of(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::from(Symbol::from(args[0].value.type_name())))
+}
+
+pub(super) const MACROS: &[(&str, MacroTransformer)] = &[
+    ("with-values", with_values),
+    ("receive", receive),
+    ("let", let_form),
+    ("do", do_form),
+    ("case-lambda", case_lambda),
+    ("letrec*", letrec_star),
+    ("let-values", let_values),
+    ("let-values*", let_values_star),
+    ("parameterize", parameterize),
+    ("when", when),
+    ("unless", unless),
+];
+
+fn values(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args: Vec<Term> = args.to_vec();
+    if args.len() == 1 {
+        Ok(args.pop().unwrap())
+    } else {
+        Ok(Term::from(MultipleValues(args)))
+    }
+}
+
+fn call_with_values(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let (producer, consumer) = (args.next().unwrap(), args.next().unwrap());
+    let produced = ctx.apply(producer, LinkedList::new())?;
+    let values = match produced.value {
+        TermValue::Values(MultipleValues(values)) => values.into_iter().collect(),
+        _ => LinkedList::from([produced])
+    };
+    ctx.apply(consumer, values)
+}
+
+/// `(with-values producer consumer)` => `(call-with-values producer consumer)`
+fn with_values(form: &Node) -> Result<Node, Error> {
+    match form.as_ref().as_slice() {
+        [_, producer, consumer] =>
+            Ok(Node::list(vec!["call-with-values".into(), producer.clone(), consumer.clone()])),
+        _ => Err(invalid_form("with-values", "(with-values producer consumer)"))
+    }
+}
+
+/// `(receive formals expression body...)`
+/// => `(call-with-values ($lambda () expression) ($lambda formals body...))`
+fn receive(form: &Node) -> Result<Node, Error> {
+    match form.as_ref().as_slice() {
+        [_, formals, expression, body @ ..] if !body.is_empty() => {
+            let mut consumer = vec!["$lambda".into(), formals.clone()];
+            consumer.extend(body.iter().cloned());
+            Ok(Node::list(vec![
+                "call-with-values".into(),
+                Node::list(vec!["$lambda".into(), Node::list(vec![]), expression.clone()]),
+                Node::list(consumer)
+            ]))
+        }
+        _ => Err(invalid_form("receive", "(receive formals expression body...)"))
+    }
+}
+
+/// `(let ((name expression)...) body...)` => `(($lambda (name...) body...) expression...)`, and
+/// the named `(let loop ((name expression)...) body...)`
+/// => `((($lambda () ($define loop ($lambda (name...) body...)) loop)) expression...)`,
+/// where the expressions are outside the scope of `loop`, while its body can call it.
+fn let_form(form: &Node) -> Result<Node, Error> {
+    let syntax = "(let [name] ((name expression)...) body...)";
+    let (name, bindings, body) = match form.as_ref().as_slice() {
+        [_, name @ Node::Symbol(_), Node::List(bindings, _), body @ ..] => (Some(name), bindings, body),
+        [_, Node::List(bindings, _), body @ ..] => (None, bindings, body),
+        _ => return Err(invalid_form("let", syntax))
+    };
+    if body.is_empty() { return Err(invalid_form("let", syntax)) }
+    let mut formals = vec![];
+    let mut call = vec![];
+    for binding in bindings {
+        let binding = match binding {
+            Node::List(binding, _) => binding.as_slice(),
+            _ => &[]
+        };
+        match binding {
+            [formal @ Node::Symbol(_), expression] => {
+                formals.push(formal.clone());
+                call.push(expression.clone());
+            }
+            _ => return Err(invalid_form("let", syntax))
+        }
+    }
+    let mut procedure = vec!["$lambda".into(), Node::list(formals)];
+    procedure.extend(body.iter().cloned());
+    let procedure = match name {
+        Some(name) => Node::list(vec![Node::list(vec![
+            "$lambda".into(),
+            Node::list(vec![]),
+            Node::list(vec!["$define".into(), name.clone(), Node::list(procedure)]),
+            name.clone()
+        ])]),
+        None => Node::list(procedure)
+    };
+    call.insert(0, procedure);
+    Ok(Node::list(call))
+}
+
+/// `(do ((name init [step])...) (test result...) command...)`
+/// => `($do ((name init [step])...) (test result...) command...)`, which the context iterates
+/// directly, instead of by calling a named `let` for each iteration.
+fn do_form(form: &Node) -> Result<Node, Error> {
+    match form.as_ref().as_slice() {
+        [_, bindings @ Node::List(..), test @ Node::List(clause, _), commands @ ..] if !clause.is_empty() => {
+            let mut direct = vec!["$do".into(), bindings.clone(), test.clone()];
+            direct.extend(commands.iter().cloned());
+            Ok(Node::list(direct))
+        }
+        _ => Err(invalid_form("do", "(do ((name init [step])...) (test result...) command...)"))
+    }
+}
+
+/// `(case-lambda (formals body...)...)` => `($case-lambda (formals body...)...)`
+fn case_lambda(form: &Node) -> Result<Node, Error> {
+    let clauses = &form.as_ref()[1..];
+    if clauses.iter().any(|clause| !matches!(clause, Node::List(clause, _) if clause.len() >= 2)) {
+        return Err(invalid_form("case-lambda", "(case-lambda (formals body...)...)"))
+    }
+    let mut direct = vec!["$case-lambda".into()];
+    direct.extend(clauses.iter().cloned());
+    Ok(Node::list(direct))
+}
+
+/// `(letrec* ((name expression)...) body...)`
+/// => `(($lambda () ($define name)... ($define name expression)... body...))`, where every name
+/// is in scope of every expression, which are evaluated in order, and a name can't be used
+/// before its own expression has been.
+fn letrec_star(form: &Node) -> Result<Node, Error> {
+    let syntax = "(letrec* ((name expression)...) body...)";
+    let (bindings, body) = match form.as_ref().as_slice() {
+        [_, Node::List(bindings, _), body @ ..] if !body.is_empty() => (bindings, body),
+        _ => return Err(invalid_form("letrec*", syntax))
+    };
+    let mut declarations = vec![];
+    let mut definitions = vec![];
+    for binding in bindings {
+        match binding {
+            Node::List(binding, _) if binding.len() == 2 && matches!(binding[0], Node::Symbol(_)) => {
+                declarations.push(Node::list(vec!["$define".into(), binding[0].clone()]));
+                definitions.push(Node::list(vec!["$define".into(), binding[0].clone(), binding[1].clone()]));
+            }
+            _ => return Err(invalid_form("letrec*", syntax))
+        }
+    }
+    let mut thunk = vec!["$lambda".into(), Node::list(vec![])];
+    thunk.extend(declarations);
+    thunk.extend(definitions);
+    thunk.extend(body.iter().cloned());
+    Ok(Node::list(vec![Node::list(thunk)]))
+}
+
+/// `(let-values ((formals expression)...) body...)`
+/// => `(call-with-values ($lambda () expression_n) (... (call-with-values ($lambda () expression_1)
+/// ($lambda formals_1 ... ($lambda formals_n body...)))))`, where each consumer returns the
+/// next one, so that the expressions are evaluated in order, all in the outer scope.
+fn let_values(form: &Node) -> Result<Node, Error> {
+    let syntax = "(let-values ((formals expression)...) body...)";
+    let (bindings, body) = match form.as_ref().as_slice() {
+        [_, Node::List(bindings, _), body @ ..] if !body.is_empty() => (bindings, body),
+        _ => return Err(invalid_form("let-values", syntax))
+    };
+    let bindings = bindings.iter()
+        .map(|binding| match binding {
+            Node::List(binding, _) if binding.len() == 2 => Ok((&binding[0], &binding[1])),
+            _ => Err(invalid_form("let-values", syntax))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut consumer = vec!["$lambda".into(), Node::list(vec![])];
+    consumer.extend(body.iter().cloned());
+    if bindings.is_empty() { return Ok(Node::list(vec![Node::list(consumer)])) }
+    consumer[1] = bindings.last().unwrap().0.clone();
+    let mut consumer = Node::list(consumer);
+    for (formals, _) in bindings.iter().rev().skip(1) {
+        consumer = Node::list(vec!["$lambda".into(), (*formals).clone(), consumer]);
+    }
+    Ok(bindings.iter().fold(consumer, |consumer, (_, expression)| Node::list(vec![
+        "call-with-values".into(),
+        Node::list(vec!["$lambda".into(), Node::list(vec![]), (*expression).clone()]),
+        consumer
+    ])))
+}
+
+/// `(let-values* ((formals expression) rest...) body...)`
+/// => `(receive formals expression (let-values* (rest...) body...))`,
+/// so that each expression is in the scope of the formals before it.
+fn let_values_star(form: &Node) -> Result<Node, Error> {
+    let syntax = "(let-values* ((formals expression)...) body...)";
+    match form.as_ref().as_slice() {
+        [_, Node::List(bindings, _), body @ ..] if !body.is_empty() => match bindings.split_first() {
+            None => {
+                let mut thunk = vec!["$lambda".into(), Node::list(vec![])];
+                thunk.extend(body.iter().cloned());
+                Ok(Node::list(vec![Node::list(thunk)]))
+            }
+            Some((Node::List(binding, _), rest)) if binding.len() == 2 => {
+                let mut inner = vec!["let-values*".into(), Node::list(rest.to_vec())];
+                inner.extend(body.iter().cloned());
+                Ok(Node::list(vec!["receive".into(), binding[0].clone(), binding[1].clone(), Node::list(inner)]))
+            }
+            Some(_) => Err(invalid_form("let-values*", syntax))
+        },
+        _ => Err(invalid_form("let-values*", syntax))
+    }
+}
+
+/// The test of `(name test body...)`, and the call of a thunk of the body, which is in tail position.
+fn guarded_body(name: &str, form: &Node) -> Result<(Node, Node), Error> {
+    match form.as_ref().as_slice() {
+        [_, test, body @ ..] if !body.is_empty() => {
+            let mut thunk = vec!["$lambda".into(), Node::list(vec![])];
+            thunk.extend(body.iter().cloned());
+            Ok((test.clone(), Node::list(vec![Node::list(thunk)])))
+        }
+        _ => Err(invalid_form(name, &format!("({name} test body...)")))
+    }
+}
+
+/// `(when test body...)` => `($if test (($lambda () body...)))`
+fn when(form: &Node) -> Result<Node, Error> {
+    let (test, body) = guarded_body("when", form)?;
+    Ok(Node::list(vec!["$if".into(), test, body]))
+}
+
+/// `(unless test body...)` => `($if test ($if #f #f) (($lambda () body...)))`,
+/// where `($if #f #f)` is the unspecified value `when` has for a false test.
+fn unless(form: &Node) -> Result<Node, Error> {
+    let (test, body) = guarded_body("unless", form)?;
+    let unspecified = Node::list(vec!["$if".into(), false.into(), false.into()]);
+    Ok(Node::list(vec!["$if".into(), test, unspecified, body]))
+}
+
+/// `(parameterize ((parameter value)...) body...)`
+/// => `(with-parameters ($lambda () body...) parameter value...)`
+fn parameterize(form: &Node) -> Result<Node, Error> {
+    let syntax = "(parameterize ((parameter value)...) body...)";
+    match form.as_ref().as_slice() {
+        [_, Node::List(bindings, _), body @ ..] if !body.is_empty() => {
+            let mut thunk = vec!["$lambda".into(), Node::list(vec![])];
+            thunk.extend(body.iter().cloned());
+            let mut call = vec!["with-parameters".into(), Node::list(thunk)];
+            for binding in bindings {
+                match binding {
+                    Node::List(binding, _) if binding.len() == 2 => call.extend(binding.iter().cloned()),
+                    _ => return Err(invalid_form("parameterize", syntax))
+                }
+            }
+            Ok(Node::list(call))
+        }
+        _ => Err(invalid_form("parameterize", syntax))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::{Term, UnitValue};
+    use super::super::tests::{eval, eval_with_depth, eval_with_output};
+
+    fn list(items: Vec<Term>) -> Term {
+        items.into_iter().rev().fold(Term::nil(), |tail, head| Term::cons(head, tail))
+    }
+
+    #[test]
+    fn dynamic_wind() {
+        assert_eq!(eval("(dynamic-wind ($lambda () 1) ($lambda () 2) ($lambda () 3))").unwrap(), Term::int(2));
+        // The after thunk runs even when the thunk fails, which it shows by failing itself.
+        let err = eval(r#"(dynamic-wind ($lambda () 1) ($lambda () (car 1)) ($lambda () (error "after")))"#).unwrap_err();
+        assert_eq!(err.message(), "after");
+        let err = eval(r#"(dynamic-wind ($lambda () (error "before")) ($lambda () (car 1)) ($lambda () 3))"#).unwrap_err();
+        assert_eq!(err.message(), "before");
+    }
+
+    #[test]
+    fn type_of() {
+        let type_of = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(type_of("(type-of 1)"), "int");
+        assert_eq!(type_of("(type-of 1.5)"), "float");
+        assert_eq!(type_of("(type-of (/ 1 2))"), "rational");
+        assert_eq!(type_of(r#"(type-of "a")"#), "string");
+        assert_eq!(type_of("(type-of #f)"), "boolean");
+        assert_eq!(type_of("(type-of +)"), "procedure");
+        assert_eq!(type_of("(type-of gcd)"), "procedure");
+        assert_eq!(type_of("(type-of ($lambda (x) x))"), "procedure");
+        assert_eq!(type_of("(type-of (cons 1 2))"), "pair");
+        assert_eq!(type_of("(type-of ())"), "nil");
+        assert_eq!(type_of("(type-of (type-of 1))"), "symbol");
+        assert_eq!(type_of("(type-of (gcd 4 6))"), "int");
+        assert_eq!(eval(r#"(car "a")"#).unwrap_err().message(), r#"'car' expects a pair, but got the string "a"."#);
+    }
+
+    #[test]
+    fn call_with_values() {
+        assert_eq!(eval("(call-with-values ($lambda () (values 1 2)) list)").unwrap(),
+            list(vec![Term::int(1), Term::int(2)]));
+        assert_eq!(eval("(call-with-values ($lambda () 1) list)").unwrap(), list(vec![Term::int(1)]));
+        assert_eq!(eval("(call-with-values ($lambda () (values)) list)").unwrap(), Term::nil());
+    }
+
+    #[test]
+    fn with_values_and_receive() {
+        assert_eq!(eval("(with-values ($lambda () (values 1 2)) list)").unwrap(),
+            list(vec![Term::int(1), Term::int(2)]));
+        assert_eq!(eval("(with-values ($lambda () (values 1 2 3)) ($lambda (a b c) (list c b a)))").unwrap(),
+            list(vec![Term::int(3), Term::int(2), Term::int(1)]));
+        assert_eq!(eval("(receive (q r) (floor/ 7 2) (list r q))").unwrap(),
+            list(vec![Term::int(1), Term::int(3)]));
+        assert_eq!(eval("(receive (a . rest) (values 1 2 3) rest)").unwrap(),
+            list(vec![Term::int(2), Term::int(3)]));
+        assert_eq!(eval("(receive all (values 1 2 3) all)").unwrap(),
+            list(vec![Term::int(1), Term::int(2), Term::int(3)]));
+        assert_eq!(eval("(receive (a b) (values 1 2 3) a)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(receive (a b c) (values 1 2) a)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(receive (a b))").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+    }
+
+    #[test]
+    fn let_values_star() {
+        assert_eq!(eval("(let-values* (((a b) (values 1 2)) ((c d e) (values b a 3))) (list a b c d e))").unwrap(),
+            list(vec![Term::int(1), Term::int(2), Term::int(2), Term::int(1), Term::int(3)]));
+        assert_eq!(eval("(let-values* () 1)").unwrap(), Term::int(1));
+        assert_eq!(eval("(let-values* (((a b c) (values 1 2))) a)").unwrap_err().kind(),
+            ErrorKind::ArityMismatch);
+        assert_eq!(eval("(let-values* ((a)) a)").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+    }
+
+    #[test]
+    fn do_loop() {
+        // Each loop gives the same result as the named `let` it would otherwise be written as.
+        let loops = [
+            ("(do ((i 0 (+ i 1)) (sum 0 (+ sum i))) ((= i 10) sum))",
+                "(let loop ((i 0) (sum 0)) ($if (= i 10) sum (loop (+ i 1) (+ sum i))))"),
+            // The steps refer to each other's values from before the iteration.
+            ("(do ((i 0 (+ i 1)) (a 0 b) (b 1 (+ a b))) ((= i 20) (list a b)))",
+                "(let loop ((i 0) (a 0) (b 1)) ($if (= i 20) (list a b) (loop (+ i 1) b (+ a b))))"),
+            ("(do ((a 1 b) (b 2 a) (n 0 (+ n 1))) ((= n 3) (list a b)))",
+                "(let loop ((a 1) (b 2) (n 0)) ($if (= n 3) (list a b) (loop b a (+ n 1))))"),
+            // A name without a step keeps its value, and one stepped by itself is unchanged.
+            ("(do ((xs (list 1 2 3) (cdr xs)) (k 10) (same 0 same) (acc () (cons (* k (car xs)) acc))) ((null? xs) acc))",
+                "(let loop ((xs (list 1 2 3)) (acc ())) ($if (null? xs) acc (loop (cdr xs) (cons (* 10 (car xs)) acc))))"),
+            // Every closure keeps the bindings of its own iteration.
+            ("(do ((i 0 (+ i 1)) (fs () (cons ($lambda () i) fs))) ((= i 3) (list ((car fs)) ((car (cdr fs))) ((car (cdr (cdr fs)))))))",
+                "(let loop ((i 0) (fs ())) ($if (= i 3) (list ((car fs)) ((car (cdr fs))) ((car (cdr (cdr fs))))) (loop (+ i 1) (cons ($lambda () i) fs))))"),
+        ];
+        for (direct, named_let) in loops {
+            assert_eq!(eval(direct).unwrap(), eval(named_let).unwrap(), "{direct}");
+        }
+        assert_eq!(eval(loops[4].0).unwrap().to_string(), "(2 1 0)");
+
+        let (result, output) = eval_with_output("(do ((i 0 (+ i 1))) ((= i 3)) (display i))");
+        assert_eq!((result.unwrap(), output.as_str()), (Term::from(UnitValue::Ignore), "012"));
+        assert_eq!(eval("(do () (#t 1 2))").unwrap(), Term::int(2));
+        // The inits are in the outer scope, and the names aren't bound after the loop.
+        assert_eq!(eval("($define i 5) (do ((i 0 (+ i 1)) (j i)) ((= i 2) j))").unwrap(), Term::int(5));
+        assert_eq!(eval("($define i 5) (do ((i 0 (+ i 1))) ((= i 2))) i").unwrap(), Term::int(5));
+        assert_eq!(eval("(do ((i 0 (+ i 1))) ((= i 100000) i))").unwrap(), Term::int(100000));
+
+        for src in ["(do ((i 0 1 2)) (#t))", "(do ((1 0)) (#t))", "(do ((i 0)) ())", "(do ((i 0)))", "($do (i) (#t))"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidSyntax, "{src}");
+        }
+    }
+
+    #[test]
+    fn case_lambda() {
+        let f = "($define f (case-lambda (() 'none') ((a) (list 'one' a)) ((a . rest) (list 'many' a rest)))) ";
+        let text = |src: &str| eval(&format!("{f}{src}")).unwrap().to_string();
+        assert_eq!(text("(f)"), r#""none""#);
+        assert_eq!(text("(f 1)"), r#"("one" 1)"#);
+        assert_eq!(text("(f 1 2)"), r#"("many" 1 (2))"#);
+        assert_eq!(text("(f 1 2 3)"), r#"("many" 1 (2 3))"#);
+        assert_eq!(text("(list (type-of f) f)"), "(procedure #<case-lambda () (a) (a . rest)>)");
+        // The first clause which takes the arguments is applied, even if a later one would as well.
+        assert_eq!(eval("((case-lambda (args 'rest') ((a) 'one')) 1)").unwrap().to_string(), r#""rest""#);
+        // A clause in tail position is a tail call, and the clauses see the scope they were made in.
+        assert_eq!(eval("($define n 7) ($define count (case-lambda ((k) (count k 0))
+            ((k acc) ($if (= k 0) (+ acc n) (count (- k 1) (+ acc 1)))))) (count 20000)").unwrap(), Term::int(20007));
+
+        let err = eval("((case-lambda ((a) a) ((a b c . d) a)) 1 2)").unwrap_err();
+        assert_eq!((err.kind(), err.message().as_str()), (ErrorKind::ArityMismatch,
+            "#<case-lambda (a) (a b c . d)> expects 1 or at least 3 argument(s), but got 2."));
+        assert_eq!(eval("((case-lambda))").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("((case-lambda) 1)").unwrap_err().message(),
+            "#<case-lambda> has no clauses, so it takes no arguments, but got 1.");
+        for src in ["(case-lambda ((a)))", "(case-lambda a)", "(case-lambda ((1) 1))", "($case-lambda 1)"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidSyntax, "{src}");
+        }
+    }
+
+    #[test]
+    fn letrec_star() {
+        assert_eq!(eval("(letrec* ((a 1) (b (+ a 1))) b)").unwrap(), Term::int(2));
+        assert_eq!(eval("(letrec* ((even? ($lambda (n) ($if (= n 0) #t (odd? (- n 1)))))
+            (odd? ($lambda (n) ($if (= n 0) #f (even? (- n 1)))))) (list (even? 10) (odd? 10)))").unwrap(),
+            list(vec![Term::boolean(true), Term::boolean(false)]));
+        let (result, output) = eval_with_output("(letrec* ((a (display 1)) (b (display 2))) 0)");
+        assert_eq!((result.unwrap(), output.as_str()), (Term::int(0), "12"));
+
+        // A name is in scope from the start, even over an outer one, but can't be used before its own
+        // expression has been evaluated.
+        let err = eval("($define b 0) (letrec* ((a b) (b 1)) a)").unwrap_err();
+        assert_eq!((err.kind(), err.message().as_str()), (ErrorKind::FreeIdentifier, "'b' is used before it is defined."));
+        assert_eq!(eval("(letrec* ((a a)) a)").unwrap_err().kind(), ErrorKind::FreeIdentifier);
+        assert_eq!(eval("(letrec* ((f ($lambda () b)) (b 2)) (f))").unwrap(), Term::int(2));
+        assert_eq!(eval("(letrec* ((a)) a)").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval("(letrec* ((1 2)) 1)").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+    }
+
+    #[test]
+    fn when_unless() {
+        assert_eq!(eval("(when (= 1 1) 1 2)").unwrap(), Term::int(2));
+        assert_eq!(eval("(when #f 1)").unwrap(), Term::from(UnitValue::Ignore));
+        assert_eq!(eval("(unless #f 1 2)").unwrap(), Term::int(2));
+        assert_eq!(eval("(unless 0 1)").unwrap(), Term::from(UnitValue::Ignore));
+        let (result, output) = eval_with_output("(when #t (display 1) (display 2)) (unless #t (display 3))");
+        assert_eq!((result.unwrap(), output.as_str()), (Term::from(UnitValue::Ignore), "12"));
+
+        // The last expression of the body is in tail position, so the loops don't grow the stack.
+        assert_eq!(eval("(let loop ((n 20000)) (when (> n 0) (loop (- n 1))))").unwrap(), Term::from(UnitValue::Ignore));
+        assert_eq!(eval("(let loop ((n 20000)) (unless (= n 0) (loop (- n 1))))").unwrap(), Term::from(UnitValue::Ignore));
+        assert_eq!(eval("(let loop ((n 20000) (acc 0)) ($if (= n 0) acc (when #t (loop (- n 1) (+ acc 1)))))").unwrap(),
+            Term::int(20000));
+
+        for src in ["(when #t)", "(unless #f)", "(when)"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidSyntax, "{src}");
+        }
+    }
+
+    #[test]
+    fn let_values() {
+        assert_eq!(eval("(let-values (((q r) (divmod 7 2))) (list q r))").unwrap(),
+            list(vec![Term::int(3), Term::int(1)]));
+        assert_eq!(eval("(let-values (((a b) (values 1 2)) ((c . rest) (values 3 4 5)) (all (values))) (list a b c rest all))")
+            .unwrap().to_string(), "(1 2 3 (4 5) ())");
+        assert_eq!(eval("(let-values () 1)").unwrap(), Term::int(1));
+        // The expressions are evaluated in order, in the outer scope rather than each other's.
+        assert_eq!(eval("($define a 1) (let-values (((a) (values 2)) ((b) (values a))) (list a b))").unwrap(),
+            list(vec![Term::int(2), Term::int(1)]));
+        let (result, output) = eval_with_output("(let-values (((a) (display 1)) ((b) (display 2))) 0)");
+        assert_eq!((result.unwrap(), output.as_str()), (Term::int(0), "12"));
+
+        assert_eq!(eval("(let-values (((a b) (values 1 2 3))) a)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(let-values (((a b) (values 1 2))))").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval("(let-values ((a)) a)").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+
+        // Multiple values can't be passed where a single value is expected.
+        let err = eval("(+ (divmod 7 2) 1)").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArityMismatch);
+        assert!(err.message().starts_with("Expected a single value, but got 2 values"), "{}", err.message());
+        assert_eq!(eval("(list (values))").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(($lambda () (list 1 (values 2 3))))").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(list (values 1))").unwrap(), list(vec![Term::int(1)]));
+    }
+
+    #[test]
+    fn let_forms() {
+        assert_eq!(eval("(let ((x 1) (y 2)) (+ x y))").unwrap(), Term::int(3));
+        assert_eq!(eval("(let () 1)").unwrap(), Term::int(1));
+        // The expressions are bound in the outer scope, not in each other's.
+        assert_eq!(eval("($define x 1) (let ((x 2) (y x)) y)").unwrap(), Term::int(1));
+
+        // A named let loops in constant stack space when it calls itself in tail position.
+        let countdown = "(let loop ((n 50000) (acc 0)) ($if (= n 0) acc (loop (- n 1) (+ acc n))))";
+        assert_eq!(eval(countdown).unwrap(), Term::int(1_250_025_000));
+        assert_eq!(eval("(let f ((n 10)) ($if (= n 0) 0 (+ 2 (f (- n 1)))))").unwrap(), Term::int(20));
+        // The expressions are outside the scope of the name.
+        assert_eq!(eval("($define loop 3) (let loop ((n loop)) ($if (= n 0) 'done' (loop (- n 1))))").unwrap(),
+            Term::string("done"));
+
+        for src in ["(let ((x 1)))", "(let (x) x)", "(let ((1 2)) 1)", "(let loop)", "(let ((x 1 2)) x)"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidSyntax, "{src}");
+        }
+    }
+
+    #[test]
+    fn let_named_million() {
+        let countdown = "(let loop ((n 1000000) (acc 0)) ($if (= n 0) acc (loop (- n 1) (+ acc n))))";
+        assert_eq!(eval(countdown).unwrap(), Term::int(500_000_500_000));
+        // A call which isn't in tail position nests the reductions, until they exceed the limit.
+        let recursion = "(let f ((n 100000)) ($if (= n 0) 0 (+ 1 (f (- n 1)))))";
+        assert_eq!(eval_with_depth(recursion, 50).unwrap_err().kind(), ErrorKind::StackOverflow);
+    }
+
+    #[test]
+    #[ignore = "ten million iterations are slow in an unoptimized build; run with --release --ignored"]
+    fn let_named_ten_million() {
+        let countdown = "(let loop ((n 10000000) (acc 0)) ($if (= n 0) acc (loop (- n 1) (+ acc n))))";
+        assert_eq!(eval(countdown).unwrap(), Term::int(50_000_005_000_000));
+    }
+
+    #[test]
+    fn parameters() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(($lambda (p) (p)) (make-parameter 1))"), "1");
+        assert_eq!(text("($define p (make-parameter 1)) (list (parameterize ((p 2)) (p)) (p))"), "(2 1)");
+        assert_eq!(text("($define p (make-parameter 1)) ($define q (make-parameter 'a')) \
+            (parameterize ((p 2) (q 'b')) (parameterize ((p 3)) (list (p) (q))))"), r#"(3 "b")"#);
+        assert_eq!(text("(type-of (make-parameter 1))"), "procedure");
+        assert_eq!(text("($define p (make-parameter 0)) (list (parameterize ((p 1) (p 2)) (p)) (p))"), "(2 0)");
+
+        // The converter applies to the initial value and to every parameterized one.
+        assert_eq!(text("(type-of ((make-parameter 2.0 exact)))"), "int");
+        assert_eq!(text("($define p (make-parameter 0 exact)) (parameterize ((p 4.0)) (p))"), "4");
+        assert_eq!(text("($define p (make-parameter 0 exact)) (parameterize ((p 4.0)) (exact? (p)))"), "#t");
+        assert_eq!(text("((make-parameter 0.5 exact))"), "1/2");
+        assert_eq!(eval("(make-parameter (/ 1.0 0) exact)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+
+        // A failing converter, or a failing body, leaves the parameters as they were.
+        assert_eq!(text("($define p (make-parameter 1)) ($define q (make-parameter 0 exact)) \
+            (guard (e (#t (list (p) (q)))) (parameterize ((p 2) (q (/ 1.0 0))) 'unreached'))"), "(1 0)");
+        assert_eq!(text("($define p (make-parameter 1)) \
+            (guard (e (#t (p))) (parameterize ((p 2)) (raise 'oops')))"), "1");
+
+        assert_eq!(eval("((make-parameter 1) 2)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(parameterize ((1 2)) 3)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(parameterize ((p)) 3)").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval("(parameterize ())").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::LinkedList;
+
+use crate::error::{Error, ErrorKind};
+use crate::syntax::{Node, Symbol};
+use super::super::condition::{ConditionProcedure, ConditionProcedureKind, ConditionType};
+use super::super::context::{Context, Handler};
+use super::super::expander::MacroTransformer;
+use super::super::term::{Term, TermValue, UnitValue};
+use super::list::elements;
+use super::{Arity::*, Builtin, expect_string, invalid_form};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("error", AtLeast(1), "Raise an error object with the message and the irritants.", error),
+    Builtin::new("raise", Exactly(1), "Raise the object to the current exception handler.", raise),
+    Builtin::new("raise-continuable", Exactly(1),
+        "Raise the object to the current exception handler, returning what the handler returns.", raise_continuable),
+    Builtin::new("with-exception-handler", Exactly(2),
+        "Call the thunk with the handler installed for the objects it raises.", with_exception_handler),
+    Builtin::new("call-with-guard", Exactly(2),
+        "Call the thunk, calling the handler on the object it raises instead, which is what `guard` expands to.", call_with_guard),
+    Builtin::new("error-object?", Exactly(1),
+        "Whether the value is an error object, or a condition of a type inheriting from `error-object`.", is_error_object),
+    Builtin::new("error-object-message", Exactly(1), "The message of the error object.", error_object_message),
+    Builtin::new("error-object-irritants", Exactly(1), "The irritants of the error object, as a list.", error_object_irritants),
+    Builtin::new("bind-condition-type", AtLeast(4),
+        "Bind a new condition type and its procedures, which is what `define-condition-type` expands to.", bind_condition_type),
+    Builtin::new("condition-type?", Exactly(2),
+        "Whether the value is a condition of the type, or of a type inheriting from it.", is_condition_type),
+];
+
+fn error(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let message = expect_string("error", &args.next().unwrap())?.clone();
+    let irritants: Vec<Term> = args.collect();
+    // The irritants are written as they would be read, unlike the message.
+    let text = irritants.iter().fold(message.clone(), |text, irritant| format!("{text} {irritant}"));
+    let object = Term::error_object(message, irritants);
+    Err(Error::new(ErrorKind::Raised).with_message(text).with_payload(object))
+}
+
+fn raise(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let object = args[0].clone();
+    Err(Error::new(ErrorKind::Raised)
+        .with_message(format!("Uncaught exception: {object}"))
+        .with_payload(object))
+}
+
+/// The object raised by the error, where errors of the interpreter itself are raised as
+/// error objects with their message.
+fn raised_object(err: &Error) -> Term {
+    match err.payload() {
+        Some(object) => object.clone(),
+        None => Term::error_object(err.message().clone(), vec![])
+    }
+}
+
+/// Errors which stop the evaluation, like exceeding the step limit, can't be handled.
+fn is_handleable(err: &Error) -> bool {
+    !matches!(err.kind(), ErrorKind::StepLimitExceeded | ErrorKind::ExpansionLimitExceeded)
+}
+
+/// Call the thunk with the handler innermost, removing it whether the thunk returns or fails.
+fn call_with_handler(ctx: &mut Context, handler: Handler, thunk: Term) -> Result<Term, Error> {
+    ctx.handlers.borrow_mut().push(handler);
+    let depth = ctx.handlers.borrow().len();
+    let result = ctx.apply(thunk, LinkedList::new());
+    ctx.handlers.borrow_mut().truncate(depth - 1);
+    result
+}
+
+/// The handler is called in the dynamic environment of the `raise-continuable`, except that
+/// it's no longer installed itself, so that it may raise to the outer handlers.
+/// Inside the body of a `guard`, the object is raised to the `guard` instead.
+fn raise_continuable(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let innermost = ctx.handlers.borrow().last().cloned();
+    match innermost {
+        Some(Handler::Procedure(handler)) => {
+            let object = args[0].clone();
+            let installed = ctx.handlers.borrow_mut().pop().unwrap();
+            let result = ctx.apply(handler, LinkedList::from([object]));
+            ctx.handlers.borrow_mut().push(installed);
+            result
+        }
+        _ => raise(ctx, args)
+    }
+}
+
+/// There are no continuations to return to the point of a `raise`, so the handler is only
+/// called once the thunk has failed. As a handler can't return from a `raise`, returning
+/// raises a secondary error object instead, to the outer handlers.
+///
+/// Errors raised by the interpreter itself are handled as error objects with their message,
+/// except those which stop the evaluation, like exceeding the step limit.
+fn with_exception_handler(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let (handler, thunk) = (args.next().unwrap(), args.next().unwrap());
+    let err = match call_with_handler(ctx, Handler::Procedure(handler.clone()), thunk) {
+        Err(err) if is_handleable(&err) => err,
+        result => return result
+    };
+    let object = raised_object(&err);
+    ctx.apply(handler, LinkedList::from([object.clone()]))?;
+    let message = "The exception handler returned from a non-continuable exception:";
+    Err(Error::new(ErrorKind::Raised)
+        .with_message(format!("{message} {object}"))
+        .with_payload(Term::error_object(message, vec![object])))
+}
+
+/// The handler re-raising the very object it was called with, when no clause of the `guard`
+/// matches it, raises the original error again, keeping its kind and its report.
+fn call_with_guard(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let (thunk, handler) = (args.next().unwrap(), args.next().unwrap());
+    let err = match call_with_handler(ctx, Handler::Guard, thunk) {
+        Err(err) if is_handleable(&err) => err,
+        result => return result
+    };
+    let object = raised_object(&err);
+    match ctx.apply(handler, LinkedList::from([object.clone()])) {
+        Err(reraised) if reraised.payload() == Some(&object) => Err(err),
+        result => result
+    }
+}
+
+/// The message and the list of irritants of an error object, where a condition of a type
+/// inheriting from `error-object` has them as its first two fields.
+fn expect_error_object(name: &str, arg: &Term) -> Result<(Term, Term), Error> {
+    match arg.value {
+        TermValue::ErrorObject(ref object) => Ok((Term::string(object.message.clone()),
+            object.irritants.iter().rev().fold(Term::nil(), |tail, irritant| Term::cons(irritant.clone(), tail)))),
+        TermValue::Condition(ref condition) if condition.condition_type.is_subtype_of(&ConditionType::error_object()) =>
+            Ok((condition.fields[0].clone(), condition.fields[1].clone())),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects an error object, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn is_error_object(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::boolean(ConditionType::of(&args[0].value)
+        .is_some_and(|condition_type| condition_type.is_subtype_of(&ConditionType::error_object()))))
+}
+
+fn error_object_message(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (message, _) = expect_error_object("error-object-message", &args[0])?;
+    Ok(message)
+}
+
+fn error_object_irritants(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (_, irritants) = expect_error_object("error-object-irritants", &args[0])?;
+    Ok(irritants)
+}
+
+fn expect_condition_type(name: &str, arg: &Term) -> Result<ConditionType, Error> {
+    match arg.value {
+        TermValue::ConditionType(ref condition_type) => Ok(condition_type.clone()),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a condition type, but got the {} {value}.", value.type_name())))
+    }
+}
+
+/// `(bind-condition-type name parent (constructor field...) predicate (field accessor)...)`,
+/// where the names are strings, binds them in the environment it's called from.
+///
+/// The fields of the type are those of its parent followed by the ones with accessors, and
+/// the constructor may initialize any of them, leaving the others unspecified.
+fn bind_condition_type(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let name = "bind-condition-type";
+    let strings = |list: Term| elements(name, list)?.iter()
+        .map(|term| expect_string(name, term).cloned())
+        .collect::<Result<Vec<String>, Error>>();
+    let invalid = |message: String| Error::new(ErrorKind::InvalidArgument).with_message(message);
+    let mut args = args.iter().cloned();
+    let type_name = expect_string(name, &args.next().unwrap())?.clone();
+    let parent = expect_condition_type(name, &args.next().unwrap())?;
+    let constructor = strings(args.next().unwrap())?;
+    let Some((constructor, initialized)) = constructor.split_first() else {
+        return Err(invalid(format!("'{name}' expects the name of a constructor for {type_name}.")))
+    };
+    let predicate = expect_string(name, &args.next().unwrap())?.clone();
+    let mut accessors = vec![];
+    for spec in args {
+        match strings(spec)?.as_slice() {
+            [field, accessor] => accessors.push((Symbol::from(field.as_str()), accessor.clone())),
+            _ => return Err(invalid(format!("'{name}' expects a field and its accessor for {type_name}.")))
+        }
+    }
+
+    let own_fields: Vec<Symbol> = accessors.iter().map(|(field, _)| field.clone()).collect();
+    let condition_type = ConditionType::new(type_name.as_str().into(), Some(parent), own_fields);
+    let fields = condition_type.fields();
+    if let Some(field) = fields.iter().enumerate().find_map(|(i, field)| fields[..i].contains(field).then_some(field)) {
+        return Err(invalid(format!("The condition type {type_name} has more than one field named '{field}'.")))
+    }
+    let indices = initialized.iter()
+        .map(|field| condition_type.field_index(field).ok_or_else(||
+            invalid(format!("'{constructor}' names the field '{field}', which {type_name} doesn't have."))))
+        .collect::<Result<Vec<usize>, Error>>()?;
+
+    let procedure = |name: &str, kind| Term::from(ConditionProcedure::new(name.into(), condition_type.clone(), kind));
+    let mut env = ctx.env.borrow_mut();
+    env.insert(constructor, procedure(constructor, ConditionProcedureKind::Constructor(indices)));
+    env.insert(&predicate, procedure(&predicate, ConditionProcedureKind::Predicate));
+    for (field, accessor) in &accessors {
+        let index = condition_type.field_index(field.as_ref()).unwrap();
+        env.insert(accessor, procedure(accessor, ConditionProcedureKind::Accessor(index)));
+    }
+    env.insert(&type_name, Term::from(condition_type.clone()));
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn is_condition_type(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let condition_type = expect_condition_type("condition-type?", args.last().unwrap())?;
+    Ok(Term::boolean(ConditionType::of(&args[0].value)
+        .is_some_and(|of| of.is_subtype_of(&condition_type))))
+}
+
+pub(super) const MACROS: &[(&str, MacroTransformer)] = &[
+    ("guard", guard),
+    ("define-condition-type", define_condition_type),
+];
+
+/// `(guard (var clause...) body...)`
+/// => `(call-with-guard ($lambda () body...) ($lambda (var) clauses))`,
+/// where the clauses `(test expression...)`, `(test)`, `(test => receiver)` and a last
+/// `(else expression...)` are tried in order, like those of `cond`, and the object is raised
+/// again if none of them matches.
+///
+/// The value of a test is bound by a procedure of its own, whose body refers to nothing but
+/// its parameters, so that it can't capture the variables of the clauses.
+fn guard(form: &Node) -> Result<Node, Error> {
+    let syntax = "(guard (var (test expression...)...) body...)";
+    let (var, clauses, body) = match form.as_ref().as_slice() {
+        [_, Node::List(spec, _), body @ ..] if !body.is_empty() => match spec.split_first() {
+            Some((var @ Node::Symbol(_), clauses)) => (var, clauses, body),
+            _ => return Err(invalid_form("guard", syntax))
+        },
+        _ => return Err(invalid_form("guard", syntax))
+    };
+    let sequence = |expressions: &[Node]| {
+        let mut thunk = vec!["$lambda".into(), Node::list(vec![])];
+        thunk.extend(expressions.iter().cloned());
+        Node::list(vec![Node::list(thunk)])
+    };
+    let thunk = |expression: Node| Node::list(vec!["$lambda".into(), Node::list(vec![]), expression]);
+    // `(($lambda (value then else) ($if value (then value) (else))) test then else)`
+    let select = |test: &Node, then: Node, otherwise: Node| Node::list(vec![
+        Node::list(vec![
+            "$lambda".into(),
+            Node::list(vec!["value".into(), "then".into(), "else".into()]),
+            Node::list(vec![
+                "$if".into(),
+                "value".into(),
+                Node::list(vec!["then".into(), "value".into()]),
+                Node::list(vec!["else".into()])
+            ])
+        ]),
+        test.clone(),
+        then,
+        thunk(otherwise)
+    ]);
+    let mut dispatch = Node::list(vec!["raise".into(), var.clone()]);
+    for (i, clause) in clauses.iter().enumerate().rev() {
+        dispatch = match clause.as_ref().as_slice() {
+            [Node::Symbol(keyword), expressions @ ..]
+                if keyword.as_ref() == "else" && i + 1 == clauses.len() && !expressions.is_empty() =>
+                sequence(expressions),
+            // `else` is only a keyword as the last clause, with expressions.
+            [Node::Symbol(keyword), ..] if keyword.as_ref() == "else" => return Err(invalid_form("guard", syntax)),
+            [test] => select(test, Node::list(vec![
+                "$lambda".into(), Node::list(vec!["value".into()]), "value".into()
+            ]), dispatch),
+            [test, Node::Symbol(arrow), receiver] if arrow.as_ref() == "=>" =>
+                select(test, receiver.clone(), dispatch),
+            [_, Node::Symbol(arrow), ..] if arrow.as_ref() == "=>" => return Err(invalid_form("guard", syntax)),
+            [test, expressions @ ..] if !expressions.is_empty() =>
+                Node::list(vec!["$if".into(), test.clone(), sequence(expressions), dispatch]),
+            _ => return Err(invalid_form("guard", syntax))
+        };
+    }
+    let mut thunk = vec!["$lambda".into(), Node::list(vec![])];
+    thunk.extend(body.iter().cloned());
+    Ok(Node::list(vec![
+        "call-with-guard".into(),
+        Node::list(thunk),
+        Node::list(vec!["$lambda".into(), Node::list(vec![var.clone()]), dispatch])
+    ]))
+}
+
+/// `(define-condition-type name parent (constructor field...) predicate (field accessor)...)`
+/// => `(bind-condition-type "name" parent (list "constructor" "field"...) "predicate" (list "field" "accessor")...)`
+fn define_condition_type(form: &Node) -> Result<Node, Error> {
+    let syntax = "(define-condition-type name parent (constructor field...) predicate (field accessor)...)";
+    let invalid = || invalid_form("define-condition-type", syntax);
+    let name = |node: &Node| match node {
+        Node::Symbol(symbol) => Ok(Node::string(symbol.as_ref())),
+        _ => Err(invalid())
+    };
+    let names = |node: &Node| match node {
+        Node::List(nodes, _) if !nodes.is_empty() => {
+            let mut list = vec!["list".into()];
+            for node in nodes {
+                list.push(name(node)?);
+            }
+            Ok(Node::list(list))
+        }
+        _ => Err(invalid())
+    };
+    let [_, type_name, parent, constructor, predicate, fields @ ..] = form.as_ref().as_slice() else {
+        return Err(invalid())
+    };
+    let mut expansion = vec!["bind-condition-type".into(), name(type_name)?, parent.clone(), names(constructor)?, name(predicate)?];
+    for field in fields {
+        match field {
+            Node::List(spec, _) if spec.len() == 2 => expansion.push(names(field)?),
+            _ => return Err(invalid())
+        }
+    }
+    Ok(Node::list(expansion))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::{Term, TermValue};
+    use super::super::tests::{eval, eval_with_output};
+
+    #[test]
+    fn error_objects() {
+        let caught = |body: &str| eval(&format!(
+            r#"(guard (e (#t {body})) (error "Bad thing:" 42 "why" (list 1 2)))"#));
+        assert_eq!(caught("(error-object? e)").unwrap(), Term::boolean(true));
+        assert_eq!(caught("(error-object-message e)").unwrap(), Term::string("Bad thing:"));
+        assert_eq!(caught("(error-object-irritants e)").unwrap().to_string(), r#"(42 "why" (1 2))"#);
+        assert_eq!(caught("e").unwrap().to_string(), r#"#<error "Bad thing:" 42 "why" (1 2)>"#);
+
+        // The handler isn't called unless something is raised.
+        assert_eq!(eval("(with-exception-handler ($lambda (e) 0) ($lambda () 1))").unwrap(), Term::int(1));
+        assert_eq!(eval("(guard (e (#t e)) (raise 5))").unwrap(), Term::int(5));
+        assert_eq!(eval("(guard (e (#t (error-object-message e))) (car 1))").unwrap(),
+            Term::string("'car' expects a pair, but got the int 1."));
+        assert_eq!(eval("(error-object? 1)").unwrap(), Term::boolean(false));
+
+        let err = eval(r#"(error "Bad thing:" 42 "why")"#).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Raised);
+        assert_eq!(err.message(), r#"Bad thing: 42 "why""#);
+        assert!(matches!(err.payload().unwrap().value, TermValue::ErrorObject(_)));
+        assert_eq!(eval("(raise (list 1))").unwrap_err().message(), "Uncaught exception: (1)");
+        assert_eq!(eval("(error 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn raise_continuable() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        // The handler of a continuable raise returns to it.
+        assert_eq!(text("(with-exception-handler ($lambda (e) (gcd e 6)) ($lambda () (list (raise-continuable 4) 1)))"),
+            "(2 1)");
+        // The handler of a raise can't return, which raises a secondary error object instead.
+        let err = eval("(with-exception-handler ($lambda (e) 0) ($lambda () (raise 'oops')))").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Raised);
+        assert_eq!(err.message(), r#"The exception handler returned from a non-continuable exception: "oops""#);
+        assert_eq!(text("(guard (e (#t (error-object-irritants e))) \
+            (with-exception-handler ($lambda (e) 0) ($lambda () (raise 'oops'))))"), r#"("oops")"#);
+        assert_eq!(text("(guard (e (#t e)) (with-exception-handler ($lambda (e) (raise 'inner')) \
+            ($lambda () (raise 'outer'))))"), r#""inner""#);
+
+        // Each handler is called with the outer ones installed.
+        assert_eq!(text("(with-exception-handler ($lambda (e) (list 'outer' e)) ($lambda () \
+            (with-exception-handler ($lambda (e) (raise-continuable (list 'inner' e))) ($lambda () \
+                (raise-continuable 1)))))"), r#"("outer" ("inner" 1))"#);
+        assert_eq!(eval("(raise-continuable 1)").unwrap_err().message(), "Uncaught exception: 1");
+
+        // A guard catches a continuable raise too, without returning to it.
+        assert_eq!(text("(with-exception-handler ($lambda (e) 'handler') ($lambda () \
+            (guard (e (#t (list 'guard' e))) (list (raise-continuable 1) 2))))"), r#"("guard" 1)"#);
+    }
+
+    #[test]
+    fn guard_clauses() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(guard (e ((error-object? e) 'error') ((odd? e) 'odd') (else 'even')) (raise 3))"), r#""odd""#);
+        assert_eq!(text("(guard (e ((error-object? e) 'error') ((odd? e) 'odd') (else 'even')) (raise 4))"), r#""even""#);
+        assert_eq!(text("(guard (e (#t 1 2)) (raise 0))"), "2");
+        assert_eq!(text("(guard (e (#t 1)) 'body')"), r#""body""#);
+        assert_eq!(text("(guard (e ((find odd? (list e)) => ($lambda (x) (* x 2))) (else 'even')) (raise 3))"), "6");
+        assert_eq!(text("(guard (e ((find odd? (list e)) => ($lambda (x) (* x 2))) (else 'even')) (raise 4))"), r#""even""#);
+        assert_eq!(text("(guard (e ((find odd? (list e)))) (raise 3))"), "3");
+        // The bindings of the expansion don't capture the variables of the clauses.
+        assert_eq!(text("(guard (value (value => ($lambda (then) (list value then)))) (raise 1))"), "(1 1)");
+
+        // The `else` clause is only evaluated on an exception, and a normal return skips it.
+        let (result, output) = eval_with_output("(guard (e (else (display 'else') 0)) (display 'body') 1)");
+        assert_eq!((result.unwrap(), output.as_str()), (Term::int(1), "body"));
+        let (result, output) = eval_with_output("(guard (e (else (display 'else') 0)) (display 'body') (raise 1))");
+        assert_eq!((result.unwrap(), output.as_str()), (Term::int(0), "bodyelse"));
+
+        // Without a matching clause, the original error is raised again.
+        let err = eval("(guard (e ((number? e) e)) (car 1))").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(guard (e ((error-object? e) e)) (raise 1))").unwrap_err().message(), "Uncaught exception: 1");
+        assert_eq!(text("(guard (outer (#t (list 'outer' outer))) (guard (e ((error-object? e) e)) (raise 1)))"),
+            r#"("outer" 1)"#);
+
+        for src in ["(guard (e) )", "(guard e 1)", "(guard (1 (#t 1)) 1)", "(guard (e (else 1) (#t 2)) 1)",
+            "(guard (e (else)) 1)", "(guard (e (#t =>)) 1)", "(guard (e (#t => car cdr)) 1)"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidSyntax, "{src}");
+        }
+    }
+
+    #[test]
+    fn condition_types() {
+        let text = |src: &str| eval(&format!("(define-condition-type file-error error-object \
+            (make-file-error message irritants filename) file-error? (filename file-error-filename)) {src}"))
+            .unwrap().to_string();
+        assert_eq!(text("(with-exception-handler \
+            ($lambda (e) (list (file-error? e) (error-object? e) (file-error-filename e) (error-object-message e))) \
+            ($lambda () (raise-continuable (make-file-error 'Cannot open' (list 1) 'a.txt'))))"), r#"(#t #t "a.txt" "Cannot open")"#);
+        assert_eq!(text("(guard (e ((file-error? e) (error-object-irritants e))) (raise (make-file-error 'Bad' (list 1 2) 'a')))"),
+            "(1 2)");
+        assert_eq!(text("(make-file-error 'Bad' () 'a')"), r#"#<condition file-error "Bad" () "a">"#);
+        // A condition is the same object as itself, but not as another with the same fields.
+        assert_eq!(text("($define c (make-file-error 'Bad' () 'a')) \
+            (list (eqv? c c) (eq? c c) (eqv? c (make-file-error 'Bad' () 'a')) (equal? c c))"), "(#t #t #f #t)");
+        assert_eq!(text("(list file-error make-file-error (type-of file-error?))"),
+            r#"(#<condition-type file-error> #<procedure make-file-error> procedure)"#);
+
+        // A type is a type of its own conditions and of those of the types inheriting from it.
+        assert_eq!(text("(define-condition-type missing-file file-error (make-missing-file filename) missing-file?) \
+            ($define c (make-missing-file 'b')) \
+            (list (file-error-filename c) (error-object-message c) (missing-file? c) (file-error? c) \
+                (condition-type? c missing-file) (condition-type? c file-error) (condition-type? c error-object) \
+                (missing-file? (make-file-error 'x' () 'a')) (condition-type? (guard (e (#t e)) (error 'x')) file-error))"),
+            r#"("b" #<unspecified> #t #t #t #t #t #f #f)"#);
+        assert_eq!(text("(guard (e (#t (list (condition-type? e error-object) (file-error? e)))) (error 'x'))"), "(#t #f)");
+
+        let err = eval("(define-condition-type c error-object (make-c message) c? (message c-message))").unwrap_err();
+        assert_eq!((err.kind(), err.message().as_str()),
+            (ErrorKind::InvalidArgument, "The condition type c has more than one field named 'message'."));
+        let err = eval("(define-condition-type c error-object (make-c size) c?)").unwrap_err();
+        assert_eq!(err.message(), "'make-c' names the field 'size', which c doesn't have.");
+        assert_eq!(eval("(define-condition-type c 1 (make-c) c?)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        let err = eval("(define-condition-type c error-object (make-c) c? (size c-size)) (c-size (guard (e (#t e)) (error 'x')))").unwrap_err();
+        assert_eq!(err.message(), r#"'c-size' expects a condition of the type c, but got the error-object #<error "x">."#);
+        for src in ["(define-condition-type c error-object (make-c) 1)", "(define-condition-type c error-object () c?)",
+            "(define-condition-type c error-object (make-c) c? (size))"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidSyntax, "{src}");
+        }
+    }
+}
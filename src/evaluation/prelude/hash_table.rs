@@ -0,0 +1,304 @@
+use std::collections::LinkedList;
+
+use crate::error::{Error, ErrorKind};
+use super::super::context::Context;
+use super::super::hash_table::{HashKey, HashTable, KeyEquality, Map};
+use super::super::term::{Term, TermValue, UnitValue};
+use super::{Arity::*, Builtin};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("make-hash-table", Exactly(0), "A new empty hash table, whose keys are compared with `equal?`.", make_hash_table),
+    Builtin::new("make-equal-hash-table", Exactly(0), "The same as `make-hash-table`.", make_equal_hash_table),
+    Builtin::new("make-eq-hash-table", Exactly(0), "A new empty hash table, whose keys are compared with `eq?`.", make_eq_hash_table),
+    Builtin::new("hash-table?", Exactly(1), "Whether the value is a hash table.", is_hash_table),
+    Builtin::new("hash-table-set!", Exactly(3), "Bind the key to the value in the hash table.", hash_table_set),
+    Builtin::new("hash-table-ref", Between(2, 3), "The value of the key in the hash table, or the result of calling the thunk if it's absent.", hash_table_ref),
+    Builtin::new("hash-table-ref/default", Exactly(3), "The value of the key in the hash table, or the default if it's absent.", hash_table_ref_default),
+    Builtin::new("hash-table-contains?", Exactly(2), "Whether the key is in the hash table.", hash_table_contains),
+    Builtin::new("hash-table-delete!", Exactly(2), "Remove the key from the hash table, if it's there.", hash_table_delete),
+    Builtin::new("hash-table-count", Exactly(1), "The number of keys in the hash table.", hash_table_count),
+    Builtin::new("hash-table-keys", Exactly(1), "A new list of the keys of the hash table, in no particular order.", hash_table_keys),
+    Builtin::new("hash-table->alist", Exactly(1), "A new list of the pairs of each key and its value, in no particular order.", hash_table_to_alist),
+    Builtin::new("hash-table-update!", Exactly(3), "Replace the value of the key, which must be present, with the result of the updater on it.", hash_table_update),
+    Builtin::new("hash-table-update!/default", Exactly(4), "Replace the value of the key, or the default if it's absent, with the result of the updater on it.", hash_table_update_default),
+    Builtin::new("map?", Exactly(1), "Whether the value is a map, as written by a literal like `{a 1 b 2}`.", is_map),
+    Builtin::new("get", Between(2, 3), "The value of the key in the map, or the default if it's absent.", get),
+    Builtin::new("assoc", Exactly(3), "A new map with the key bound to the value, leaving the map as it is.", assoc),
+    Builtin::new("hash-table-merge!", Between(2, 3), "Add the keys of the second hash table to the first, calling the procedure on the old and the new values of the keys in both, or taking the new ones.", hash_table_merge),
+];
+
+fn expect_hash_table<'a>(name: &str, arg: &'a Term) -> Result<&'a HashTable, Error> {
+    match arg.value {
+        TermValue::HashTable(ref table) => Ok(table),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a hash table, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn expect_key(name: &str, table: &HashTable, arg: &Term) -> Result<HashKey, Error> {
+    table.key(arg).ok_or_else(|| Error::new(ErrorKind::TypeMismatch)
+        .with_message(format!("'{name}' can't use the {} {arg} as a key.", arg.value.type_name())))
+}
+
+fn missing_key(name: &str, key: &Term) -> Error {
+    Error::new(ErrorKind::InvalidArgument)
+        .with_message(format!("'{name}' expects a key of the hash table, but {key} is absent."))
+}
+
+fn make_hash_table(_: &mut Context, _: &[Term]) -> Result<Term, Error> {
+    Ok(Term::from(HashTable::new(KeyEquality::Equal)))
+}
+
+fn make_equal_hash_table(_: &mut Context, _: &[Term]) -> Result<Term, Error> {
+    Ok(Term::from(HashTable::new(KeyEquality::Equal)))
+}
+
+fn make_eq_hash_table(_: &mut Context, _: &[Term]) -> Result<Term, Error> {
+    Ok(Term::from(HashTable::new(KeyEquality::Eq)))
+}
+
+fn is_hash_table(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::HashTable(_)).into())
+}
+
+fn hash_table_set(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let table = expect_hash_table("hash-table-set!", &args[0])?;
+    table.insert(expect_key("hash-table-set!", table, &args[1])?, args[1].clone(), args[2].clone());
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn hash_table_ref(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let table = expect_hash_table("hash-table-ref", &args[0])?;
+    match (table.get(&expect_key("hash-table-ref", table, &args[1])?), args.get(2)) {
+        (Some(value), _) => Ok(value),
+        (None, Some(thunk)) => ctx.apply(thunk.clone(), LinkedList::new()),
+        (None, None) => Err(missing_key("hash-table-ref", &args[1]))
+    }
+}
+
+fn hash_table_ref_default(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let table = expect_hash_table("hash-table-ref/default", &args[0])?;
+    Ok(table.get(&expect_key("hash-table-ref/default", table, &args[1])?).unwrap_or_else(|| args[2].clone()))
+}
+
+fn hash_table_contains(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let table = expect_hash_table("hash-table-contains?", &args[0])?;
+    Ok(table.get(&expect_key("hash-table-contains?", table, &args[1])?).is_some().into())
+}
+
+fn hash_table_delete(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let table = expect_hash_table("hash-table-delete!", &args[0])?;
+    table.remove(&expect_key("hash-table-delete!", table, &args[1])?);
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn hash_table_count(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::int(expect_hash_table("hash-table-count", &args[0])?.len() as i64))
+}
+
+fn hash_table_keys(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let entries = expect_hash_table("hash-table-keys", &args[0])?.entries();
+    Ok(entries.into_iter().fold(Term::nil(), |tail, (_, key, _)| Term::cons(key, tail)))
+}
+
+fn hash_table_to_alist(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let entries = expect_hash_table("hash-table->alist", &args[0])?.entries();
+    Ok(entries.into_iter().fold(Term::nil(), |tail, (_, key, value)| Term::cons(Term::cons(key, value), tail)))
+}
+
+/// The updater is called with the table unborrowed, so that it may use the table itself.
+fn update(ctx: &mut Context, name: &str, args: &[Term], default: Option<&Term>) -> Result<Term, Error> {
+    let table = expect_hash_table(name, &args[0])?;
+    let key = expect_key(name, table, &args[1])?;
+    let old = match (table.get(&key), default) {
+        (Some(value), _) => value,
+        (None, Some(default)) => default.clone(),
+        (None, None) => return Err(missing_key(name, &args[1]))
+    };
+    let new = ctx.apply(args[2].clone(), LinkedList::from([old]))?;
+    table.insert(key, args[1].clone(), new);
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn hash_table_update(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    update(ctx, "hash-table-update!", args, None)
+}
+
+fn hash_table_update_default(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    update(ctx, "hash-table-update!/default", args, Some(&args[3]))
+}
+
+fn hash_table_merge(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let target = expect_hash_table("hash-table-merge!", &args[0])?;
+    let source = expect_hash_table("hash-table-merge!", &args[1])?;
+    // The keys are made anew for the first table, which may compare them differently.
+    for (_, term, new) in source.entries() {
+        let key = expect_key("hash-table-merge!", target, &term)?;
+        let value = match (target.get(&key), args.get(2)) {
+            (Some(old), Some(merger)) => ctx.apply(merger.clone(), LinkedList::from([old, new]))?,
+            _ => new
+        };
+        target.insert(key, term, value);
+    }
+    Ok(args[0].clone())
+}
+
+fn expect_map<'a>(name: &str, arg: &'a Term) -> Result<&'a Map, Error> {
+    match arg.value {
+        TermValue::Map(ref map) => Ok(map),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a map, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn invalid_map_key(name: &str, arg: &Term) -> Error {
+    Error::new(ErrorKind::TypeMismatch)
+        .with_message(format!("'{name}' can't use the {} {arg} as a key.", arg.value.type_name()))
+}
+
+fn is_map(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::Map(_)).into())
+}
+
+fn get(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let map = expect_map("get", &args[0])?;
+    if HashKey::of(&args[1], KeyEquality::Equal).is_none() {
+        return Err(invalid_map_key("get", &args[1]))
+    }
+    match (map.get(&args[1]), args.get(2)) {
+        (Some(value), _) => Ok(value),
+        (None, Some(default)) => Ok(default.clone()),
+        (None, None) => Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("'get' expects a key of the map, but {} is absent.", args[1])))
+    }
+}
+
+fn assoc(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let map = expect_map("assoc", &args[0])?;
+    map.insert(args[1].clone(), args[2].clone()).map(Term::from).ok_or_else(|| invalid_map_key("assoc", &args[1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::Term;
+    use super::super::tests::eval;
+
+    #[test]
+    fn hash_table_basics() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        let table = "($define t (make-hash-table)) (hash-table-set! t 'a' 1) (hash-table-set! t (list 1 2) 2) ";
+        assert_eq!(text(&format!("{table} (hash-table-ref t 'a')")), "1");
+        // The keys are compared with `equal?`, so a new list with the same elements finds the value.
+        assert_eq!(text(&format!("{table} (hash-table-ref t (list 1 2))")), "2");
+        assert_eq!(text(&format!("{table} (hash-table-ref/default t 'b' 0)")), "0");
+        assert_eq!(text(&format!("{table} (hash-table-ref t 'b' ($lambda () 'none'))")), r#""none""#);
+        assert_eq!(text(&format!("{table} (hash-table-contains? t 1)")), "#f");
+        assert_eq!(text(&format!("{table} (hash-table-count t)")), "2");
+        assert_eq!(text(&format!("{table} (hash-table-set! t 'a' 3) (hash-table-delete! t (list 1 2)) (hash-table->alist t)")),
+            r#"(("a" . 3))"#);
+        assert_eq!(text(&format!("{table} t")), "#<hash-table 2>");
+        assert_eq!(text("(hash-table-set! (make-hash-table) 0.0 1)"), "#<unspecified>");
+        assert_eq!(eval("($define t (make-hash-table)) (hash-table-set! t -0.0 1) (hash-table-ref t 0.0)").unwrap(),
+            Term::int(1));
+        assert_eq!(text("(hash-table? (make-hash-table))"), "#t");
+
+        assert_eq!(eval("(hash-table-ref (make-hash-table) 'a')").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(hash-table-set! (make-hash-table) (guard (e (#t e)) (error 'x')) 1)").unwrap_err().kind(),
+            ErrorKind::TypeMismatch);
+        assert_eq!(eval("(hash-table-count (list))").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn map_literals() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        let map = "($define m {'a' 1 'b' (2 3) (x) #t}) ";
+        assert_eq!(text(&format!("{map}m")), r#"{"a" 1 "b" (2 3) (x) #t}"#);
+        // The keys and values are data, and keys are compared by `equal?`.
+        assert_eq!(text(&format!("{map}(list (get m 'a') (get m 'b') (get m (list (read 'x'))) (get m 'c' 0))")),
+            "(1 (2 3) #t 0)");
+        assert_eq!(text("(get {a 1} (read 'a'))"), "1");
+        assert_eq!(text(&format!("{map}(list (assoc m 'a' 5) (assoc m 'c' 6) m)")),
+            r#"({"a" 5 "b" (2 3) (x) #t} {"a" 1 "b" (2 3) (x) #t "c" 6} {"a" 1 "b" (2 3) (x) #t})"#);
+        assert_eq!(text("(list {} {1 2 1 3} (map? {}) (map? (list)) (type-of {}))"), "({} {1 3} #t #f map)");
+        // Maps are equal if they bind equal keys to equal values, in any order.
+        assert_eq!(text("(list (equal? {a 1} {a 1}) (equal? {a 1 b (2)} {b (2) a 1}) (equal? {a 1} {a 2})
+            (equal? {a 1} {a 1 b 2}) (equal? {a 1} {b 1}) (eqv? {a 1} {a 1}))"), "(#t #t #f #f #f #f)");
+        // Maps as keys are compared by `equal?` too, whatever the order of their entries.
+        assert_eq!(text("(list (get {{1 2} x} {1 2}) (get {{a 1 b 2} y} {b 2 a 1}) (get {{1 2} x} {1 3} #f))"),
+            "(x y #f)");
+        assert_eq!(text("($define t (make-hash-table)) (hash-table-set! t {1 2} 'x') (hash-table-ref t {1 2})"),
+            r#""x""#);
+
+        assert_eq!(eval("{a 1 b}").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval("(list {a})").unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval("(get {a 1} 'b')").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(get (list) 'a')").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(assoc {} (guard (e (#t e)) (error 'x')) 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn hash_table_equality() {
+        let tables = "($define u (vector 1 2)) ($define v (vector 1 2))
+            ($define eq-table (make-eq-hash-table)) ($define equal-table (make-equal-hash-table))
+            (hash-table-set! eq-table u 'u') (hash-table-set! eq-table v 'v')
+            (hash-table-set! equal-table u 'u') (hash-table-set! equal-table v 'v') ";
+        let text = |src: &str| eval(&format!("{tables} {src}")).unwrap().to_string();
+        // Two vectors of the same elements are different keys by `eq?`, but the same by `equal?`.
+        assert_eq!(text("(hash-table-count eq-table)"), "2");
+        assert_eq!(text("(list (hash-table-ref eq-table u) (hash-table-ref eq-table v))"), r#"("u" "v")"#);
+        assert_eq!(text("(hash-table-ref/default eq-table (vector 1 2) #f)"), "#f");
+        assert_eq!(text("(hash-table-count equal-table)"), "1");
+        assert_eq!(text("(list (hash-table-ref equal-table u) (hash-table-ref equal-table (vector 1 2)))"), r#"("v" "v")"#);
+        // Changing a key in place keeps its identity, but not its contents.
+        assert_eq!(text("(vector-set! u 0 9) (hash-table-ref/default eq-table u #f)"), r#""u""#);
+        assert_eq!(text("(vector-set! u 0 9) (hash-table-ref/default equal-table u #f)"), "#f");
+
+        // Atoms are compared by value in both, and procedures by identity.
+        assert_eq!(text(r"(hash-table-set! eq-table 'k' 1) (hash-table-ref eq-table (string #\k))"), "1");
+        assert_eq!(text("(hash-table-set! eq-table (list 1) 1) (hash-table-contains? eq-table (list 1))"), "#f");
+        assert_eq!(text("(hash-table-set! equal-table car 1) (hash-table-ref equal-table car)"), "1");
+        assert_eq!(text("(hash-table-set! equal-table car 1) (hash-table-contains? equal-table cdr)"), "#f");
+        assert_eq!(text("(hash-table-merge! equal-table eq-table) (hash-table-count equal-table)"), "1");
+    }
+
+    #[test]
+    fn hash_table_updates() {
+        let counter = "($define counts (make-hash-table))
+            (let loop ((words (list 'the' 'cat' 'saw' 'the' 'other' 'cat' 'the')))
+              ($if (null? words) #t
+                (loop (($lambda () (hash-table-update!/default counts (car words) ($lambda (n) (+ n 1)) 0)
+                  (cdr words))))))";
+        let count = |word: &str| eval(&format!("{counter} (hash-table-ref/default counts '{word}' 0)")).unwrap();
+        assert_eq!(count("the"), Term::int(3));
+        assert_eq!(count("cat"), Term::int(2));
+        assert_eq!(count("saw"), Term::int(1));
+        assert_eq!(count("dog"), Term::int(0));
+        assert_eq!(eval(&format!("{counter} (hash-table-count counts)")).unwrap(), Term::int(4));
+
+        let table = "($define t (make-hash-table)) (hash-table-set! t 'a' 1) ";
+        assert_eq!(eval(&format!("{table} (hash-table-update! t 'a' ($lambda (n) (* n 10))) (hash-table-ref t 'a')"))
+            .unwrap(), Term::int(10));
+        let err = eval(&format!("{table} (hash-table-update! t 'b' ($lambda (n) n))")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+        // The updater may use the table itself.
+        assert_eq!(eval(&format!("{table} (hash-table-update! t 'a' ($lambda (n) (hash-table-count t))) \
+            (hash-table-ref t 'a')")).unwrap(), Term::int(1));
+    }
+
+    #[test]
+    fn hash_table_merging() {
+        let tables = "($define a (make-hash-table)) (hash-table-set! a 'x' 1) (hash-table-set! a 'y' 2)
+            ($define b (make-hash-table)) (hash-table-set! b 'y' 20) (hash-table-set! b 'z' 30) ";
+        let merged = |merge: &str, key: &str| eval(&format!("{tables} {merge} (hash-table-ref/default a '{key}' #f)")).unwrap();
+        // The values of the second table win by default.
+        assert_eq!(merged("(hash-table-merge! a b)", "x"), Term::int(1));
+        assert_eq!(merged("(hash-table-merge! a b)", "y"), Term::int(20));
+        assert_eq!(merged("(hash-table-merge! a b)", "z"), Term::int(30));
+        assert_eq!(merged("(hash-table-merge! a b +)", "y"), Term::int(22));
+        assert_eq!(merged("(hash-table-merge! a b +)", "z"), Term::int(30));
+        // The second table is left as it was.
+        assert_eq!(eval(&format!("{tables} (hash-table-merge! a b) (hash-table-count b)")).unwrap(), Term::int(2));
+        assert_eq!(eval(&format!("{tables} (hash-table-count (hash-table-merge! a b))")).unwrap(), Term::int(3));
+    }
+}
@@ -0,0 +1,557 @@
+use std::collections::LinkedList;
+use std::io::Write;
+
+use crate::error::{Error, ErrorKind};
+use crate::parser::{SrcInfo, SyntacticParser};
+use crate::{if_or, share};
+use super::super::context::{Context, Sink, Source};
+use super::super::combiner::Parameter;
+use super::super::port::Port;
+use super::super::term::{format_float, Term, TermValue, UnitValue};
+use super::{Arity::*, Builtin, expect_count, expect_int, expect_string};
+use super::bytevector::{bytevector_of, expect_byte, expect_bytevector};
+use super::control::wind;
+use super::string::expect_range;
+use super::number::format_integer;
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("display", Between(1, 2), "Write the value to the port, or the current output, in a human-readable form.", display),
+    Builtin::new("write", Between(1, 2), "Write the value to the port, or the current output, as it would be read.", write),
+    Builtin::new("newline", Between(0, 1), "Write a line ending to the port, or the current output.", newline),
+    Builtin::new("format", AtLeast(2),
+        "Write the arguments by the directives of the control string to the port, the current output if #t, or a new string if #f.",
+        format),
+    Builtin::new("read-line", Between(0, 1), "Read a line from the port, or the current input, without its line ending.", read_line),
+    Builtin::new("input-port?", Exactly(1), "Whether the value is an input port.", is_input_port),
+    Builtin::new("output-port?", Exactly(1), "Whether the value is an output port.", is_output_port),
+    Builtin::new("input-port-open?", Exactly(1), "Whether the value is an input port which is still open.", is_input_port_open),
+    Builtin::new("output-port-open?", Exactly(1), "Whether the value is an output port which is still open.", is_output_port_open),
+    Builtin::new("binary-port?", Exactly(1), "Whether the value is a port of bytes.", is_binary_port),
+    Builtin::new("textual-port?", Exactly(1), "Whether the value is a port of characters.", is_textual_port),
+    Builtin::new("open-binary-input-file", Exactly(1), "A binary input port reading the file.", open_binary_input_file),
+    Builtin::new("open-binary-output-file", Exactly(1), "A binary output port writing the file, which is created or truncated.", open_binary_output_file),
+    Builtin::new("read-u8", Exactly(1), "Read a byte from the binary port, or the end of file object.", read_u8),
+    Builtin::new("peek-u8", Exactly(1), "The next byte of the binary port without reading it, or the end of file object.", peek_u8),
+    Builtin::new("u8-ready?", Exactly(1), "Whether a byte can be read from the binary port without blocking.", is_u8_ready),
+    Builtin::new("write-u8", Exactly(2), "Write the byte to the binary port.", write_u8),
+    Builtin::new("read-bytevector", Exactly(2), "A new bytevector of up to the count of bytes read from the binary port, or the end of file object.", read_bytevector),
+    Builtin::new("read-bytevector!", Between(2, 4),
+        "Read bytes from the binary port into the bytevector between the start and the end, giving their count or the end of file object.",
+        read_bytevector_into),
+    Builtin::new("write-bytevector", Between(2, 4), "Write the bytes of the bytevector between the start and the end to the binary port.", write_bytevector),
+    Builtin::new("close-port", Exactly(1), "Close the port, unless it is closed already.", close_port),
+    Builtin::new("close-input-port", Exactly(1), "Close the input port, unless it is closed already.", close_input_port),
+    Builtin::new("close-output-port", Exactly(1), "Close the output port, unless it is closed already.", close_output_port),
+    Builtin::new("call-with-port", Exactly(2), "Call the procedure with the port, closing the port once it returns.", call_with_port),
+    Builtin::new("eof-object", Exactly(0), "The value read once the input is exhausted.", eof_object),
+    Builtin::new("eof-object?", Exactly(1), "Whether the value is the end of the input.", is_eof_object),
+    Builtin::new("read", Exactly(1), "The first datum written in the string, without evaluating it, or the end of file object.", read),
+    Builtin::new("load", Exactly(1), "Evaluate the forms of the file in the current environment.", load),
+];
+
+fn io_failed(err: std::io::Error) -> Error {
+    Error::new(ErrorKind::IoError).with_message(format!("Failed to write the output: {err}"))
+}
+
+fn expect_port<'a>(name: &str, arg: &'a Term) -> Result<&'a Port, Error> {
+    match arg.value {
+        TermValue::Port(ref port) => Ok(port),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a port, but got the {} {value}.", value.type_name())))
+    }
+}
+
+/// The port given as the argument at `index`, or the one the parameter is bound to, which
+/// `parameterize` may have bound to anything.
+fn port_of(name: &str, args: &[Term], index: usize, parameter: &Parameter) -> Result<Port, Error> {
+    match args.get(index) {
+        Some(port) => expect_port(name, port).cloned(),
+        None => expect_port(name, &parameter.value()).cloned()
+    }
+}
+
+/// The port given as the argument at `index`, or the current output port.
+fn output_of(name: &str, args: &[Term], index: usize, ctx: &Context) -> Result<Sink, Error> {
+    port_of(name, args, index, &ctx.current_output)?.sink().ok_or_else(|| Error::new(ErrorKind::TypeMismatch)
+        .with_message(format!("'{name}' expects an output port.")))?
+}
+
+/// The port given as the argument at `index`, or the current input port.
+fn input_of(name: &str, args: &[Term], index: usize, ctx: &Context) -> Result<Source, Error> {
+    port_of(name, args, index, &ctx.current_input)?.source().ok_or_else(|| Error::new(ErrorKind::TypeMismatch)
+        .with_message(format!("'{name}' expects an input port.")))?
+}
+
+fn display(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let output = output_of("display", args, 1, ctx)?;
+    // Strings are written as their contents, even within lists, and anything else as it would be read.
+    output.write_str(&format!("{:#}", args[0])).map_err(io_failed)?;
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn write(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let output = output_of("write", args, 1, ctx)?;
+    output.write_str(&args[0].to_string()).map_err(io_failed)?;
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn newline(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    output_of("newline", args, 0, ctx)?.write_str("\n").map_err(io_failed)?;
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn format(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let control = expect_string("format", &args[1])?;
+    let text = format_directives(control, &mut args[2..].iter())?;
+    let output = match args[0].value {
+        TermValue::Bool(false) => return Ok(text.into()),
+        TermValue::Bool(true) => output_of("format", &[], 0, ctx)?,
+        _ => output_of("format", args, 0, ctx)?
+    };
+    output.write_str(&text).map_err(io_failed)?;
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn invalid_directive(message: String) -> Error {
+    Error::new(ErrorKind::InvalidArgument).with_message(format!("'format' {message}"))
+}
+
+/// The control string with its directives replaced, which are `~a` and `~s` for a value as
+/// written by `display` and `write`, `~<radix>d` for an integer, `~<width>.<precision>` followed
+/// by `f`, `e` or `g` for a number in fixed, scientific or general notation, `~%` for a line
+/// ending and `~~` for a tilde.
+fn format_directives(control: &str, args: &mut std::slice::Iter<Term>) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut chars = control.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '~' {
+            out.push(ch);
+            continue
+        }
+        let mut spec = String::new();
+        while let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit() || *ch == '.') {
+            spec.push(ch);
+        }
+        let directive = chars.next()
+            .ok_or_else(|| invalid_directive("expects a directive after the last '~'.".to_string()))?;
+        let mut next_arg = || args.next().ok_or_else(|| Error::new(ErrorKind::ArityMismatch)
+            .with_message(format!("'format' expects an argument for the directive ~{spec}{directive}.")));
+        match directive.to_ascii_lowercase() {
+            'a' | 's' | '%' | '~' if !spec.is_empty() => return Err(invalid_directive(
+                format!("doesn't take a specifier in the directive ~{spec}{directive}."))),
+            'a' => out.push_str(&format!("{:#}", next_arg()?)),
+            's' => out.push_str(&next_arg()?.to_string()),
+            '%' => out.push('\n'),
+            '~' => out.push('~'),
+            'd' => {
+                let radix = match spec.as_str() {
+                    "" => 10,
+                    _ => spec.parse::<u32>().ok().filter(|radix| (2..=36).contains(radix)).ok_or_else(||
+                        invalid_directive(format!("expects a radix between 2 and 36, but got ~{spec}d.")))?
+                };
+                out.push_str(&format_integer(expect_int("format", next_arg()?)?, radix));
+            }
+            notation @ ('f' | 'e' | 'g') => {
+                let (width, precision) = spec.split_once('.').map_or((spec.as_str(), None), |(w, p)| (w, Some(p)));
+                // The formatter of std takes widths and precisions up to `u16::MAX`, and `~g` may add
+                // up to 4 digits to the precision for a small exponent.
+                let parse = |digits: &str| digits.parse::<usize>().ok().filter(|&n| n <= MAX_FIELD).ok_or_else(||
+                    invalid_directive(format!("expects a width and precision of at most {MAX_FIELD}, but got ~{spec}{directive}.")));
+                let width = if_or!(width.is_empty(), 0, parse(width)?);
+                let precision = precision.map(parse).transpose()?;
+                let x = match next_arg()?.value {
+                    TermValue::Int(n) => n as f64,
+                    TermValue::Rational(ratio) => ratio.to_f64(),
+                    TermValue::Float(x) => x,
+                    ref value => return Err(Error::new(ErrorKind::TypeMismatch).with_message(format!(
+                        "'format' expects a number for ~{directive}, but got the {} {value}.", value.type_name())))
+                };
+                let text = match (notation, precision) {
+                    _ if !x.is_finite() => format_float(x),
+                    ('f', Some(precision)) => format!("{x:.precision$}"),
+                    ('f', None) => format_float(x),
+                    ('e', Some(precision)) => format!("{x:.precision$e}"),
+                    ('e', None) => format!("{x:e}"),
+                    _ => format_general(x, precision.unwrap_or(6))
+                };
+                out.push_str(&format!("{text:>width$}"));
+            }
+            _ => return Err(invalid_directive(format!("doesn't know the directive ~{directive}.")))
+        }
+    }
+    match args.len() {
+        0 => Ok(out),
+        n => Err(Error::new(ErrorKind::ArityMismatch)
+            .with_message(format!("'format' got {n} argument(s) more than its directives take.")))
+    }
+}
+
+/// The largest width or precision of a number directive of `format`.
+const MAX_FIELD: usize = u16::MAX as usize - 4;
+
+/// The number with the significant digits of the precision, in scientific notation if its
+/// exponent is below -4 or not below the precision, and in fixed notation otherwise, without
+/// trailing zeros.
+fn format_general(x: f64, precision: usize) -> String {
+    let precision = precision.max(1);
+    let scientific = format!("{x:.*e}", precision - 1);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let trim = |digits: &str| if_or!(digits.contains('.'),
+        digits.trim_end_matches('0').trim_end_matches('.').to_string(), digits.to_string());
+    if exponent < -4 || exponent >= precision as i32 {
+        format!("{}e{exponent}", trim(mantissa))
+    } else {
+        trim(&format!("{x:.*}", (precision as i32 - 1 - exponent) as usize))
+    }
+}
+
+fn read_line(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    match input_of("read-line", args, 0, ctx)?.read_line() {
+        Ok(Some(line)) => Ok(Term::string(line)),
+        Ok(None) => Ok(Term::eof()),
+        Err(err) => Err(read_failed(err))
+    }
+}
+
+fn is_input_port(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::boolean(matches!(args[0].value, TermValue::Port(ref port) if port.is_input())))
+}
+
+fn is_output_port(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::boolean(matches!(args[0].value, TermValue::Port(ref port) if port.is_output())))
+}
+
+fn is_input_port_open(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let port = expect_port("input-port-open?", &args[0])?;
+    Ok(Term::boolean(port.is_input() && port.is_open()))
+}
+
+fn is_output_port_open(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let port = expect_port("output-port-open?", &args[0])?;
+    Ok(Term::boolean(port.is_output() && port.is_open()))
+}
+
+fn is_binary_port(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::boolean(matches!(args[0].value, TermValue::Port(ref port) if port.is_binary())))
+}
+
+fn is_textual_port(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::boolean(matches!(args[0].value, TermValue::Port(ref port) if !port.is_binary())))
+}
+
+fn open_file<T>(name: &str, args: &[Term], open: fn(&str) -> std::io::Result<T>) -> Result<T, Error> {
+    let path = expect_string(name, &args[0])?;
+    open(path).map_err(|err| Error::new(ErrorKind::IoError)
+        .with_message(format!("'{name}' failed to open \"{path}\": {err}")))
+}
+
+fn open_binary_input_file(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let file = open_file("open-binary-input-file", args, |path| std::fs::File::open(path))?;
+    Ok(Term::from(Port::binary_input(Source::new(std::io::BufReader::new(file)))))
+}
+
+fn open_binary_output_file(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let file = open_file("open-binary-output-file", args, |path| std::fs::File::create(path))?;
+    Ok(Term::from(Port::binary_output(Sink::new(file))))
+}
+
+/// The source of the binary input port given as the argument at `index`.
+fn binary_input_of(name: &str, args: &[Term], index: usize) -> Result<Source, Error> {
+    let port = args.get(index).unwrap();
+    expect_port(name, port)?.binary_source().ok_or_else(|| Error::new(ErrorKind::TypeMismatch)
+        .with_message(format!("'{name}' expects a binary input port.")))?
+}
+
+fn read_byte(name: &str, args: &[Term], read: fn(&Source) -> std::io::Result<Option<u8>>) -> Result<Term, Error> {
+    match read(&binary_input_of(name, args, 0)?) {
+        Ok(Some(byte)) => Ok(Term::int(byte as i64)),
+        Ok(None) => Ok(Term::eof()),
+        Err(err) => Err(read_failed(err))
+    }
+}
+
+fn read_u8(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    read_byte("read-u8", args, Source::read_u8)
+}
+
+fn peek_u8(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    read_byte("peek-u8", args, Source::peek_u8)
+}
+
+/// Files never block, so an open binary input port is always ready, even at its end.
+fn is_u8_ready(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    binary_input_of("u8-ready?", args, 0)?;
+    Ok(Term::boolean(true))
+}
+
+fn binary_output_of(name: &str, args: &[Term], index: usize) -> Result<Sink, Error> {
+    let port = args.get(index).unwrap();
+    expect_port(name, port)?.binary_sink().ok_or_else(|| Error::new(ErrorKind::TypeMismatch)
+        .with_message(format!("'{name}' expects a binary output port.")))?
+}
+
+fn write_u8(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let byte = expect_byte("write-u8", &args[0])?;
+    let mut sink = binary_output_of("write-u8", args, 1)?;
+    sink.write_all(&[byte]).and_then(|_| sink.flush()).map_err(io_failed)?;
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn read_failed(err: std::io::Error) -> Error {
+    Error::new(ErrorKind::IoError).with_message(format!("Failed to read the input: {err}"))
+}
+
+fn read_bytevector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let count = expect_count("read-bytevector", &args[0])?;
+    let bytes = binary_input_of("read-bytevector", args, 1)?.read_bytes(count).map_err(read_failed)?;
+    Ok(if_or!(bytes.is_empty() && count > 0, Term::eof(), bytevector_of(bytes)))
+}
+
+/// Fewer bytes than the range holds are read only at the end of the input.
+fn read_bytevector_into(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let name = "read-bytevector!";
+    let mut bytevector = expect_bytevector(name, &args[0])?.borrow_mut();
+    let source = binary_input_of(name, args, 1)?;
+    let (start, end) = expect_range(name, &mut args[2..].iter(), bytevector.len())?;
+    let bytes = source.read_bytes(end - start).map_err(read_failed)?;
+    bytevector[start..start + bytes.len()].copy_from_slice(&bytes);
+    Ok(if_or!(bytes.is_empty() && start < end, Term::eof(), Term::int(bytes.len() as i64)))
+}
+
+fn write_bytevector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let name = "write-bytevector";
+    let bytevector = expect_bytevector(name, &args[0])?.borrow();
+    let mut sink = binary_output_of(name, args, 1)?;
+    let (start, end) = expect_range(name, &mut args[2..].iter(), bytevector.len())?;
+    sink.write_all(&bytevector[start..end]).and_then(|_| sink.flush()).map_err(io_failed)?;
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn close_port(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    expect_port("close-port", &args[0])?.close();
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn close_input_port(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let port = expect_port("close-input-port", &args[0])?;
+    if !port.is_input() {
+        return Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message("'close-input-port' expects an input port.".to_string()))
+    }
+    port.close();
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn close_output_port(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let port = expect_port("close-output-port", &args[0])?;
+    if !port.is_output() {
+        return Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message("'close-output-port' expects an output port.".to_string()))
+    }
+    port.close();
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+/// The port is closed on the way out of the procedure, as by the `after` thunk of a
+/// `dynamic-wind`, so that it's closed even if the procedure fails.
+fn call_with_port(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let (port, procedure) = (args.next().unwrap(), args.next().unwrap());
+    let closed = expect_port("call-with-port", &port)?.clone();
+    wind(ctx,
+        |ctx| ctx.apply(procedure, LinkedList::from([port])),
+        |_| { closed.close(); Ok(()) })
+}
+
+fn eof_object(_: &mut Context, _: &[Term]) -> Result<Term, Error> {
+    Ok(Term::eof())
+}
+
+fn is_eof_object(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::boolean(matches!(args[0].value, TermValue::Eof)))
+}
+
+fn read(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let text = expect_string("read", &args[0])?;
+    let mut parser = SyntacticParser::new(share!(SrcInfo::new("<read>", text.as_str())))
+        .with_symbol_case(ctx.symbol_case());
+    match parser.parse_one() {
+        Some(node) => Ok(Term::datum(node?)),
+        None => Ok(Term::eof())
+    }
+}
+
+/// Every form is evaluated even if an earlier one fails, and the first error is returned.
+fn load(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let path = expect_string("load", &args[0])?;
+    let mut first_error = None;
+    for result in ctx.eval_file_incremental(std::path::Path::new(path)) {
+        if let Err(err) = result { first_error.get_or_insert(err); }
+    }
+    first_error.map_or(Ok(Term::from(UnitValue::Ignore)), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::{Term, TermValue, UnitValue};
+    use super::super::tests::{eval, eval_with_output};
+
+    #[test]
+    fn display_write_newline() {
+        let output = |src: &str| {
+            let (result, output) = eval_with_output(src);
+            result.unwrap();
+            output
+        };
+        assert_eq!(output(r#"(display "a")"#), "a");
+        assert_eq!(output(r#"(write "a")"#), r#""a""#);
+        assert_eq!(output("(newline)"), "\n");
+        // Strings are escaped by `write`, but written as they are by `display`.
+        assert_eq!(output("(display \"a\tb\") (newline) (write \"a\tb\")"), "a\tb\n\"a\\tb\"");
+        assert_eq!(output("(display (list 1 'x')) (write (list 1 'x'))"), r#"(1 x)(1 "x")"#);
+        assert_eq!(output("(display (vector 'a b' (list 'c'))) (display (cons 'x' 'y'))"), "#(a b (c))(x . y)");
+        assert_eq!(output("(newline (current-output-port))"), "\n");
+        assert_eq!(eval("(eq? (current-output-port) (current-output-port))").unwrap(), Term::boolean(true));
+        // The current ports are parameters, which `parameterize` rebinds for the output by default.
+        assert_eq!(eval(r#"(parameterize ((current-output-port (current-input-port))) (display "x"))"#)
+            .unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(output(r#"(guard (e (#t #f)) (parameterize ((current-output-port 1)) (display "x"))) (display "y")"#), "y");
+        assert_eq!(eval("(newline 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn format_directives() {
+        let text = |src: &str| match eval(src).unwrap().value {
+            TermValue::Str(s) => s,
+            value => panic!("{value:?} is not a string.")
+        };
+        assert_eq!(text(r#"(format #f "~16d" 255)"#), "ff");
+        assert_eq!(text(r#"(format #f "~8d" 255)"#), "377");
+        assert_eq!(text(r#"(format #f "~2d" 10)"#), "1010");
+        assert_eq!(text(r#"(format #f "~d and ~36D" -42 35)"#), "-42 and z");
+        assert_eq!(text(r#"(format #f "~a ~s~%~~" "x" "x")"#), "x \"x\"\n~");
+        assert_eq!(text(r#"(format #f "~a ~s" (list "x") (list "x"))"#), "(x) (\"x\")");
+        assert_eq!(text(r#"(format #f "[~8.3f] [~.2f] [~f]" 3.14159 2 0.5)"#), "[   3.142] [2.00] [0.5]");
+        assert_eq!(text(r#"(format #f "~.3e ~e" 1234.5 0.25)"#), "1.234e3 2.5e-1");
+        assert_eq!(text(r#"(format #f "~g ~.3g ~.3g ~8.2g" 0.5 1234.5 0.0001 0.00001)"#), "0.5 1.23e3 0.0001     1e-5");
+        let (result, output) = eval_with_output(r#"(format #t "~a=~d" "x" 1) (format (current-output-port) "!")"#);
+        assert_eq!((result.unwrap(), output.as_str()), (Term::from(UnitValue::Ignore), "x=1!"));
+
+        let error_kind = |src: &str| eval(src).unwrap_err().kind();
+        assert_eq!(error_kind(r#"(format #f "~1d" 1)"#), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(r#"(format #f "~37d" 1)"#), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(r#"(format #f "~1.2.3f" 1)"#), ErrorKind::InvalidArgument);
+        // Widths and precisions too large for the formatter are rejected rather than panicking.
+        for directive in ["~.70000f", "~70000f", "~.65535g", "~65535e", "~99999999999999999999999f"] {
+            assert_eq!(error_kind(&format!("(format #f \"{directive}\" 1.0)")), ErrorKind::InvalidArgument, "{directive}");
+        }
+        assert!(eval(r#"(format #f "~.65531g" 0.001)"#).unwrap().to_string().starts_with("\"0.00100000000000000002"));
+        assert_eq!(eval(r#"(format #f "~65531f" 1.5)"#).unwrap().to_string().len(), 65531 + 2);
+        assert_eq!(error_kind(r#"(format #f "~2a" 1)"#), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(r#"(format #f "~q" 1)"#), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(r#"(format #f "~")"#), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(r#"(format #f "~d ~d" 1)"#), ErrorKind::ArityMismatch);
+        assert_eq!(error_kind(r#"(format #f "~d" 1 2)"#), ErrorKind::ArityMismatch);
+        assert_eq!(error_kind(r#"(format #f "~d" 1.5)"#), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(r#"(format #f "~f" "x")"#), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn port_lifecycle() {
+        let with_port = |port: &str, body: &str| eval(&format!("(($lambda (p) {body}) ({port}))"));
+        assert_eq!(with_port("current-input-port", "(input-port-open? p)").unwrap(), Term::boolean(true));
+        assert_eq!(with_port("current-input-port", "(close-port p) (input-port-open? p)").unwrap(), Term::boolean(false));
+        assert_eq!(with_port("current-output-port", "(output-port-open? p)").unwrap(), Term::boolean(true));
+        assert_eq!(with_port("current-output-port", "(close-output-port p) (output-port-open? p)").unwrap(),
+            Term::boolean(false));
+        assert_eq!(with_port("current-output-port", "(input-port-open? p)").unwrap(), Term::boolean(false));
+
+        // Closing a port twice is harmless, but reading or writing a closed port isn't.
+        assert_eq!(with_port("current-input-port", "(close-port p) (close-input-port p) (input-port-open? p)").unwrap(),
+            Term::boolean(false));
+        let err = with_port("current-input-port", "(close-input-port p) (read-line p)").unwrap_err();
+        assert_eq!((err.kind(), err.message().as_str()), (ErrorKind::InvalidArgument, "Port is closed"));
+        let err = with_port("current-output-port", r#"(close-port p) (display "x" p)"#).unwrap_err();
+        assert_eq!((err.kind(), err.message().as_str()), (ErrorKind::InvalidArgument, "Port is closed"));
+        assert_eq!(with_port("current-output-port", "(close-input-port p)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+
+        // The port is closed once the procedure returns, or fails.
+        assert_eq!(with_port("current-output-port", "(call-with-port p output-port-open?)").unwrap(), Term::boolean(true));
+        assert_eq!(with_port("current-output-port", "(call-with-port p output-port-open?) (output-port-open? p)").unwrap(),
+            Term::boolean(false));
+        let handled = with_port("current-output-port",
+            "(guard (e (#t (output-port-open? p))) (call-with-port p car))");
+        assert_eq!(handled.unwrap(), Term::boolean(false));
+    }
+
+    #[test]
+    fn read_data() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(read "(a b)")"#), "(a b)");
+        assert_eq!(text(r#"(car (read "(a b)"))"#), "a");
+        assert_eq!(text(r#"(type-of (car (read "[a b]")))"#), "symbol");
+        assert_eq!(text(r#"(read "(1 (2.5 #t) 'x' ())")"#), r#"(1 (2.5 #t) "x" ())"#);
+        assert_eq!(text(r#"(read "(gcd 4 6) ignored")"#), "(gcd 4 6)");
+        assert_eq!(text(r#"(read "42")"#), "42");
+        assert_eq!(text(r#"(eof-object? (read "  "))"#), "#t");
+        assert_eq!(eval(r#"(read "(")"#).unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval(r#"(read ")")"#).unwrap_err().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(eval("(read 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn binary_ports() {
+        let path = std::env::temp_dir().join(format!("thesis-binary-{}.bin", std::process::id()));
+        let path = path.to_string_lossy().replace('\\', "/");
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(&format!(r#"($define p (open-binary-output-file "{path}"))
+            (write-u8 0 p) (write-u8 65 p) (write-u8 255 p) (close-port p)
+            (list (binary-port? p) (textual-port? p) (output-port? p) (output-port-open? p))"#)), "(#t #f #t #f)");
+        assert_eq!(std::fs::read(&path).unwrap(), [0, 65, 255]);
+        assert_eq!(text(&format!(r#"($define p (open-binary-input-file "{path}"))
+            (list (input-port? p) (u8-ready? p) (peek-u8 p) (read-u8 p) (read-u8 p) (peek-u8 p) (read-u8 p)
+                (eof-object? (peek-u8 p)) (eof-object? (read-u8 p)) (u8-ready? p))"#)), "(#t #t 0 0 65 255 255 #t #t #t)");
+
+        // Binary ports only take bytes, and textual ports only characters.
+        let error_kind = |src: String| eval(&src).unwrap_err().kind();
+        let input = format!(r#"(open-binary-input-file "{path}")"#);
+        assert_eq!(error_kind(format!("(read-line {input})")), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(read-u8 (current-input-port))".to_string()), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(format!("(write-u8 1 {input})")), ErrorKind::TypeMismatch);
+        let output = format!(r#"(open-binary-output-file "{path}")"#);
+        assert_eq!(error_kind(format!(r#"(display "a" {output})"#)), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(format!("(write-u8 256 {output})")), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(format!("(write-u8 -1 {output})")), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(format!("(write-u8 1.0 {output})")), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(format!("(($lambda (p) (close-port p) (read-u8 p)) {input})")), ErrorKind::InvalidArgument);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error_kind(format!(r#"(open-binary-input-file "{path}")"#)), ErrorKind::IoError);
+    }
+
+    #[test]
+    fn bytevector_ports() {
+        let path = std::env::temp_dir().join(format!("thesis-bytevector-{}.bin", std::process::id()));
+        let path = path.to_string_lossy().replace('\\', "/");
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(&format!(r#"($define p (open-binary-output-file "{path}"))
+            (write-bytevector (bytevector 1 2 3) p) (write-bytevector (bytevector 4 5 6 7) p 1 3) (close-port p) (output-port-open? p)"#)), "#f");
+        assert_eq!(std::fs::read(&path).unwrap(), [1, 2, 3, 5, 6]);
+        assert_eq!(text(&format!(r#"($define p (open-binary-input-file "{path}"))
+            (list (read-bytevector 2 p) (read-bytevector 0 p) (read-bytevector 5 p) (eof-object? (read-bytevector 1 p))
+                (read-bytevector 0 p))"#)), "(#u8(1 2) #u8() #u8(3 5 6) #t #u8())");
+        assert_eq!(text(&format!(r#"($define p (open-binary-input-file "{path}")) ($define b (make-bytevector 4 9))
+            (list (read-bytevector! b p 1) (bytevector-u8-ref b 0) (bytevector-u8-ref b 3) (read-bytevector! b p 0 2) b (eof-object? (read-bytevector! b p)) (read-bytevector! b p 4))"#)),
+            "(3 9 3 2 #u8(5 6 2 3) #t 0)");
+
+        let error_kind = |src: String| eval(&src).unwrap_err().kind();
+        let input = format!(r#"(open-binary-input-file "{path}")"#);
+        assert_eq!(error_kind(format!("(read-bytevector -1 {input})")), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(format!("(read-bytevector! (bytevector 1) {input} 0 2)")), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(format!("(write-bytevector (bytevector 1) {input})")), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(format!("(read-bytevector 1 (open-binary-output-file \"{path}\"))")), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(format!("(write-bytevector (vector 1) (open-binary-output-file \"{path}\"))")), ErrorKind::TypeMismatch);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
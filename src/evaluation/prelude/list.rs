@@ -0,0 +1,681 @@
+use std::collections::LinkedList;
+use std::rc::Rc;
+
+use crate::error::{Error, ErrorKind};
+use super::super::context::Context;
+#[cfg(feature = "mutable-pairs")]
+use super::super::term::UnitValue;
+use super::super::term::{MultipleValues, PairValue, Ratio, Term, TermValue};
+use super::{Arity::*, Builtin, expect_count};
+use super::boolean::{is_equal, is_eqv};
+use super::number::exact_fraction;
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("cons", Exactly(2), "A new pair of the two values.", cons),
+    Builtin::new("car", Exactly(1), "The first element of the pair.", car),
+    Builtin::new("cdr", Exactly(1), "The second element of the pair.", cdr),
+    Builtin::new("list", AtLeast(0), "A new list of the arguments.", list),
+    Builtin::new("make-list", Between(1, 2), "A new list of the count copies of the value, or of `#f`.", make_list),
+    Builtin::new("iota", Between(1, 3), "A new list of the count numbers from the start, 0 by default, by the step, 1 by default.", iota),
+    Builtin::new("list-tabulate", Exactly(2), "A new list of the results of calling the procedure on each index up to the count.", list_tabulate),
+    Builtin::new("every", AtLeast(2), "The last result of the predicate, if it holds for the elements at every position of the lists, or `#f`.", every),
+    Builtin::new("any", AtLeast(2), "The first true result of the predicate on the elements at a position of the lists, or `#f`.", any),
+    Builtin::new("foldl", Exactly(3), "The result of calling the procedure on the accumulator, from the initial value, and each element of the list in turn.", foldl),
+    Builtin::new("reduce", Exactly(3), "Combine each element of the list with the result so far, as `(f elem acc)`, from the first element, or the initial value if the list is empty.", reduce),
+    Builtin::new("find", Exactly(2), "The first element of the list satisfying the predicate, or `#f`.", find),
+    Builtin::new("take", Exactly(2), "A new list of the first count elements of the list.", take),
+    Builtin::new("drop", Exactly(2), "The list after its first count elements.", drop),
+    Builtin::new("take-while", Exactly(2), "A new list of the leading elements of the list satisfying the predicate.", take_while),
+    Builtin::new("drop-while", Exactly(2), "The list after its leading elements satisfying the predicate.", drop_while),
+    Builtin::new("span", Exactly(2), "Two values: the leading elements of the list satisfying the predicate, and the rest.", span),
+    Builtin::new("break", Exactly(2), "Two values: the leading elements of the list not satisfying the predicate, and the rest.", break_list),
+    Builtin::new("delete", Between(2, 3), "The list without the elements equal to the value, by `equal?` or the given procedure.", delete),
+    Builtin::new("delete!", Between(2, 3), "The same as `delete`.", delete),
+    Builtin::new("delq", Exactly(2), "The list without the elements `eq?` to the value.", delq),
+    Builtin::new("delete-duplicates", Between(1, 2), "The list with only the first of the equal elements, by `equal?` or the given procedure.", delete_duplicates),
+    Builtin::new("lset-union", AtLeast(1), "The elements of any of the lists, as sets equal by the procedure.", lset_union),
+    Builtin::new("lset-intersection", AtLeast(2), "The elements of the first list which are in every other list, by the procedure.", lset_intersection),
+    Builtin::new("lset-difference", AtLeast(2), "The elements of the first list which are in no other list, by the procedure.", lset_difference),
+    Builtin::new("null?", Exactly(1), "Whether the value is the empty list.", is_null),
+    Builtin::new("pair?", Exactly(1), "Whether the value is a pair.", is_pair),
+    Builtin::new("list?", Exactly(1), "Whether the value is a proper list.", is_list),
+    #[cfg(feature = "mutable-pairs")]
+    Builtin::new("set-car!", Exactly(2), "Replace the first element of the pair.", set_car),
+    #[cfg(feature = "mutable-pairs")]
+    Builtin::new("set-cdr!", Exactly(2), "Replace the second element of the pair.", set_cdr),
+];
+
+fn expect_pair<'a>(name: &str, arg: &'a Term) -> Result<&'a PairValue, Error> {
+    match arg.value {
+        TermValue::Pair(ref pair) => Ok(pair),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a pair, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn cons(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    Ok(Term::cons(args.next().unwrap(), args.next().unwrap()))
+}
+
+fn car(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(expect_pair("car", &args[0])?.borrow().0.clone())
+}
+
+fn cdr(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(expect_pair("cdr", &args[0])?.borrow().1.clone())
+}
+
+fn list(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(list_from(args.iter().cloned()))
+}
+
+/// A new list of the terms in order.
+fn list_from<I: DoubleEndedIterator<Item = Term>>(terms: I) -> Term {
+    terms.rev().fold(Term::nil(), |tail, head| Term::cons(head, tail))
+}
+
+fn make_list(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let count = expect_count("make-list", &args[0])?;
+    let fill = args.get(1).cloned().unwrap_or(Term::boolean(false));
+    Ok((0..count).fold(Term::nil(), |tail, _| Term::cons(fill.clone(), tail)))
+}
+
+/// The numbers are exact if both the start and the step are, and each one is computed from
+/// the start rather than the previous number, so that inexact steps don't accumulate errors.
+fn iota(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let count = expect_count("iota", args.next().unwrap())?;
+    let mut number = |default: i64| match args.next().map(|arg| &arg.value) {
+        None => Ok(TermValue::Int(default)),
+        Some(value @ (TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_))) => Ok(value.clone()),
+        Some(value) => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'iota' expects a number, but got the {} {value}.", value.type_name())))
+    };
+    let (start, step) = (number(0)?, number(1)?);
+    let numbers = (0..count).map(|i| match (exact_fraction(&start), exact_fraction(&step)) {
+        (Some((a, b)), Some((c, d))) => (i as i128).checked_mul(c * b)
+            .and_then(|offset| (a * d).checked_add(offset))
+            .and_then(|numerator| Ratio::reduce(numerator, b * d))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidArgument)
+                .with_message("'iota' counts out of the range of exact integers.".to_string())),
+        _ => Ok(Term::from(to_float(&start) + i as f64 * to_float(&step)))
+    });
+    Ok(list_from(numbers.collect::<Result<Vec<Term>, Error>>()?.into_iter()))
+}
+
+fn to_float(value: &TermValue) -> f64 {
+    match *value {
+        TermValue::Int(n) => n as f64,
+        TermValue::Rational(ratio) => ratio.to_f64(),
+        TermValue::Float(x) => x,
+        _ => unreachable!("Only numbers are converted.")
+    }
+}
+
+fn list_tabulate(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let count = expect_count("list-tabulate", &args.next().unwrap())?;
+    let procedure = args.next().unwrap();
+    let elements = (0..count)
+        .map(|i| ctx.apply(procedure.clone(), LinkedList::from([Term::int(i as i64)])))
+        .collect::<Result<Vec<Term>, Error>>()?;
+    Ok(list_from(elements.into_iter()))
+}
+
+/// The head and the tail of the list, or `None` if it is empty.
+fn next_pair(name: &str, list: &Term) -> Result<Option<(Term, Term)>, Error> {
+    match list.value {
+        TermValue::Nil => Ok(None),
+        TermValue::Pair(ref pair) => Ok(Some(pair.borrow().clone())),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a list, but got the {} {value}.", value.type_name())))
+    }
+}
+
+/// Call the predicate on the elements at each position of the lists in turn, until `stop`
+/// holds for a result or the shortest list runs out, returning the last result if any.
+fn traverse(name: &str, args: &[Term], ctx: &mut Context, stop: fn(&Term) -> bool) -> Result<Option<Term>, Error> {
+    let (predicate, lists) = args.split_first().unwrap();
+    let mut lists = lists.to_vec();
+    let mut result = None;
+    loop {
+        let mut elements = LinkedList::new();
+        for list in lists.iter_mut() {
+            let Some((head, tail)) = next_pair(name, list)? else { return Ok(result) };
+            elements.push_back(head);
+            *list = tail;
+        }
+        let value = ctx.apply(predicate.clone(), elements)?;
+        let done = stop(&value);
+        result = Some(value);
+        if done { return Ok(result) }
+    }
+}
+
+fn every(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(traverse("every", args, ctx, Term::is_false)?.unwrap_or(Term::boolean(true)))
+}
+
+fn any(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    match traverse("any", args, ctx, |value| !value.is_false())? {
+        Some(value) if !value.is_false() => Ok(value),
+        _ => Ok(Term::boolean(false))
+    }
+}
+
+/// Thread the accumulator through the procedure, which takes it before each element.
+fn fold(name: &str, procedure: &Term, mut accumulator: Term, mut list: Term, ctx: &mut Context) -> Result<Term, Error> {
+    while let Some((head, tail)) = next_pair(name, &list)? {
+        accumulator = ctx.apply(procedure.clone(), LinkedList::from([accumulator, head]))?;
+        list = tail;
+    }
+    Ok(accumulator)
+}
+
+fn foldl(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    fold("foldl", &args[0], args[1].clone(), args[2].clone(), ctx)
+}
+
+fn reduce(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let Some((mut accumulator, mut list)) = next_pair("reduce", &args[2])? else { return Ok(args[1].clone()) };
+    // Unlike `foldl`, the element is passed before the result so far, as in SRFI 1.
+    while let Some((head, tail)) = next_pair("reduce", &list)? {
+        accumulator = ctx.apply(args[0].clone(), LinkedList::from([head, accumulator]))?;
+        list = tail;
+    }
+    Ok(accumulator)
+}
+
+fn find(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let (predicate, mut list) = (args.next().unwrap(), args.next().unwrap());
+    while let Some((head, tail)) = next_pair("find", &list)? {
+        if !ctx.apply(predicate.clone(), LinkedList::from([head.clone()]))?.is_false() {
+            return Ok(head)
+        }
+        list = tail;
+    }
+    Ok(Term::boolean(false))
+}
+
+/// Split the first count elements off the list, failing if it is shorter than that.
+fn split_at(name: &str, args: &[Term]) -> Result<(Vec<Term>, Term), Error> {
+    let mut args = args.iter().cloned();
+    let mut list = args.next().unwrap();
+    let count = expect_count(name, &args.next().unwrap())?;
+    let mut prefix = vec![];
+    while prefix.len() < count {
+        let Some((head, tail)) = next_pair(name, &list)? else {
+            return Err(Error::new(ErrorKind::InvalidArgument)
+                .with_message(format!("'{name}' expects at most {} elements, but got a count of {count}.", prefix.len())))
+        };
+        prefix.push(head);
+        list = tail;
+    }
+    Ok((prefix, list))
+}
+
+fn take(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(list_from(split_at("take", args)?.0.into_iter()))
+}
+
+// The rest of the list is shared rather than copied.
+fn drop(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(split_at("drop", args)?.1)
+}
+
+/// Split the leading elements for which the predicate `holds`, or doesn't, off the list.
+fn split_while(name: &str, args: &[Term], ctx: &mut Context, holds: bool) -> Result<(Vec<Term>, Term), Error> {
+    let mut args = args.iter().cloned();
+    let (predicate, mut list) = (args.next().unwrap(), args.next().unwrap());
+    let mut prefix = vec![];
+    while let Some((head, tail)) = next_pair(name, &list)? {
+        if ctx.apply(predicate.clone(), LinkedList::from([head.clone()]))?.is_false() == holds { break }
+        prefix.push(head);
+        list = tail;
+    }
+    Ok((prefix, list))
+}
+
+fn take_while(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(list_from(split_while("take-while", args, ctx, true)?.0.into_iter()))
+}
+
+fn drop_while(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(split_while("drop-while", args, ctx, true)?.1)
+}
+
+fn span(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (prefix, rest) = split_while("span", args, ctx, true)?;
+    Ok(Term::from(MultipleValues(vec![list_from(prefix.into_iter()), rest])))
+}
+
+fn break_list(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (prefix, rest) = split_while("break", args, ctx, false)?;
+    Ok(Term::from(MultipleValues(vec![list_from(prefix.into_iter()), rest])))
+}
+
+/// The elements of the list in order.
+pub(super) fn elements(name: &str, mut list: Term) -> Result<Vec<Term>, Error> {
+    let mut elements = vec![];
+    while let Some((head, tail)) = next_pair(name, &list)? {
+        elements.push(head);
+        list = tail;
+    }
+    Ok(elements)
+}
+
+/// An equivalence of elements, which is either built in or a procedure of the program.
+enum Equivalence {
+    Native(fn(&Term, &Term) -> bool),
+    Procedure(Term)
+}
+
+impl Equivalence {
+    fn holds(&self, ctx: &mut Context, a: &Term, b: &Term) -> Result<bool, Error> {
+        match self {
+            Equivalence::Native(same) => Ok(same(a, b)),
+            Equivalence::Procedure(same) => Ok(!ctx.apply(same.clone(), LinkedList::from([a.clone(), b.clone()]))?.is_false())
+        }
+    }
+
+    /// Whether any of the elements is equivalent to the value, stopping at the first one.
+    fn any(&self, ctx: &mut Context, value: &Term, elements: &[Term]) -> Result<bool, Error> {
+        for element in elements {
+            if self.holds(ctx, value, element)? { return Ok(true) }
+        }
+        Ok(false)
+    }
+}
+
+fn remove_equivalent(name: &str, value: Term, list: Term, same: Equivalence, ctx: &mut Context) -> Result<Term, Error> {
+    let mut kept = vec![];
+    for element in elements(name, list)? {
+        if !same.holds(ctx, &value, &element)? { kept.push(element) }
+    }
+    Ok(list_from(kept.into_iter()))
+}
+
+fn delete(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let (value, list) = (args.next().unwrap(), args.next().unwrap());
+    let same = args.next().map_or(Equivalence::Native(is_equal), Equivalence::Procedure);
+    remove_equivalent("delete", value, list, same, ctx)
+}
+
+fn delq(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    remove_equivalent("delq", args.next().unwrap(), args.next().unwrap(), Equivalence::Native(is_eqv), ctx)
+}
+
+fn delete_duplicates(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let list = args.next().unwrap();
+    let same = args.next().map_or(Equivalence::Native(is_equal), Equivalence::Procedure);
+    let mut kept: Vec<Term> = vec![];
+    for element in elements("delete-duplicates", list)? {
+        if !same.any(ctx, &element, &kept)? { kept.push(element) }
+    }
+    Ok(list_from(kept.into_iter()))
+}
+
+/// The equivalence procedure and the elements of each list given after it.
+fn lset_args(name: &str, args: &[Term]) -> Result<(Equivalence, Vec<Vec<Term>>), Error> {
+    let mut args = args.iter().cloned();
+    let same = Equivalence::Procedure(args.next().unwrap());
+    let lists = args.map(|list| elements(name, list)).collect::<Result<Vec<_>, Error>>()?;
+    Ok((same, lists))
+}
+
+/// Following SRFI 1, the elements missing from the first list are added to its front.
+fn lset_union(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (same, lists) = lset_args("lset-union", args)?;
+    let mut lists = lists.into_iter();
+    let mut union: Vec<Term> = lists.next().unwrap_or_default();
+    union.reverse();
+    for list in lists {
+        for element in list {
+            if !same.any(ctx, &element, &union)? { union.push(element) }
+        }
+    }
+    Ok(list_from(union.into_iter().rev()))
+}
+
+/// Keep the elements of the first list for which being in each other list is `wanted`.
+fn filter_lset(name: &str, args: &[Term], ctx: &mut Context, wanted: bool) -> Result<Term, Error> {
+    let (same, mut lists) = lset_args(name, args)?;
+    let first = lists.remove(0);
+    let mut kept = vec![];
+    'elements: for element in first {
+        for list in &lists {
+            if same.any(ctx, &element, list)? != wanted { continue 'elements }
+        }
+        kept.push(element);
+    }
+    Ok(list_from(kept.into_iter()))
+}
+
+fn lset_intersection(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    filter_lset("lset-intersection", args, ctx, true)
+}
+
+fn lset_difference(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    filter_lset("lset-difference", args, ctx, false)
+}
+
+fn is_null(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::Nil).into())
+}
+
+fn is_pair(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::Pair(_)).into())
+}
+
+/// Follow the cdrs with Floyd's algorithm, so that a circular list is rejected rather than
+/// traversed forever.
+fn is_list(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut slow = args[0].clone();
+    let mut fast = slow.clone();
+    loop {
+        for _ in 0..2 {
+            fast = match fast.value {
+                TermValue::Nil => return Ok(true.into()),
+                TermValue::Pair(ref pair) => pair.borrow().1.clone(),
+                _ => return Ok(false.into())
+            };
+        }
+        // The fast pointer has already passed through the pair.
+        if let TermValue::Pair(ref pair) = slow.value {
+            let next = pair.borrow().1.clone();
+            slow = next;
+        }
+        if let (TermValue::Pair(a), TermValue::Pair(b)) = (&slow.value, &fast.value) {
+            if Rc::ptr_eq(a, b) { return Ok(false.into()) }
+        }
+    }
+}
+
+#[cfg(feature = "mutable-pairs")]
+fn set_pair(name: &str, args: &[Term], set: fn(&mut (Term, Term), Term)) -> Result<Term, Error> {
+    let mut args = args.iter().cloned();
+    let pair = args.next().unwrap();
+    set(&mut expect_pair(name, &pair)?.borrow_mut(), args.next().unwrap());
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+#[cfg(feature = "mutable-pairs")]
+fn set_car(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    set_pair("set-car!", args, |pair, value| pair.0 = value)
+}
+
+#[cfg(feature = "mutable-pairs")]
+fn set_cdr(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    set_pair("set-cdr!", args, |pair, value| pair.1 = value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::{Term, TermValue};
+    use super::super::tests::{boolean, eval, eval_with_output};
+
+    #[test]
+    fn pairs_and_lists() {
+        assert_eq!(eval("(cons 1 2)").unwrap(), Term::cons(Term::int(1), Term::int(2)));
+        assert_eq!(eval("(car (cons 1 2))").unwrap(), Term::int(1));
+        assert_eq!(eval("(cdr (list 1 2))").unwrap(), Term::cons(Term::int(2), Term::nil()));
+        assert_eq!(eval("(list)").unwrap(), Term::nil());
+        assert!(boolean("(null? ())"));
+        assert!(!boolean("(null? (list 1))"));
+        assert!(boolean("(pair? (cons 1 ()))"));
+        assert!(!boolean("(pair? ())"));
+        assert_eq!(eval("(car ())").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(cdr 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn list_construction() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(make-list 3 'a')"), r#"("a" "a" "a")"#);
+        assert_eq!(text("(make-list 2)"), "(#f #f)");
+        assert_eq!(text("(make-list 0 1)"), "()");
+        assert_eq!(text("(iota 5)"), "(0 1 2 3 4)");
+        assert_eq!(text("(iota 3 1)"), "(1 2 3)");
+        assert_eq!(text("(iota 3 1 2)"), "(1 3 5)");
+        assert_eq!(text("(iota 3 0 -1)"), "(0 -1 -2)");
+        assert_eq!(text("(iota 4 0 0.1)"), "(0.0 0.1 0.2 0.30000000000000004)");
+        assert_eq!(text("(iota 2 1.5)"), "(1.5 2.5)");
+        assert_eq!(text("(iota 0)"), "()");
+        assert_eq!(text("(list-tabulate 3 list)"), "((0) (1) (2))");
+        assert_eq!(text("(list-tabulate 4 ($lambda (i) (gcd i 6)))"), "(6 1 2 3)");
+        assert_eq!(text("(list-tabulate 3 ($lambda (i) (* i i)))"), "(0 1 4)");
+        // Each index is passed alone, so the product of it is the index itself.
+        assert_eq!(text("(list-tabulate 3 *)"), "(0 1 2)");
+        for src in ["(make-list -1)", "(iota -1)", "(list-tabulate -2 list)"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidArgument);
+        }
+        assert_eq!(eval("(iota 2 0 9223372036854775807)").unwrap().to_string(), "(0 9223372036854775807)");
+        assert_eq!(eval("(iota 3 0 9223372036854775807)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(iota 3 (/ 1 2) (/ 1 2))").unwrap().to_string(), "(1/2 1 3/2)");
+        assert_eq!(eval("(iota 1.5)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(iota 2 #t)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn every_and_any() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(every pair? ())"), "#t");
+        assert_eq!(text("(any pair? ())"), "#f");
+        assert_eq!(text("(every car (list (list 1) (list 2)))"), "2");
+        assert_eq!(text("(every car (list (list 1) (list #f) (list 3)))"), "#f");
+        assert_eq!(text("(any car (list (list #f) (list 2) (list 3)))"), "2");
+        assert_eq!(text("(any car (list (list #f)))"), "#f");
+        assert_eq!(text("(every cons (list 1 2) (list 3 4 5))"), "(2 . 4)");
+        assert_eq!(text("(any ($lambda (a b) ($if (null? a) b #f)) (list 1 () 3) (list 4 5))"), "5");
+        assert_eq!(text("(every pair? () (list 1))"), "#t");
+
+        // The predicate isn't called past the element that decides the result.
+        let counted = |src: &str| {
+            let (result, output) = eval_with_output(&format!("($define (f x) (display '.') x) {src}"));
+            (result.unwrap().to_string(), output)
+        };
+        assert_eq!(counted("(every f (list 1 #f 3 4))"), ("#f".to_string(), "..".to_string()));
+        assert_eq!(counted("(any f (list #f 2 #f 4))"), ("2".to_string(), "..".to_string()));
+        assert_eq!(counted("(every f (list 1 2 3))"), ("3".to_string(), "...".to_string()));
+        assert_eq!(eval("(any car (list (list 1) 2))").unwrap().to_string(), "1");
+
+        assert_eq!(eval("(every pair? 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(any pair?)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn folds() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(foldl + 0 (list 1 2 3))"), "6");
+        assert_eq!(text("(foldl + 'init' ())"), r#""init""#);
+        assert_eq!(text("(foldl cons () (list 1 2 3))"), "(((() . 1) . 2) . 3)");
+        assert_eq!(text("(foldl ($lambda (acc x) (- acc x)) 10 (list 1 2 3))"), "4");
+        assert_eq!(text("(reduce + 0 (list 1 2 3))"), "6");
+        assert_eq!(text("(reduce cons 0 (list 1 2 3))"), "(3 2 . 1)");
+        assert_eq!(text("(reduce - 0 (list 1 2 3))"), "2");
+        assert_eq!(text("(reduce + 'none' ())"), r#""none""#);
+        assert_eq!(text("(reduce + 0 (list 5))"), "5");
+
+        assert_eq!(eval("(foldl + 0 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(foldl + 0 (cons 1 2))").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(reduce + 0 #t)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(foldl ($lambda (x) x) 0 (list 1))").unwrap_err().kind(), ErrorKind::ArityMismatch);
+        assert_eq!(eval("(foldl + 0)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn list_iteration() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(find odd? (list 2 4 5 7))"), "5");
+        assert_eq!(text("(find odd? (list 2 4))"), "#f");
+        assert_eq!(text("(find odd? ())"), "#f");
+        assert_eq!(text("(take (list 1 2 3) 2)"), "(1 2)");
+        assert_eq!(text("(take (list 1 2 3) 3)"), "(1 2 3)");
+        assert_eq!(text("(take () 0)"), "()");
+        assert_eq!(text("(drop (list 1 2 3) 2)"), "(3)");
+        assert_eq!(text("(drop (list 1 2 3) 0)"), "(1 2 3)");
+        assert_eq!(text("(drop (list 1 2 3) 3)"), "()");
+        assert_eq!(text("(drop (cons 1 2) 1)"), "2");
+        for src in ["(take (list 1 2) 3)", "(drop (list 1 2) 3)", "(take () 1)", "(drop (list 1) -1)"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidArgument, "{src}");
+        }
+        assert_eq!(eval("(take 1 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+
+        assert_eq!(text("(take-while odd? (list 1 3 4 5))"), "(1 3)");
+        assert_eq!(text("(take-while odd? (list 2 3))"), "()");
+        assert_eq!(text("(take-while odd? (list 1 3))"), "(1 3)");
+        assert_eq!(text("(drop-while odd? (list 1 3 4 5))"), "(4 5)");
+        assert_eq!(text("(drop-while odd? (list 1 3))"), "()");
+        assert_eq!(text("(drop-while odd? ())"), "()");
+        assert_eq!(text("(call-with-values ($lambda () (span odd? (list 1 3 4 5))) list)"), "((1 3) (4 5))");
+        assert_eq!(text("(call-with-values ($lambda () (span odd? ())) list)"), "(() ())");
+        assert_eq!(text("(call-with-values ($lambda () (break odd? (list 2 4 5 6))) list)"), "((2 4) (5 6))");
+        assert_eq!(text("(call-with-values ($lambda () (break odd? (list 1 2))) list)"), "(() (1 2))");
+        assert_eq!(text("(call-with-values ($lambda () (break odd? (list 2 4))) list)"), "((2 4) ())");
+    }
+
+    #[test]
+    fn set_operations() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(delete 2 (list 1 2 3 2))"), "(1 3)");
+        assert_eq!(text("(delete (list 1) (list (list 1) 2 (list 1)))"), "(2)");
+        assert_eq!(text("(delete 1 ())"), "()");
+        assert_eq!(text("(delete 1 (list 1))"), "()");
+        assert_eq!(text("(delete 3 (list 1 2 3 4 5) ($lambda (x y) (eqv? (gcd x y) 1)))"), "(3)");
+        assert_eq!(text("(delete! 2 (list 1 2))"), "(1)");
+        assert_eq!(text("(delq (list 1) (list (list 1) 2))"), "((1) 2)");
+        assert_eq!(text("(delete-duplicates (list 3 1 3 2 1 3))"), "(3 1 2)");
+        assert_eq!(text("(delete-duplicates (list 'a' (list 1) 'a' (list 1)))"), r#"("a" (1))"#);
+        assert_eq!(text("(delete-duplicates ())"), "()");
+        assert_eq!(text("(delete-duplicates (list 4 6 9 8 3) ($lambda (x y) (not (eqv? (gcd x y) 1))))"), "(4 9)");
+
+        assert_eq!(text("(lset-union eq? (list 'a' 'b' 'c') (list 'a' 'e' 'i'))"), r#"("i" "e" "a" "b" "c")"#);
+        assert_eq!(text("(lset-union eq? () (list 1) (list 1 2))"), "(2 1)");
+        assert_eq!(text("(lset-union eq?)"), "()");
+        assert_eq!(text("(lset-intersection eq? (list 1 2 3 4) (list 4 2 5) (list 2 4 6))"), "(2 4)");
+        assert_eq!(text("(lset-intersection eq? (list 1) ())"), "()");
+        assert_eq!(text("(lset-difference eqv? (list 1 2 3 4) (list 4 2 5) (list 1))"), "(3)");
+        assert_eq!(text("(lset-difference eqv? (list 1))"), "(1)");
+        assert_eq!(text("(lset-intersection equal? (list (list 1) (list 2)) (list (list 2)))"), "((2))");
+
+        // The comparison stops at the first equal element.
+        let (result, output) = eval_with_output("(lset-intersection ($lambda (x y) (display '.') (eqv? x y)) (list 1) (list 1 1 1))");
+        assert_eq!((result.unwrap().to_string(), output), ("(1)".to_string(), ".".to_string()));
+        assert_eq!(eval("(delete 1 2)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(lset-difference eq?)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn proper_lists() {
+        assert!(boolean("(list? ())"));
+        assert!(boolean("(list? (list 1))"));
+        assert!(boolean("(list? (list 1 2))"));
+        assert!(boolean("(list? (list 1 2 3 4 5 6 7))"));
+        assert!(boolean("(list? (cons 1 (cons 2 ())))"));
+        assert!(!boolean("(list? (cons 1 2))"));
+        assert!(!boolean("(list? (cons 1 (cons 2 3)))"));
+        assert!(!boolean("(list? 1)"));
+        assert!(!boolean(r#"(list? "list")"#));
+        assert_eq!(eval("(list? 1 2)").unwrap_err().kind(), ErrorKind::ArityMismatch);
+    }
+
+    fn call(func: super::super::NativeFnPtr, args: Vec<Term>) -> Term {
+        let mut ctx = super::Context::new(crate::share!(crate::parser::SrcInfo::new("test", "")));
+        func(&mut ctx, &args).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "mutable-pairs")]
+    fn set_car_is_visible_to_aliases() {
+        let pair = Term::cons(Term::int(1), Term::int(2));
+        let alias = pair.clone();
+        call(super::set_car, vec![alias.clone(), Term::int(3)]);
+        assert_eq!(call(super::car, vec![pair.clone()]), Term::int(3));
+        call(super::set_cdr, vec![pair, Term::nil()]);
+        assert_eq!(call(super::is_list, vec![alias]), Term::boolean(true));
+        assert_eq!(eval("(set-car! 1 2)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(set-cdr! (list 1))").unwrap_err().kind(), ErrorKind::ArityMismatch);
+    }
+
+    /// A list of the numbers whose last cdr is its first pair, as `set-cdr!` makes with the
+    /// mutable-pairs feature.
+    fn circular_list(len: i64) -> Term {
+        let list = (0..len).rev().fold(Term::nil(), |tail, n| Term::cons(Term::int(n), tail));
+        let mut last = list.clone();
+        while let TermValue::Pair(pair) = last.value.clone() {
+            match pair.borrow().1.value {
+                TermValue::Nil => break,
+                _ => last = pair.borrow().1.clone()
+            }
+        }
+        let TermValue::Pair(ref pair) = last.value else { unreachable!() };
+        pair.borrow_mut().1 = list.clone();
+        list
+    }
+
+    #[test]
+    fn list_predicate_rejects_circular_lists() {
+        for len in 1..5 {
+            let list = circular_list(len);
+            assert_eq!(call(super::is_list, vec![list.clone()]), Term::boolean(false));
+            assert_eq!(call(super::is_list, vec![call(super::cdr, vec![list])]), Term::boolean(false));
+        }
+    }
+
+    #[test]
+    fn pairs_are_shared_by_aliases() {
+        let list = Term::cons(Term::int(1), Term::nil());
+        let alias = call(super::list, vec![list.clone()]);
+        let TermValue::Pair(ref pair) = list.value else { unreachable!() };
+        pair.borrow_mut().0 = Term::int(2);
+        assert_eq!(alias.to_string(), "((2))");
+        // A circular list is written with its cycle elided.
+        assert_eq!(circular_list(2).to_string(), "(0 1 . ...)");
+    }
+
+    #[test]
+    #[cfg(feature = "mutable-pairs")]
+    fn write_circular_lists() {
+        let text = |src: &str| eval(&format!("($define x (list 1 2)) {src}")).unwrap().to_string();
+        assert_eq!(text("(set-cdr! x x) x"), "(1 . ...)");
+        assert_eq!(text("(set-cdr! (cdr x) x) (list x x)"), "((1 2 . ...) (1 2 . ...))");
+        assert_eq!(text("(set-car! x x) x"), "(... 2)");
+        // The value of an error message is written the same way, rather than hanging.
+        let err = eval("($define x (list 1)) (set-cdr! x x) (vector-ref x 0)").unwrap_err();
+        assert!(err.message().contains("(1 . ...)"), "{}", err.message());
+        let (result, output) = eval_with_output("($define v (vector 1 2)) (vector-set! v 0 v) (display v) v");
+        assert_eq!((result.unwrap().to_string(), output), ("#(... 2)".to_string(), "#(... 2)".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "mutable-pairs")]
+    fn set_cdr_makes_circular_lists() {
+        for len in 1..5 {
+            let items = (0..len).map(Term::int).collect();
+            let list = call(super::list, items);
+            let mut last = list.clone();
+            while let TermValue::Pair(ref pair) = last.value.clone() {
+                match pair.borrow().1.value {
+                    TermValue::Nil => break,
+                    _ => last = pair.borrow().1.clone()
+                }
+            }
+            assert_eq!(call(super::is_list, vec![list.clone()]), Term::boolean(true));
+            call(super::set_cdr, vec![last, list.clone()]);
+            assert_eq!(call(super::is_list, vec![list.clone()]), Term::boolean(false));
+            assert_eq!(call(super::is_list, vec![call(super::cdr, vec![list])]), Term::boolean(false));
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "mutable-pairs"))]
+    fn pairs_are_immutable_by_default() {
+        assert_eq!(eval("(set-car! (list 1) 2)").unwrap_err().kind(), ErrorKind::FreeIdentifier);
+    }
+}
@@ -0,0 +1,249 @@
+mod boolean;
+mod bytevector;
+mod control;
+mod exception;
+mod hash_table;
+mod io;
+mod list;
+mod number;
+mod record;
+mod string;
+mod vector;
+
+use std::sync::OnceLock;
+
+use crate::error::{Error, ErrorKind};
+use super::combiner::{NativeFn, NativeFnPtr};
+use super::condition::ConditionType;
+use super::context::Env;
+use super::expander::{Macro, MacroTransformer};
+use super::term::{Term, TermValue};
+
+/// The number of arguments accepted by a builtin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+    Between(usize, usize)
+}
+
+impl Arity {
+    /// Check the number of arguments given to the builtin named `name`.
+    pub fn check(self, name: &str, args: &[Term]) -> Result<(), Error> {
+        match self {
+            Arity::Exactly(arity) => expect_arity(name, args, arity),
+            Arity::AtLeast(arity) => expect_at_least(name, args, arity),
+            Arity::Between(min, max) => expect_between(name, args, min, max)
+        }
+    }
+}
+
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arity::Exactly(n) => write!(f, "{n}"),
+            Arity::AtLeast(n) => write!(f, "{n}+"),
+            Arity::Between(min, max) => write!(f, "{min}-{max}")
+        }
+    }
+}
+
+/// The documented entry of a builtin bound by the prelude.
+#[derive(Debug, Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub description: &'static str,
+    func: NativeFnPtr
+}
+
+impl Builtin {
+    const fn new(name: &'static str, arity: Arity, description: &'static str, func: NativeFnPtr) -> Self {
+        Self { name, arity, description, func }
+    }
+}
+
+const MODULES: &[&[Builtin]] = &[
+    boolean::BUILTINS,
+    bytevector::BUILTINS,
+    control::BUILTINS,
+    exception::BUILTINS,
+    hash_table::BUILTINS,
+    io::BUILTINS,
+    list::BUILTINS,
+    number::BUILTINS,
+    record::BUILTINS,
+    string::BUILTINS,
+    vector::BUILTINS,
+];
+
+pub fn builtins() -> impl Iterator<Item = &'static Builtin> {
+    MODULES.iter().flat_map(|builtins| builtins.iter())
+}
+
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    builtins().find(|builtin| builtin.name == name)
+}
+
+/// The names of all the builtins, in the order they are documented.
+pub fn prelude_names() -> &'static [&'static str] {
+    static NAMES: OnceLock<Vec<&'static str>> = OnceLock::new();
+    NAMES.get_or_init(|| builtins().map(|builtin| builtin.name).collect())
+}
+
+/// The derived forms of each module, which are desugared by the macro expander.
+const MACRO_MODULES: &[&[(&str, MacroTransformer)]] = &[
+    control::MACROS,
+    exception::MACROS,
+];
+
+/// Bind all the builtins and derived forms into the environment.
+pub fn install_prelude(env: &mut Env) {
+    for builtin in builtins() {
+        env.insert(&builtin.name.to_string(), Term::from(NativeFn::new(builtin.func).with_arity(builtin.name, builtin.arity)));
+    }
+    // The root of the condition types, which the error objects are instances of.
+    env.insert(&"error-object".to_string(), Term::from(ConditionType::error_object()));
+    for (name, transformer) in MACRO_MODULES.iter().flat_map(|macros| macros.iter()) {
+        env.insert_macro(&name.to_string(), Macro::new(*transformer));
+    }
+}
+
+fn invalid_form(name: &str, syntax: &str) -> Error {
+    Error::new(ErrorKind::InvalidSyntax).with_message(format!("'{name}' must be of the form '{syntax}'."))
+}
+
+fn expect_arity(name: &str, args: &[Term], arity: usize) -> Result<(), Error> {
+    if args.len() == arity { Ok(()) } else {
+        Err(Error::new(ErrorKind::ArityMismatch)
+            .with_message(format!("'{name}' expects {arity} argument(s), but got {}.", args.len())))
+    }
+}
+
+fn expect_at_least(name: &str, args: &[Term], arity: usize) -> Result<(), Error> {
+    if args.len() >= arity { Ok(()) } else {
+        Err(Error::new(ErrorKind::ArityMismatch)
+            .with_message(format!("'{name}' expects at least {arity} argument(s), but got {}.", args.len())))
+    }
+}
+
+fn expect_between(name: &str, args: &[Term], min: usize, max: usize) -> Result<(), Error> {
+    if (min..=max).contains(&args.len()) { Ok(()) } else {
+        Err(Error::new(ErrorKind::ArityMismatch)
+            .with_message(format!("'{name}' expects {min} to {max} arguments, but got {}.", args.len())))
+    }
+}
+
+fn expect_string<'a>(name: &str, arg: &'a Term) -> Result<&'a String, Error> {
+    match arg.value {
+        TermValue::Str(ref s) => Ok(s),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a string, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn expect_int(name: &str, arg: &Term) -> Result<i64, Error> {
+    match arg.value {
+        TermValue::Int(n) => Ok(n),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects an exact integer, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn expect_count(name: &str, arg: &Term) -> Result<usize, Error> {
+    match expect_int(name, arg)? {
+        n if n < 0 => Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("'{name}' expects a non-negative count, but got {n}."))),
+        n => Ok(n as usize)
+    }
+}
+
+/// The error for a count of elements there isn't enough memory for, which is reported rather
+/// than aborting on the failed allocation.
+fn allocation_failed(name: &str, count: usize) -> Error {
+    Error::new(ErrorKind::InvalidArgument)
+        .with_message(format!("'{name}' can't allocate {count} elements."))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{Error, ErrorKind};
+    use crate::parser::{SrcInfo, SyntacticParser};
+    use crate::share;
+    use crate::syntax::Node;
+    use crate::test_support::Buffer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use super::super::context::{Context, Sink};
+    use super::super::term::{Term, TermValue};
+
+    #[test]
+    fn prelude_names_are_bound() {
+        use super::super::context::Env;
+        let mut env = Env::new();
+        super::install_prelude(&mut env);
+        for name in super::prelude_names() {
+            assert!(env.lookup(&name.to_string()).is_some(), "'{name}' is not bound.");
+        }
+        assert_eq!(super::builtin("gcd").unwrap().arity, super::Arity::AtLeast(0));
+    }
+
+    #[test]
+    fn prelude_descriptions_are_not_empty() {
+        for builtin in super::builtins() {
+            assert!(!builtin.description.is_empty(), "'{}' has no description.", builtin.name);
+        }
+    }
+
+    #[test]
+    fn prelude_arity_is_checked() {
+        // Every builtin is called only with a number of arguments its arity accepts.
+        for src in ["(car)", "(cons 1)", "(vector-ref (vector 1) 0 1)", "(string-pad)", "(< 1)", "(max)"] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::ArityMismatch, "{src}");
+        }
+        assert_eq!(eval("(eq? 1)").unwrap_err().message(), "'eq?' expects 2 argument(s), but got 1.");
+        assert_eq!(eval("(iota)").unwrap_err().message(), "'iota' expects 1 to 3 arguments, but got 0.");
+        assert_eq!(eval("(list)").unwrap(), Term::nil());
+    }
+
+    /// Evaluate every form of `src` in a fresh context, returning the last result.
+    pub(super) fn eval(src: &str) -> Result<Term, Error> {
+        let src = share!(SrcInfo::new("test", src));
+        eval_in(Context::new(src.clone()), src)
+    }
+
+    /// Evaluate `src`, which must give a boolean.
+    pub(super) fn boolean(src: &str) -> bool {
+        match eval(src).unwrap().value {
+            TermValue::Bool(b) => b,
+            value => panic!("{value:?} is not a boolean.")
+        }
+    }
+
+    /// Like `eval`, but with the output of the context collected into a string.
+    pub(super) fn eval_with_output(src: &str) -> (Result<Term, Error>, String) {
+        let buffer = Buffer::default();
+        let src = share!(SrcInfo::new("test", src));
+        let ctx = Context::new(src.clone()).with_output(Sink::new(buffer.clone()));
+        let result = eval_in(ctx, src);
+        let output = String::from_utf8(buffer.0.take()).unwrap();
+        (result, output)
+    }
+
+    /// Like `eval`, but with the reductions nested no deeper than `depth`.
+    pub(super) fn eval_with_depth(src: &str, depth: usize) -> Result<Term, Error> {
+        let src = share!(SrcInfo::new("test", src));
+        eval_in(Context::new(src.clone()).with_max_reduction_depth(depth), src)
+    }
+
+    fn eval_in(mut ctx: Context, src: Rc<RefCell<SrcInfo>>) -> Result<Term, Error> {
+        let mut parser = SyntacticParser::new(src);
+        parser.try_parse()?;
+        let mut result = Term::new();
+        for node in <Node as AsRef<Vec<Node>>>::as_ref(&parser.tree()) {
+            result = ctx.macroexpand_all(node.clone())?.into();
+            ctx.reduce(&mut result)?;
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,1169 @@
+use std::cmp::Ordering;
+
+use crate::error::{Error, ErrorKind};
+use crate::{if_or, seq};
+use super::super::context::Context;
+use super::super::term::{format_float, MultipleValues, Ratio, Term, TermValue};
+use super::{Arity::*, Builtin, expect_int, expect_string};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("+", AtLeast(0), "The sum of the numbers.", add),
+    Builtin::new("-", AtLeast(1), "The first number minus the others, or its negation if it is alone.", subtract),
+    Builtin::new("*", AtLeast(0), "The product of the numbers.", multiply),
+    Builtin::new("/", AtLeast(1), "The first number divided by the others, or its reciprocal if it is alone.", divide),
+    Builtin::new("=", AtLeast(2), "Whether the numbers are all equal.", equal),
+    Builtin::new("<", AtLeast(2), "Whether the numbers are strictly increasing.", less),
+    Builtin::new(">", AtLeast(2), "Whether the numbers are strictly decreasing.", greater),
+    Builtin::new("<=", AtLeast(2), "Whether the numbers are non-decreasing.", less_or_equal),
+    Builtin::new(">=", AtLeast(2), "Whether the numbers are non-increasing.", greater_or_equal),
+    Builtin::new("gcd", AtLeast(0), "The greatest common divisor of the integers.", gcd),
+    Builtin::new("lcm", AtLeast(0), "The least common multiple of the integers.", lcm),
+    Builtin::new("quotient", Exactly(2), "The integer quotient, truncated toward zero.", quotient),
+    Builtin::new("remainder", Exactly(2), "The integer remainder, with the sign of the dividend.", remainder),
+    Builtin::new("modulo", Exactly(2), "The integer modulus, with the sign of the divisor.", modulo),
+    Builtin::new("floor-quotient", Exactly(2), "The integer quotient, rounded toward negative infinity.", floor_quotient),
+    Builtin::new("floor-remainder", Exactly(2), "The remainder of the floor division.", floor_remainder),
+    Builtin::new("floor/", Exactly(2), "The floor quotient and remainder as two values.", floor_div),
+    Builtin::new("divmod", Exactly(2), "The same as `floor/`.", divmod),
+    Builtin::new("truncate-quotient", Exactly(2), "The integer quotient, truncated toward zero.", truncate_quotient),
+    Builtin::new("truncate-remainder", Exactly(2), "The remainder of the truncate division.", truncate_remainder),
+    Builtin::new("truncate/", Exactly(2), "The truncate quotient and remainder as two values.", truncate_div),
+    Builtin::new("min", AtLeast(1), "The minimum of the numbers, inexact if any of them is.", min),
+    Builtin::new("max", AtLeast(1), "The maximum of the numbers, inexact if any of them is.", max),
+    Builtin::new("abs", Exactly(1), "The absolute value of the number.", abs),
+    Builtin::new("floor", Exactly(1), "The largest integer not greater than the number.", floor),
+    Builtin::new("ceiling", Exactly(1), "The smallest integer not less than the number.", ceiling),
+    Builtin::new("truncate", Exactly(1), "The integer closest to the number toward zero.", truncate),
+    Builtin::new("round", Exactly(1), "The closest integer to the number, with ties to even.", round),
+    Builtin::new("exact", Exactly(1), "The exact representation of the number.", exact),
+    Builtin::new("inexact", Exactly(1), "The inexact representation of the number.", inexact),
+    Builtin::new("exact->inexact", Exactly(1), "The inexact representation of the number, like `inexact`.", exact_to_inexact),
+    Builtin::new("inexact->exact", Exactly(1), "The exact representation of the number, like `exact`.", inexact_to_exact),
+    Builtin::new("number?", Exactly(1), "Whether the value is a number.", is_number),
+    Builtin::new("complex?", Exactly(1), "Whether the value is a complex number.", is_complex),
+    Builtin::new("real?", Exactly(1), "Whether the value is a real number.", is_real),
+    Builtin::new("rational?", Exactly(1), "Whether the value is a rational number.", is_rational),
+    Builtin::new("integer?", Exactly(1), "Whether the value is an integer.", is_integer),
+    Builtin::new("exact?", Exactly(1), "Whether the number is exact.", is_exact),
+    Builtin::new("inexact?", Exactly(1), "Whether the number is inexact.", is_inexact),
+    Builtin::new("exact-integer?", Exactly(1), "Whether the value is an exact integer.", is_exact_integer),
+    Builtin::new("zero?", Exactly(1), "Whether the number is zero.", is_zero),
+    Builtin::new("positive?", Exactly(1), "Whether the number is greater than zero.", is_positive),
+    Builtin::new("negative?", Exactly(1), "Whether the number is less than zero.", is_negative),
+    Builtin::new("odd?", Exactly(1), "Whether the exact integer is odd.", is_odd),
+    Builtin::new("even?", Exactly(1), "Whether the exact integer is even.", is_even),
+    Builtin::new("string->number", Between(1, 2),
+        "The number written in the string in the radix, or #f if it isn't one.", string_to_number),
+    Builtin::new("number->string", Between(1, 2), "The number written in the radix.", number_to_string),
+];
+
+fn out_of_range(name: &str) -> Error {
+    Error::new(ErrorKind::IntegerOverflow)
+        .with_message(format!("The result of '{name}' is out of the exact integer range."))
+}
+
+fn gcd_of(a: i64, b: i64) -> u64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn gcd(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut result = 0i64;
+    for arg in args {
+        let n = expect_int("gcd", arg)?;
+        result = i64::try_from(gcd_of(result, n)).map_err(|_| out_of_range("gcd"))?;
+    }
+    Ok(result.into())
+}
+
+fn lcm(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut result = 1i64;
+    for arg in args {
+        let n = expect_int("lcm", arg)?;
+        if n == 0 || result == 0 {
+            result = 0;
+            continue;
+        }
+        let divisor = gcd_of(result, n) as i64;
+        result = (result / divisor)
+            .checked_mul(n)
+            .and_then(i64::checked_abs)
+            .ok_or_else(|| out_of_range("lcm"))?;
+    }
+    Ok(result.into())
+}
+
+/// The operands of an integer division, which are inexact if any of them is inexact.
+enum DivisionOperands {
+    Exact(i64, i64),
+    Inexact(f64, f64)
+}
+
+fn expect_integral_float(name: &str, arg: &Term) -> Result<f64, Error> {
+    match arg.value {
+        TermValue::Int(n) => Ok(n as f64),
+        TermValue::Float(x) if x.fract() == 0.0 => Ok(x),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects an integer, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn zero_divisor(name: &str) -> Error {
+    Error::new(ErrorKind::DivisionByZero)
+        .with_message(format!("'{name}' is called with a zero divisor."))
+}
+
+/// Extract the dividend and the non-zero divisor of a binary division of exact integers.
+fn exact_division_operands(name: &str, args: &[Term]) -> Result<(i64, i64), Error> {
+    match (expect_int(name, &args[0])?, expect_int(name, &args[1])?) {
+        (_, 0) => Err(zero_divisor(name)),
+        operands => Ok(operands)
+    }
+}
+
+/// Extract the dividend and the non-zero divisor of a binary division of exact or inexact integers.
+fn division_operands(name: &str, args: &[Term]) -> Result<DivisionOperands, Error> {
+    let (dividend, divisor) = (&args[0], &args[1]);
+    let operands = match (&dividend.value, &divisor.value) {
+        (TermValue::Int(a), TermValue::Int(b)) => DivisionOperands::Exact(*a, *b),
+        _ => DivisionOperands::Inexact(expect_integral_float(name, dividend)?, expect_integral_float(name, divisor)?)
+    };
+    match operands {
+        DivisionOperands::Exact(_, 0) | DivisionOperands::Inexact(_, 0.0) => Err(zero_divisor(name)),
+        operands => Ok(operands)
+    }
+}
+
+fn truncate_quotient_of(dividend: i64, divisor: i64) -> Result<i64, Error> {
+    dividend.checked_div(divisor).ok_or_else(|| out_of_range("quotient"))
+}
+
+fn truncate_remainder_of(dividend: i64, divisor: i64) -> i64 {
+    // The remainder of `i64::MIN / -1` is 0, though the quotient overflows.
+    dividend.wrapping_rem(divisor)
+}
+
+fn floor_remainder_of(dividend: i64, divisor: i64) -> i64 {
+    let rem = truncate_remainder_of(dividend, divisor);
+    if rem != 0 && (rem < 0) != (divisor < 0) { rem + divisor } else { rem }
+}
+
+fn floor_quotient_of(dividend: i64, divisor: i64) -> Result<i64, Error> {
+    let quot = truncate_quotient_of(dividend, divisor)?;
+    Ok(if floor_remainder_of(dividend, divisor) != truncate_remainder_of(dividend, divisor) {
+        quot - 1
+    } else { quot })
+}
+
+fn quotient(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (dividend, divisor) = exact_division_operands("quotient", args)?;
+    truncate_quotient_of(dividend, divisor).map(Term::from)
+}
+
+fn remainder(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (dividend, divisor) = exact_division_operands("remainder", args)?;
+    Ok(truncate_remainder_of(dividend, divisor).into())
+}
+
+fn modulo(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (dividend, divisor) = exact_division_operands("modulo", args)?;
+    Ok(floor_remainder_of(dividend, divisor).into())
+}
+
+/// Evaluate the floor division, as `(quotient, remainder)`.
+fn floor_division(name: &str, args: &[Term]) -> Result<(Term, Term), Error> {
+    match division_operands(name, args)? {
+        DivisionOperands::Exact(dividend, divisor) => Ok((
+            floor_quotient_of(dividend, divisor)?.into(),
+            floor_remainder_of(dividend, divisor).into()
+        )),
+        DivisionOperands::Inexact(dividend, divisor) => {
+            let quot = (dividend / divisor).floor();
+            Ok((quot.into(), (dividend - divisor * quot).into()))
+        }
+    }
+}
+
+/// Evaluate the truncate division, as `(quotient, remainder)`.
+fn truncate_division(name: &str, args: &[Term]) -> Result<(Term, Term), Error> {
+    match division_operands(name, args)? {
+        DivisionOperands::Exact(dividend, divisor) => Ok((
+            truncate_quotient_of(dividend, divisor)?.into(),
+            truncate_remainder_of(dividend, divisor).into()
+        )),
+        DivisionOperands::Inexact(dividend, divisor) =>
+            Ok(((dividend / divisor).trunc().into(), (dividend % divisor).into()))
+    }
+}
+
+fn floor_quotient(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    floor_division("floor-quotient", args).map(|(quot, _)| quot)
+}
+
+fn floor_remainder(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    floor_division("floor-remainder", args).map(|(_, rem)| rem)
+}
+
+fn floor_div(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    floor_division("floor/", args).map(|(quot, rem)| Term::from(MultipleValues(vec![quot, rem])))
+}
+
+fn divmod(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    floor_division("divmod", args).map(|(quot, rem)| Term::from(MultipleValues(vec![quot, rem])))
+}
+
+fn truncate_quotient(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    truncate_division("truncate-quotient", args).map(|(quot, _)| quot)
+}
+
+fn truncate_remainder(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    truncate_division("truncate-remainder", args).map(|(_, rem)| rem)
+}
+
+fn truncate_div(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    truncate_division("truncate/", args).map(|(quot, rem)| Term::from(MultipleValues(vec![quot, rem])))
+}
+
+/// A numerator and a positive denominator, not necessarily in lowest terms. Products and sums
+/// of two fractions of 64-bit integers fit without overflow.
+type Fraction = (i128, i128);
+
+/// The fraction of an exact number, or `None` if it is inexact or not a number.
+pub(super) fn exact_fraction(value: &TermValue) -> Option<Fraction> {
+    match *value {
+        TermValue::Int(n) => Some((n as i128, 1)),
+        TermValue::Rational(ratio) => Some((ratio.numerator() as i128, ratio.denominator() as i128)),
+        _ => None
+    }
+}
+
+/// Two operands of an arithmetic or a comparison, promoted to the same representation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Numeric {
+    Int(i64),
+    Fraction(Fraction),
+    Float(f64)
+}
+
+impl Numeric {
+    fn compare(self, other: Numeric) -> Option<Ordering> {
+        match (self, other) {
+            (Numeric::Int(a), Numeric::Int(b)) => Some(a.cmp(&b)),
+            (Numeric::Fraction((a, b)), Numeric::Fraction((c, d))) => Some((a * d).cmp(&(c * b))),
+            (Numeric::Float(a), Numeric::Float(b)) => a.partial_cmp(&b),
+            _ => unreachable!("The operands are coerced to the same representation.")
+        }
+    }
+}
+
+/// Promote both operands to floats if either of them is inexact, to fractions if either of
+/// them is a ratio, or keep both integers.
+fn coerce_numeric(name: &str, a: &TermValue, b: &TermValue) -> Result<(Numeric, Numeric), Error> {
+    match (a, b) {
+        (TermValue::Int(a), TermValue::Int(b)) => Ok((Numeric::Int(*a), Numeric::Int(*b))),
+        (TermValue::Int(_) | TermValue::Rational(_), TermValue::Int(_) | TermValue::Rational(_)) =>
+            Ok((Numeric::Fraction(exact_fraction(a).unwrap()), Numeric::Fraction(exact_fraction(b).unwrap()))),
+        (TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_),
+            TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_)) =>
+            Ok((Numeric::Float(to_float(a)), Numeric::Float(to_float(b)))),
+        (TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_), value) | (value, _) =>
+            Err(Error::new(ErrorKind::TypeMismatch)
+                .with_message(format!("'{name}' expects numbers, but got the {} {value}.", value.type_name())))
+    }
+}
+
+/// The exact number of the fraction, or an error if it is out of range.
+fn reduce(name: &str, (numerator, denominator): Fraction) -> Result<Term, Error> {
+    Ratio::reduce(numerator, denominator).ok_or_else(|| out_of_range(name))
+}
+
+/// The operations of an arithmetic on each representation of numbers.
+struct Operation {
+    exact: fn(i64, i64) -> Option<i64>,
+    fraction: fn(Fraction, Fraction) -> Fraction,
+    inexact: fn(f64, f64) -> f64
+}
+
+const ADDITION: Operation = Operation {
+    exact: i64::checked_add,
+    fraction: |(a, b), (c, d)| (a * d + c * b, b * d),
+    inexact: |a, b| a + b
+};
+
+const SUBTRACTION: Operation = Operation {
+    exact: i64::checked_sub,
+    fraction: |(a, b), (c, d)| (a * d - c * b, b * d),
+    inexact: |a, b| a - b
+};
+
+const MULTIPLICATION: Operation = Operation {
+    exact: i64::checked_mul,
+    fraction: |(a, b), (c, d)| (a * c, b * d),
+    inexact: |a, b| a * b
+};
+
+/// Fold the numbers from the identity, with the checked exact operation on integers or
+/// ratios, or the inexact one as soon as an operand is inexact.
+fn fold_numeric<'a>(name: &str, identity: Term, args: impl Iterator<Item = &'a Term>, operation: Operation)
+    -> Result<Term, Error> {
+    let mut result = identity;
+    for arg in args {
+        result = match coerce_numeric(name, &result.value, &arg.value)? {
+            (Numeric::Int(a), Numeric::Int(b)) =>
+                (operation.exact)(a, b).map(Term::from).ok_or_else(|| out_of_range(name))?,
+            (Numeric::Fraction(a), Numeric::Fraction(b)) => reduce(name, (operation.fraction)(a, b))?,
+            (Numeric::Float(a), Numeric::Float(b)) => (operation.inexact)(a, b).into(),
+            _ => unreachable!("The operands are coerced to the same representation.")
+        };
+    }
+    Ok(result)
+}
+
+fn add(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    fold_numeric("+", Term::int(0), args.iter(), ADDITION)
+}
+
+fn multiply(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    fold_numeric("*", Term::int(1), args.iter(), MULTIPLICATION)
+}
+
+fn subtract(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let first = args.next().unwrap();
+    if args.len() == 0 {
+        return fold_numeric("-", Term::int(0), std::iter::once(first), SUBTRACTION)
+    }
+    fold_numeric("-", first.clone(), args, SUBTRACTION)
+}
+
+/// Integers that don't divide exactly give an exact ratio.
+fn divide(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let first = args.next().unwrap();
+    let (mut result, divisors) = if args.len() == 0 {
+        (Term::int(1), vec![first])
+    } else {
+        (first.clone(), args.collect())
+    };
+    for divisor in divisors {
+        result = match coerce_numeric("/", &result.value, &divisor.value)? {
+            (_, Numeric::Int(0) | Numeric::Fraction((0, _))) => return Err(zero_divisor("/")),
+            (Numeric::Int(a), Numeric::Int(b)) => reduce("/", (a as i128, b as i128))?,
+            (Numeric::Fraction((a, b)), Numeric::Fraction((c, d))) => reduce("/", (a * d, b * c))?,
+            (Numeric::Float(a), Numeric::Float(b)) => (a / b).into(),
+            _ => unreachable!("The operands are coerced to the same representation.")
+        };
+    }
+    Ok(result)
+}
+
+/// Whether every adjacent pair of the numbers is ordered as one of the orderings.
+/// Comparisons with NaN are always false, but every argument is still checked to be a number.
+fn compare_chain(name: &str, args: &[Term], orderings: &[Ordering]) -> Result<Term, Error> {
+    let mut holds = true;
+    for (a, b) in args.iter().zip(args.iter().skip(1)) {
+        let (a, b) = coerce_numeric(name, &a.value, &b.value)?;
+        holds &= a.compare(b).is_some_and(|order| orderings.contains(&order));
+    }
+    Ok(holds.into())
+}
+
+fn equal(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    compare_chain("=", args, &[Ordering::Equal])
+}
+
+fn less(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    compare_chain("<", args, &[Ordering::Less])
+}
+
+fn greater(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    compare_chain(">", args, &[Ordering::Greater])
+}
+
+fn less_or_equal(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    compare_chain("<=", args, &[Ordering::Less, Ordering::Equal])
+}
+
+fn greater_or_equal(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    compare_chain(">=", args, &[Ordering::Greater, Ordering::Equal])
+}
+
+/// Find the argument that is ordered as `ordering` against all the others.
+/// The result is inexact if any of the arguments is inexact.
+fn extremum(name: &str, args: &[Term], ordering: Ordering) -> Result<Term, Error> {
+    let mut inexact = false;
+    let mut result = &args[0];
+    for arg in args {
+        let (a, b) = coerce_numeric(name, &arg.value, &result.value)?;
+        inexact |= matches!(arg.value, TermValue::Float(_));
+        // Let NaN take over the result once it appears.
+        let order = a.compare(b).unwrap_or(if matches!(arg.value, TermValue::Float(x) if x.is_nan()) { ordering } else { Ordering::Equal });
+        if order == ordering { result = arg }
+    }
+    Ok(if inexact { Term::from(to_float(&result.value)) } else { result.clone() })
+}
+
+fn to_float(value: &TermValue) -> f64 {
+    match value {
+        TermValue::Int(n) => *n as f64,
+        TermValue::Rational(ratio) => ratio.to_f64(),
+        TermValue::Float(x) => *x,
+        _ => f64::NAN
+    }
+}
+
+fn min(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    extremum("min", args, Ordering::Less)
+}
+
+fn max(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    extremum("max", args, Ordering::Greater)
+}
+
+/// Apply a unary numeric operation, which keeps exact numbers exact.
+fn unary_numeric(name: &str, args: &[Term], exact: fn(i64) -> Option<i64>, fraction: fn(Fraction) -> Fraction,
+    inexact: fn(f64) -> f64) -> Result<Term, Error> {
+    match args[0].value {
+        TermValue::Int(n) => exact(n).map(Term::from).ok_or_else(|| out_of_range(name)),
+        TermValue::Rational(ratio) => reduce(name, fraction(exact_fraction(&TermValue::Rational(ratio)).unwrap())),
+        TermValue::Float(x) => Ok(inexact(x).into()),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a number, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn abs(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    unary_numeric("abs", args, i64::checked_abs, |(a, b)| (a.abs(), b), f64::abs)
+}
+
+fn floor(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    unary_numeric("floor", args, Some, |(a, b)| (a.div_euclid(b), 1), f64::floor)
+}
+
+fn ceiling(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    unary_numeric("ceiling", args, Some, |(a, b)| (-(-a).div_euclid(b), 1), f64::ceil)
+}
+
+fn truncate(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    unary_numeric("truncate", args, Some, |(a, b)| (a / b, 1), f64::trunc)
+}
+
+/// Round to the nearest integer, with ties to even.
+fn round(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    unary_numeric("round", args, Some, |(a, b)| {
+        let (floor, remainder) = (a.div_euclid(b), a.rem_euclid(b));
+        match (2 * remainder).cmp(&b) {
+            Ordering::Less => (floor, 1),
+            Ordering::Equal if floor % 2 == 0 => (floor, 1),
+            _ => (floor + 1, 1)
+        }
+    }, f64::round_ties_even)
+}
+
+fn to_inexact(name: &str, args: &[Term]) -> Result<Term, Error> {
+    match args[0].value {
+        ref value @ (TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_)) => Ok(to_float(value).into()),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a number, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn to_exact(name: &str, args: &[Term]) -> Result<Term, Error> {
+    match args[0].value {
+        TermValue::Int(_) | TermValue::Rational(_) => Ok(args[0].clone()),
+        TermValue::Float(x) if x.is_finite() => float_to_exact(x).ok_or_else(|| out_of_range(name)),
+        TermValue::Float(x) => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("{x} has no exact representation."))),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a number, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn exact(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    to_exact("exact", args)
+}
+
+fn inexact(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    to_inexact("inexact", args)
+}
+
+fn exact_to_inexact(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    to_inexact("exact->inexact", args)
+}
+
+fn inexact_to_exact(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    to_exact("inexact->exact", args)
+}
+
+fn type_predicate(args: &[Term], predicate: fn(&TermValue) -> bool) -> Result<Term, Error> {
+    Ok(predicate(&args[0].value).into())
+}
+
+/// Like `type_predicate`, but only accepts numbers.
+fn numeric_predicate(name: &str, args: &[Term], predicate: fn(&TermValue) -> bool) -> Result<Term, Error> {
+    match args[0].value {
+        ref value @ (TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_)) => Ok(predicate(value).into()),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a number, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn is_number(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    type_predicate(args, |value| matches!(value, TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_)))
+}
+
+// There are no complex numbers yet, so any real is complex.
+fn is_complex(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    type_predicate(args, |value| matches!(value, TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_)))
+}
+
+fn is_real(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    type_predicate(args, |value| matches!(value, TermValue::Int(_) | TermValue::Rational(_) | TermValue::Float(_)))
+}
+
+fn is_rational(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    type_predicate(args, |value| match value {
+        TermValue::Int(_) | TermValue::Rational(_) => true,
+        TermValue::Float(x) => x.is_finite(),
+        _ => false
+    })
+}
+
+fn is_integer(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    type_predicate(args, |value| match value {
+        TermValue::Int(_) => true,
+        TermValue::Float(x) => x.is_finite() && x.fract() == 0.0,
+        _ => false
+    })
+}
+
+fn is_exact(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    numeric_predicate("exact?", args, |value| matches!(value, TermValue::Int(_) | TermValue::Rational(_)))
+}
+
+fn is_inexact(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    numeric_predicate("inexact?", args, |value| matches!(value, TermValue::Float(_)))
+}
+
+fn is_exact_integer(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    type_predicate(args, |value| matches!(value, TermValue::Int(_)))
+}
+
+fn is_zero(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    numeric_predicate("zero?", args, |value| match *value {
+        TermValue::Int(n) => n == 0,
+        TermValue::Rational(_) => false,
+        TermValue::Float(x) => x == 0.0,
+        _ => unreachable!()
+    })
+}
+
+fn is_positive(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    numeric_predicate("positive?", args, |value| match *value {
+        TermValue::Int(n) => n > 0,
+        TermValue::Rational(ratio) => ratio.numerator() > 0,
+        TermValue::Float(x) => x > 0.0,
+        _ => unreachable!()
+    })
+}
+
+fn is_negative(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    numeric_predicate("negative?", args, |value| match *value {
+        TermValue::Int(n) => n < 0,
+        TermValue::Rational(ratio) => ratio.numerator() < 0,
+        TermValue::Float(x) => x < 0.0,
+        _ => unreachable!()
+    })
+}
+
+fn is_odd(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok((expect_int("odd?", &args[0])? % 2 != 0).into())
+}
+
+fn is_even(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok((expect_int("even?", &args[0])? % 2 == 0).into())
+}
+
+/// The radix given as an optional argument, which defaults to 10.
+fn radix_of(name: &str, arg: Option<&Term>) -> Result<u32, Error> {
+    match arg {
+        None => Ok(10),
+        Some(arg) => match expect_int(name, arg)? {
+            radix @ 2..=36 => Ok(radix as u32),
+            radix => Err(Error::new(ErrorKind::InvalidArgument)
+                .with_message(format!("'{name}' expects a radix between 2 and 36, but got {radix}.")))
+        }
+    }
+}
+
+fn string_to_number(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let text = expect_string("string->number", &args[0])?;
+    let radix = radix_of("string->number", args.get(1))?;
+    Ok(parse_number(text, radix).unwrap_or_else(|| false.into()))
+}
+
+/// Parse a number with optional prefixes like `#x` and `#e`, where a radix prefix overrides `radix`.
+fn parse_number(text: &str, radix: u32) -> Option<Term> {
+    let (mut radix, mut exactness, mut rest) = (radix, None, text);
+    let mut radix_prefixed = false;
+    while let Some(prefixed) = rest.strip_prefix('#') {
+        let mut chars = prefixed.chars();
+        match (chars.next()?.to_ascii_lowercase(), radix_prefixed, exactness) {
+            ('b', false, _) => seq!(radix = 2, radix_prefixed = true),
+            ('o', false, _) => seq!(radix = 8, radix_prefixed = true),
+            ('d', false, _) => seq!(radix = 10, radix_prefixed = true),
+            ('x', false, _) => seq!(radix = 16, radix_prefixed = true),
+            ('e', _, None) => exactness = Some(true),
+            ('i', _, None) => exactness = Some(false),
+            _ => return None
+        }
+        rest = chars.as_str();
+    }
+    let number = parse_real(rest, radix)?;
+    match (exactness, &number.value) {
+        (Some(true), &TermValue::Float(x)) => float_to_exact(x),
+        (Some(false), value @ (TermValue::Int(_) | TermValue::Rational(_))) => Some(to_float(value).into()),
+        _ => Some(number)
+    }
+}
+
+/// The exact number equal to the finite float, or `None` if it is out of range. Every finite
+/// float is a fraction whose denominator is a power of two.
+fn float_to_exact(x: f64) -> Option<Term> {
+    if !x.is_finite() { return None }
+    if x.fract() == 0.0 { return float_to_int(x).map(Term::from) }
+    let bits = x.to_bits();
+    let (biased_exponent, fraction) = ((bits >> 52) & 0x7ff, bits & ((1 << 52) - 1));
+    // The float is `mantissa * 2^exponent`, where the exponent is negative as it isn't integral.
+    let (mantissa, exponent) = match biased_exponent {
+        0 => (fraction, -1074),
+        _ => (fraction | 1 << 52, biased_exponent as i32 - 1075)
+    };
+    let twos = mantissa.trailing_zeros() as i32;
+    let numerator = (mantissa >> twos) as i128;
+    let shift = -exponent - twos;
+    // A denominator of 2^63 or more doesn't fit into 64 bits.
+    if shift > 62 { return None }
+    Ratio::reduce(if_or!(x < 0.0, -numerator, numerator), 1 << shift)
+}
+
+/// The exact integer equal to the real, if there is one in range.
+fn float_to_int(x: f64) -> Option<i64> {
+    if x.is_finite() && x.fract() == 0.0 && x >= i64::MIN as f64 && x < i64::MAX as f64 {
+        Some(x as i64)
+    } else {
+        None
+    }
+}
+
+fn parse_real(text: &str, radix: u32) -> Option<Term> {
+    match text {
+        "+inf.0" => return Some(f64::INFINITY.into()),
+        "-inf.0" => return Some(f64::NEG_INFINITY.into()),
+        "+nan.0" | "-nan.0" => return Some(f64::NAN.into()),
+        _ => ()
+    }
+    // A ratio of integers out of range becomes inexact, like an integer out of range.
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        if denominator.starts_with(['+', '-']) { return None }
+        return match (parse_integer(numerator, radix)?.value, parse_integer(denominator, radix)?.value) {
+            (_, TermValue::Int(0)) => None,
+            (TermValue::Int(n), TermValue::Int(d)) => Ratio::reduce(n as i128, d as i128),
+            (n, d) => Some((to_float(&n) / to_float(&d)).into())
+        }
+    }
+    match parse_integer(text, radix) {
+        Some(int) => Some(int),
+        None if radix == 10 && is_decimal(text) => text.parse::<f64>().ok().map(Term::from),
+        None => None
+    }
+}
+
+/// An optionally signed integer, which becomes inexact if it doesn't fit into 64 bits.
+fn parse_integer(text: &str, radix: u32) -> Option<Term> {
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_digit(radix)) {
+        return None
+    }
+    match i64::from_str_radix(text, radix) {
+        Ok(n) => Some(n.into()),
+        Err(_) if radix == 10 => text.parse::<f64>().ok().map(Term::from),
+        Err(_) => {
+            let magnitude = digits.chars()
+                .fold(0.0, |acc, ch| acc * radix as f64 + ch.to_digit(radix).unwrap() as f64);
+            Some(if_or!(text.starts_with('-'), -magnitude, magnitude).into())
+        }
+    }
+}
+
+/// Decimals like `1.5`, `.5`, `1.` and `1e-3`, optionally signed.
+fn is_decimal(text: &str) -> bool {
+    let text = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text, None)
+    };
+    let valid_mantissa = mantissa.chars().filter(|&ch| ch == '.').count() <= 1
+        && mantissa.chars().all(|ch| ch.is_ascii_digit() || ch == '.')
+        && mantissa.chars().any(|ch| ch.is_ascii_digit());
+    let valid_exponent = exponent.is_none_or(|exponent| {
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !digits.is_empty() && digits.chars().all(|ch| ch.is_ascii_digit())
+    });
+    valid_mantissa && valid_exponent
+}
+
+fn number_to_string(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let radix = radix_of("number->string", args.get(1))?;
+    match args[0].value {
+        TermValue::Int(n) => Ok(format_integer(n, radix).into()),
+        TermValue::Rational(ratio) =>
+            Ok(format!("{}/{}", format_integer(ratio.numerator(), radix), format_integer(ratio.denominator(), radix)).into()),
+        TermValue::Float(x) if radix == 10 => Ok(format_float(x).into()),
+        TermValue::Float(_) => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message("'number->string' only writes inexact numbers in radix 10.".to_string())),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'number->string' expects a number, but got the {} {value}.", value.type_name())))
+    }
+}
+
+pub(super) fn format_integer(n: i64, radix: u32) -> String {
+    let mut magnitude = n.unsigned_abs();
+    let mut digits = vec![];
+    loop {
+        digits.push(char::from_digit((magnitude % radix as u64) as u32, radix).unwrap());
+        magnitude /= radix as u64;
+        if magnitude == 0 { break }
+    }
+    if n < 0 { digits.push('-') }
+    digits.into_iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::{MultipleValues, Term, TermValue};
+    use super::super::tests::{boolean, eval};
+
+    fn int(src: &str) -> i64 {
+        match eval(src).unwrap().value {
+            TermValue::Int(n) => n,
+            value => panic!("{value:?} is not an integer.")
+        }
+    }
+
+    fn float(src: &str) -> f64 {
+        match eval(src).unwrap().value {
+            TermValue::Float(x) => x,
+            value => panic!("{value:?} is not a real.")
+        }
+    }
+
+    fn ratio(src: &str) -> (i64, i64) {
+        match eval(src).unwrap().value {
+            TermValue::Rational(ratio) => (ratio.numerator(), ratio.denominator()),
+            value => panic!("{value:?} is not a ratio.")
+        }
+    }
+
+    fn error_kind(src: &str) -> ErrorKind {
+        eval(src).map(|term: Term| term.value).unwrap_err().kind()
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(int("(+)"), 0);
+        assert_eq!(int("(+ 1 2 3)"), 6);
+        assert_eq!(float("(+ 1 2.5)"), 3.5);
+        assert_eq!(float("(+ 1.5 2)"), 3.5);
+        assert_eq!(int("(- 5)"), -5);
+        assert_eq!(int("(- 10 1 2)"), 7);
+        assert_eq!(float("(- 1 0.5)"), 0.5);
+        assert_eq!(int("(*)"), 1);
+        assert_eq!(int("(* 2 3 4)"), 24);
+        assert_eq!(float("(* 2 0.5)"), 1.0);
+        assert_eq!(int("(/ 12 2 3)"), 2);
+        assert_eq!(ratio("(/ 2)"), (1, 2));
+        assert_eq!(ratio("(/ 1 4)"), (1, 4));
+        assert_eq!(ratio("(/ 6 -4)"), (-3, 2));
+        assert_eq!(ratio("(+ (/ 1 2) (/ 1 3))"), (5, 6));
+        assert_eq!(ratio("(- (/ 1 2))"), (-1, 2));
+        assert_eq!(ratio("(- 1 (/ 1 3))"), (2, 3));
+        assert_eq!(int("(* (/ 2 3) (/ 3 2))"), 1);
+        assert_eq!(ratio("(/ (/ 1 2) 3)"), (1, 6));
+        assert_eq!(float("(+ (/ 1 2) 0.25)"), 0.75);
+        assert_eq!(error_kind("(/ 1 (- (/ 1 2) (/ 1 2)))"), ErrorKind::DivisionByZero);
+        assert_eq!(error_kind("(/ 1 9223372036854775807 2)"), ErrorKind::IntegerOverflow);
+        assert_eq!(float("(/ 1.0 0)"), f64::INFINITY);
+
+        // Exact integers never overflow into floats.
+        assert_eq!(error_kind("(+ 9223372036854775807 1)"), ErrorKind::IntegerOverflow);
+        assert_eq!(error_kind("(* 4611686018427387904 2)"), ErrorKind::IntegerOverflow);
+        assert_eq!(error_kind("(- -9223372036854775807 2)"), ErrorKind::IntegerOverflow);
+        assert_eq!(error_kind("(- (- -9223372036854775807 1))"), ErrorKind::IntegerOverflow);
+        assert_eq!(error_kind("(/ (- -9223372036854775807 1) -1)"), ErrorKind::IntegerOverflow);
+        assert_eq!(float("(+ 9223372036854775807 1.0)"), 9223372036854775808.0);
+
+        assert_eq!(error_kind("(/ 1 0)"), ErrorKind::DivisionByZero);
+        assert_eq!(error_kind("(+ 1 'a')"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(- 'a' 1)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(- 'a')"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(-)"), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn comparison() {
+        assert!(boolean("(= 1 1 1)"));
+        assert!(boolean("(= 1 1.0)"));
+        assert!(!boolean("(= 1 2)"));
+        assert!(boolean("(< 1 2 3.5)"));
+        assert!(!boolean("(< 1 3 2)"));
+        assert!(boolean("(> 3 2.5 1)"));
+        assert!(boolean("(<= 1 1 2)"));
+        assert!(!boolean("(<= 2 1)"));
+        assert!(boolean("(>= 2 2 1)"));
+        let nan = r#"(string->number "+nan.0")"#;
+        assert!(!boolean(&format!("(= {nan} {nan})")));
+        assert!(!boolean(&format!("(< 1 {nan})")));
+        assert!(!boolean(&format!("(>= {nan} 1)")));
+        // Every argument is checked, even once the result is known.
+        assert_eq!(error_kind("(< 2 1 'a')"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(= 1)"), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(int("(gcd)"), 0);
+        assert_eq!(int("(gcd 12 18)"), 6);
+        assert_eq!(int("(gcd -12 18 -8)"), 2);
+        assert_eq!(int("(gcd 0 5)"), 5);
+        assert_eq!(int("(lcm)"), 1);
+        assert_eq!(int("(lcm 4 6)"), 12);
+        assert_eq!(int("(lcm -4 6 5)"), 60);
+        assert_eq!(int("(lcm 4 0)"), 0);
+        assert_eq!(error_kind("(gcd 1.5 3)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(gcd (- -9223372036854775807 1) 0)"), ErrorKind::IntegerOverflow);
+        assert_eq!(error_kind("(lcm 4611686018427387904 3)"), ErrorKind::IntegerOverflow);
+    }
+
+    #[test]
+    fn quotient_remainder_modulo() {
+        assert_eq!(int("(quotient 13 4)"), 3);
+        assert_eq!(int("(quotient -13 4)"), -3);
+        assert_eq!(int("(quotient 13 -4)"), -3);
+        assert_eq!(int("(quotient -13 -4)"), 3);
+
+        assert_eq!(int("(remainder 13 4)"), 1);
+        assert_eq!(int("(remainder -13 4)"), -1);
+        assert_eq!(int("(remainder 13 -4)"), 1);
+        assert_eq!(int("(remainder -13 -4)"), -1);
+
+        assert_eq!(int("(modulo 13 4)"), 1);
+        assert_eq!(int("(modulo -13 4)"), 3);
+        assert_eq!(int("(modulo 13 -4)"), -3);
+        assert_eq!(int("(modulo -13 -4)"), -1);
+        assert_eq!(int("(modulo 12 -4)"), 0);
+        assert_eq!(error_kind("(quotient (- -9223372036854775807 1) -1)"), ErrorKind::IntegerOverflow);
+        assert_eq!(int("(remainder (- -9223372036854775807 1) -1)"), 0);
+    }
+
+    #[test]
+    fn floor_and_truncate_division() {
+        // (dividend, divisor, floor quotient, floor remainder, truncate quotient, truncate remainder)
+        let cases = [(13, 4, 3, 1, 3, 1), (-13, 4, -4, 3, -3, -1), (13, -4, -4, -3, -3, 1), (-13, -4, 3, -1, 3, -1)];
+        for (a, b, fq, fr, tq, tr) in cases {
+            assert_eq!(int(&format!("(floor-quotient {a} {b})")), fq);
+            assert_eq!(int(&format!("(floor-remainder {a} {b})")), fr);
+            assert_eq!(int(&format!("(truncate-quotient {a} {b})")), tq);
+            assert_eq!(int(&format!("(truncate-remainder {a} {b})")), tr);
+
+            assert_eq!(float(&format!("(floor-quotient {a}.0 {b})")), fq as f64);
+            assert_eq!(float(&format!("(floor-remainder {a} {b}.0)")), fr as f64);
+            assert_eq!(float(&format!("(truncate-quotient {a}.0 {b}.0)")), tq as f64);
+            assert_eq!(float(&format!("(truncate-remainder {a}.0 {b})")), tr as f64);
+
+            assert_eq!(eval(&format!("(floor/ {a} {b})")).unwrap().value,
+                TermValue::Values(MultipleValues(vec![fq.into(), fr.into()])));
+            assert_eq!(eval(&format!("(truncate/ {a} {b})")).unwrap().value,
+                TermValue::Values(MultipleValues(vec![tq.into(), tr.into()])));
+        }
+        assert_eq!(error_kind("(floor-quotient 1.5 2)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(truncate-remainder 1 0.0)"), ErrorKind::DivisionByZero);
+    }
+
+    #[test]
+    fn division_values() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        // (dividend, divisor, floor quotient and remainder, truncate quotient and remainder)
+        let cases = [(13, 4, "(3 1)", "(3 1)"), (-13, 4, "(-4 3)", "(-3 -1)"),
+            (13, -4, "(-4 -3)", "(-3 1)"), (-13, -4, "(3 -1)", "(3 -1)")];
+        for (a, b, floor, truncate) in cases {
+            assert_eq!(text(&format!("(receive (q r) (floor/ {a} {b}) (list q r))")), floor);
+            assert_eq!(text(&format!("(call-with-values ($lambda () (truncate/ {a} {b})) list)")), truncate);
+        }
+        // Any inexact operand makes both results inexact.
+        assert_eq!(text("(receive (q r) (floor/ -13.0 4) (list q r))"), "(-4.0 3.0)");
+        assert_eq!(text("(receive (q r) (truncate/ -13 4.0) (list q r))"), "(-3.0 -1.0)");
+        assert_eq!(text("(receive (q r) (floor/ 13.0 -4.0) (list q r))"), "(-4.0 -3.0)");
+        assert_eq!(error_kind("(floor/ 1 0)"), ErrorKind::DivisionByZero);
+        assert_eq!(error_kind("(truncate/ 1.5 1)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(floor/ 1)"), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn min_and_max() {
+        assert_eq!(int("(min 3 1 2)"), 1);
+        assert_eq!(int("(max 3 1 2)"), 3);
+        assert_eq!(int("(min -3 -7)"), -7);
+        assert_eq!(int("(max -3 -7)"), -3);
+        assert_eq!(int("(max 5)"), 5);
+        assert_eq!(float("(min 1 2.0)"), 1.0);
+        assert_eq!(float("(max 1 2.0 3)"), 3.0);
+        assert_eq!(float("(min 1.5 -2.5)"), -2.5);
+        assert_eq!(float("(max 1.5)"), 1.5);
+        assert_eq!(error_kind("(min)"), ErrorKind::ArityMismatch);
+        assert_eq!(error_kind("(max 1 values)"), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn abs_and_rounding() {
+        assert_eq!(int("(abs -7)"), 7);
+        assert_eq!(int("(abs 7)"), 7);
+        assert_eq!(float("(abs -7.5)"), 7.5);
+        for name in ["floor", "ceiling", "truncate", "round"] {
+            assert_eq!(int(&format!("({name} 3)")), 3);
+            assert_eq!(int(&format!("({name} -3)")), -3);
+        }
+        assert_eq!(float("(floor 3.7)"), 3.0);
+        assert_eq!(float("(floor -3.2)"), -4.0);
+        assert_eq!(float("(ceiling 3.2)"), 4.0);
+        assert_eq!(float("(ceiling -3.7)"), -3.0);
+        assert_eq!(float("(truncate 3.7)"), 3.0);
+        assert_eq!(float("(truncate -3.7)"), -3.0);
+        assert_eq!(float("(round 3.7)"), 4.0);
+        assert_eq!(float("(round 2.5)"), 2.0);
+        assert_eq!(float("(round 3.5)"), 4.0);
+        assert_eq!(float("(round -2.5)"), -2.0);
+        assert_eq!(float("(round -3.5)"), -4.0);
+        assert_eq!(float("(round -0.5)"), -0.0);
+        assert_eq!(int("(inexact->exact (round 7.5))"), 8);
+        assert_eq!(float("(exact->inexact 3)"), 3.0);
+        assert_eq!(ratio("(abs (/ -1 2))"), (1, 2));
+        assert_eq!(int("(floor (/ 7 2))"), 3);
+        assert_eq!(int("(floor (/ -7 2))"), -4);
+        assert_eq!(int("(ceiling (/ 7 2))"), 4);
+        assert_eq!(int("(ceiling (/ -7 2))"), -3);
+        assert_eq!(int("(truncate (/ -7 2))"), -3);
+        assert_eq!(int("(round (/ 5 2))"), 2);
+        assert_eq!(int("(round (/ 7 2))"), 4);
+        assert_eq!(int("(round (/ -5 2))"), -2);
+        assert_eq!(int("(round (/ 5 3))"), 2);
+        assert_eq!(error_kind("(floor values)"), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn exactness_conversion() {
+        assert_eq!(int("(exact 3)"), 3);
+        assert_eq!(int("(exact -2.0)"), -2);
+        assert_eq!(int("(exact (inexact 7))"), 7);
+        assert_eq!(float("(inexact 3)"), 3.0);
+        assert_eq!(float("(inexact 0.25)"), 0.25);
+        assert_eq!(float(r#"(inexact (string->number "1/3"))"#), 1.0 / 3.0);
+        assert_eq!(ratio("(exact (inexact (/ 1 4)))"), (1, 4));
+        for name in ["exact", "inexact->exact"] {
+            assert_eq!(ratio(&format!("({name} 0.5)")), (1, 2));
+            assert_eq!(ratio(&format!("({name} -0.75)")), (-3, 4));
+            assert_eq!(ratio(&format!("({name} 0.1)")), (3602879701896397, 36028797018963968));
+            assert_eq!(error_kind(&format!("({name} 1e300)")), ErrorKind::IntegerOverflow);
+            assert_eq!(error_kind(&format!("({name} 1e-300)")), ErrorKind::IntegerOverflow);
+            assert_eq!(error_kind(&format!("({name} (/ 1.0 0))")), ErrorKind::TypeMismatch);
+            assert_eq!(error_kind(&format!("({name} (/ 0.0 0))")), ErrorKind::TypeMismatch);
+        }
+        for name in ["exact", "inexact", "exact->inexact", "inexact->exact"] {
+            assert_eq!(error_kind(&format!("({name} 'a')")), ErrorKind::TypeMismatch);
+            assert_eq!(error_kind(&format!("({name} 1 2)")), ErrorKind::ArityMismatch);
+        }
+    }
+
+    #[test]
+    fn numeric_type_predicates() {
+        // (value, number?, rational?, integer?, exact?, exact-integer?)
+        let cases = [
+            ("3", true, true, true, Some(true), true),
+            ("-3", true, true, true, Some(true), true),
+            ("3.0", true, true, true, Some(false), false),
+            ("3.5", true, true, false, Some(false), false),
+            ("(/ 7 2)", true, true, false, Some(true), false),
+            // An infinity, as the literal overflows.
+            ("1e400", true, false, false, Some(false), false),
+            ("gcd", false, false, false, None, false),
+        ];
+        for (value, number, rational, integer, exact, exact_integer) in cases {
+            assert_eq!(boolean(&format!("(number? {value})")), number);
+            assert_eq!(boolean(&format!("(complex? {value})")), number);
+            assert_eq!(boolean(&format!("(real? {value})")), number);
+            assert_eq!(boolean(&format!("(rational? {value})")), rational);
+            assert_eq!(boolean(&format!("(integer? {value})")), integer);
+            assert_eq!(boolean(&format!("(exact-integer? {value})")), exact_integer);
+            match exact {
+                Some(exact) => {
+                    assert_eq!(boolean(&format!("(exact? {value})")), exact);
+                    assert_eq!(boolean(&format!("(inexact? {value})")), !exact);
+                }
+                None => {
+                    assert_eq!(error_kind(&format!("(exact? {value})")), ErrorKind::TypeMismatch);
+                    assert_eq!(error_kind(&format!("(inexact? {value})")), ErrorKind::TypeMismatch);
+                }
+            }
+        }
+        assert!(boolean("(exact? (floor 3))"));
+        assert!(!boolean("(exact? (floor 3.0))"));
+    }
+
+    #[test]
+    fn division_errors() {
+        assert_eq!(error_kind("(quotient 1 0)"), ErrorKind::DivisionByZero);
+        assert_eq!(error_kind("(remainder 1 0)"), ErrorKind::DivisionByZero);
+        assert_eq!(error_kind("(modulo 1 0)"), ErrorKind::DivisionByZero);
+        assert_eq!(error_kind("(modulo 1.0 2)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(quotient 1)"), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn sign_and_parity_predicates() {
+        assert!(boolean("(zero? 0)"));
+        assert!(boolean("(zero? 0.0)"));
+        assert!(boolean("(zero? -0.0)"));
+        assert!(!boolean("(zero? 7)"));
+        assert!(boolean("(positive? 7)"));
+        assert!(!boolean("(positive? 0)"));
+        assert!(!boolean("(positive? -7.5)"));
+        assert!(boolean("(negative? -7)"));
+        assert!(boolean("(negative? -7.5)"));
+        assert!(!boolean("(negative? -0.0)"));
+        assert!(!boolean("(zero? (/ 1 2))"));
+        assert!(boolean("(positive? (/ 1 2))"));
+        assert!(boolean("(negative? (/ -1 2))"));
+        assert!(boolean("(odd? 9223372036854775807)"));
+        assert!(!boolean("(even? 9223372036854775807)"));
+        assert!(boolean("(even? 9223372036854775806)"));
+        assert!(boolean("(odd? -3)"));
+        assert!(boolean("(even? 0)"));
+        assert_eq!(error_kind("(odd? 3.0)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(even? 2.0)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(zero? gcd)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(positive? 1 2)"), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn string_to_number_conformance() {
+        enum Expected { Int(i64), Ratio(i64, i64), Float(f64), False }
+        use Expected::*;
+        let cases = [
+            (r##""""##, False),
+            (r##""0""##, Int(0)),
+            (r##""42""##, Int(42)),
+            (r##""+42""##, Int(42)),
+            (r##""-42""##, Int(-42)),
+            (r##""1.5""##, Float(1.5)),
+            (r##""-1.5""##, Float(-1.5)),
+            (r##"".5""##, Float(0.5)),
+            (r##""5.""##, Float(5.0)),
+            (r##""1e3""##, Float(1000.0)),
+            (r##""1E-3""##, Float(0.001)),
+            (r##""+1.5e2""##, Float(150.0)),
+            (r##""+inf.0""##, Float(f64::INFINITY)),
+            (r##""-inf.0""##, Float(f64::NEG_INFINITY)),
+            (r##""inf""##, False),
+            (r##""nan""##, False),
+            (r##""1/2""##, Ratio(1, 2)),
+            (r##""6/3""##, Int(2)),
+            (r##""-6/4""##, Ratio(-3, 2)),
+            (r##""9223372036854775808/2""##, Float(4611686018427387904.0)),
+            (r##""1/0""##, False),
+            (r##""1/-2""##, False),
+            (r##""100" 16"##, Int(256)),
+            (r##""ff" 16"##, Int(255)),
+            (r##""FF" 16"##, Int(255)),
+            (r##""-ff" 16"##, Int(-255)),
+            (r##""777" 8"##, Int(511)),
+            (r##""8" 8"##, False),
+            (r##""101" 2"##, Int(5)),
+            (r##""102" 2"##, False),
+            (r##""z" 36"##, Int(35)),
+            (r##""Zz" 36"##, Int(1295)),
+            (r##""1.5" 16"##, False),
+            (r##""#xff""##, Int(255)),
+            (r##""#XFF""##, Int(255)),
+            (r##""#b1010""##, Int(10)),
+            (r##""#o17""##, Int(15)),
+            (r##""#d99""##, Int(99)),
+            (r##""#x10" 2"##, Int(16)),
+            (r##""#d10" 16"##, Int(10)),
+            (r##""#e1.0""##, Int(1)),
+            (r##""#e1.5""##, Ratio(3, 2)),
+            (r##""#i1/4""##, Float(0.25)),
+            (r##""#i3""##, Float(3.0)),
+            (r##""#x#i10""##, Float(16.0)),
+            (r##""#e#x10""##, Int(16)),
+            (r##""#x#x10""##, False),
+            (r##""#e#i10""##, False),
+            (r##""#q1""##, False),
+            (r##""#""##, False),
+            (r##""+""##, False),
+            (r##""-""##, False),
+            (r##"".""##, False),
+            (r##""1e""##, False),
+            (r##""1..2""##, False),
+            (r##""abc""##, False),
+            (r##"" 1""##, False),
+            (r##""9223372036854775807""##, Int(i64::MAX)),
+            (r##""9223372036854775808""##, Float(9223372036854775808.0)),
+        ];
+        assert!(cases.len() >= 50);
+        for (args, expected) in cases {
+            let result = eval(&format!("(string->number {args})")).unwrap().value;
+            match (expected, result) {
+                (Int(expected), TermValue::Int(n)) => assert_eq!(n, expected, "{args}"),
+                (Ratio(numerator, denominator), TermValue::Rational(ratio)) =>
+                    assert_eq!((ratio.numerator(), ratio.denominator()), (numerator, denominator), "{args}"),
+                (Float(expected), TermValue::Float(x)) => assert_eq!(x, expected, "{args}"),
+                (False, TermValue::Bool(false)) => (),
+                (_, result) => panic!("Unexpected {result:?} for {args}.")
+            }
+        }
+        assert!(float(r#"(string->number "+nan.0")"#).is_nan());
+        assert_eq!(error_kind(r#"(string->number "1" 1)"#), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind(r#"(string->number "1" 37)"#), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind("(string->number 1)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(r#"(string->number)"#), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn number_to_string() {
+        let string = |src: &str| match eval(src).unwrap().value {
+            TermValue::Str(s) => s,
+            value => panic!("{value:?} is not a string.")
+        };
+        assert_eq!(string("(number->string 255 16)"), "ff");
+        assert_eq!(string("(number->string -255 16)"), "-ff");
+        assert_eq!(string("(number->string 0 2)"), "0");
+        assert_eq!(string("(number->string 10 2)"), "1010");
+        assert_eq!(string("(number->string 35 36)"), "z");
+        assert_eq!(string("(number->string 42)"), "42");
+        assert_eq!(string("(number->string -9223372036854775807 16)"), "-7fffffffffffffff");
+        assert_eq!(string("(number->string 1.5)"), "1.5");
+        assert_eq!(string("(number->string 2.0)"), "2.0");
+        assert_eq!(string("(number->string (/ 1 2))"), "1/2");
+        assert_eq!(string("(number->string (/ -255 16) 16)"), "-ff/10");
+        assert_eq!(string(r#"(number->string (string->number "-inf.0"))"#), "-inf.0");
+        assert_eq!(int(r#"(string->number (number->string 12345 7) 7)"#), 12345);
+        assert_eq!(error_kind("(number->string 1.5 2)"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind("(number->string 1 40)"), ErrorKind::InvalidArgument);
+        assert_eq!(error_kind("(number->string 1 'a')"), ErrorKind::TypeMismatch);
+        assert_eq!(error_kind(r#"(number->string "1")"#), ErrorKind::TypeMismatch);
+    }
+}
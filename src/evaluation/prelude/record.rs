@@ -0,0 +1,224 @@
+use std::collections::LinkedList;
+
+use crate::error::{Error, ErrorKind};
+use crate::syntax::Symbol;
+use super::super::context::Context;
+use super::super::record::{RecordConstructorDescriptor, RecordProcedure, RecordProcedureKind, RecordTypeDef};
+use super::super::term::{Term, TermValue};
+use super::list::elements;
+use super::{Arity::*, Builtin, expect_count};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("make-record-type-descriptor", Exactly(6),
+        "A new record type of the name, the parent type or `#f`, the uid `#f`, whether it's sealed and opaque, \
+        and a vector of field specifications like `(mutable x)` or `(immutable y)`.", make_record_type_descriptor),
+    Builtin::new("record-type-descriptor?", Exactly(1), "Whether the value is a record type.", is_record_type_descriptor),
+    Builtin::new("make-record-constructor-descriptor", Exactly(3),
+        "How to construct the records of the type, given the descriptor of its parent or `#f`, and a protocol or `#f`.",
+        make_record_constructor_descriptor),
+    Builtin::new("record-constructor", Exactly(1), "The constructor of the records described by the constructor descriptor.", record_constructor),
+    Builtin::new("record-predicate", Exactly(1), "The predicate of the records of the type, or of a type inheriting from it.", record_predicate),
+    Builtin::new("record-accessor", Exactly(2), "The accessor of the field of the type at the index, among its own fields.", record_accessor),
+    Builtin::new("record-mutator", Exactly(2), "The mutator of the mutable field of the type at the index, among its own fields.", record_mutator),
+    Builtin::new("record?", Exactly(1), "Whether the value is a record of a type which isn't opaque.", is_record),
+    Builtin::new("record-rtd", Exactly(1), "The type of the record, which mustn't be opaque.", record_rtd),
+];
+
+fn expect_record_type(name: &str, arg: &Term) -> Result<RecordTypeDef, Error> {
+    match arg.value {
+        TermValue::RecordTypeDescriptor(ref record_type) => Ok(record_type.clone()),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a record type descriptor, but got the {} {value}.", value.type_name())))
+    }
+}
+
+/// The name given as either a symbol or a string.
+fn expect_name(name: &str, arg: &Term) -> Result<Symbol, Error> {
+    match arg.value {
+        TermValue::Sym(ref symbol) => Ok(symbol.clone()),
+        TermValue::Str(ref s) => Ok(Symbol::from(s.as_str())),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a name, but got the {} {value}.", value.type_name())))
+    }
+}
+
+/// The name of a field and whether it's mutable, from a specification like `(mutable x)`.
+fn field_spec(name: &str, spec: &Term) -> Result<(Symbol, bool), Error> {
+    let invalid = || Error::new(ErrorKind::InvalidArgument)
+        .with_message(format!("'{name}' expects a field specification like (mutable x) or (immutable x), but got {spec}."));
+    match elements(name, spec.clone()).map_err(|_| invalid())?.as_slice() {
+        [mutability, field] => match expect_name(name, mutability)?.as_ref().as_str() {
+            "mutable" => Ok((expect_name(name, field)?, true)),
+            "immutable" => Ok((expect_name(name, field)?, false)),
+            _ => Err(invalid())
+        },
+        _ => Err(invalid())
+    }
+}
+
+/// The types are always generative, so a uid, which would make one nongenerative, is rejected.
+fn make_record_type_descriptor(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let name = "make-record-type-descriptor";
+    let type_name = expect_name(name, &args[0])?;
+    let parent = match args[1].value {
+        TermValue::Bool(false) => None,
+        _ => Some(expect_record_type(name, &args[1])?)
+    };
+    if let Some(parent) = parent.as_ref().filter(|parent| parent.is_sealed()) {
+        return Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("The record type {} is sealed, so {type_name} can't inherit from it.", parent.name())))
+    }
+    if !args[2].is_false() {
+        return Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("'{name}' expects the uid #f, as nongenerative record types aren't supported.")))
+    }
+    let specs = match args[5].value {
+        TermValue::Vector(ref vector) => vector.borrow().clone(),
+        ref value => return Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a vector of fields, but got the {} {value}.", value.type_name())))
+    };
+    let fields = specs.iter().map(|spec| field_spec(name, spec)).collect::<Result<Vec<_>, Error>>()?;
+    let record_type = RecordTypeDef::new(type_name, parent, !args[3].is_false(), !args[4].is_false(), fields);
+    Ok(Term::from(record_type))
+}
+
+fn is_record_type_descriptor(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::RecordTypeDescriptor(_)).into())
+}
+
+fn make_record_constructor_descriptor(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let name = "make-record-constructor-descriptor";
+    let record_type = expect_record_type(name, &args[0])?;
+    let parent = match args[1].value {
+        TermValue::Bool(false) => None,
+        TermValue::RecordConstructorDescriptor(ref parent) => Some(parent.clone()),
+        ref value => return Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a record constructor descriptor or #f, but got the {} {value}.", value.type_name())))
+    };
+    let protocol = (!args[2].is_false()).then(|| args[2].clone());
+    Ok(Term::from(RecordConstructorDescriptor::new(record_type, parent, protocol)?))
+}
+
+/// Without a protocol, the constructor takes every field in order. Otherwise, it's what the
+/// protocol returns when given the raw constructor, which takes every field in order if the
+/// type has no parent, or else the fields of the parent, returning a constructor of the rest.
+fn record_constructor(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let descriptor = match args[0].value {
+        TermValue::RecordConstructorDescriptor(ref descriptor) => descriptor.clone(),
+        ref value => return Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'record-constructor' expects a record constructor descriptor, but got the {} {value}.",
+                value.type_name())))
+    };
+    let record_type = descriptor.record_type().clone();
+    let constructor = |kind| Term::from(RecordProcedure::new(record_type.clone(), kind));
+    match descriptor.protocol() {
+        None => Ok(constructor(RecordProcedureKind::Constructor(vec![]))),
+        Some(protocol) => {
+            let raw = match record_type.parent() {
+                Some(_) => constructor(RecordProcedureKind::Inherit),
+                None => constructor(RecordProcedureKind::Constructor(vec![]))
+            };
+            ctx.apply(protocol.clone(), LinkedList::from([raw]))
+        }
+    }
+}
+
+fn record_predicate(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let record_type = expect_record_type("record-predicate", &args[0])?;
+    Ok(Term::from(RecordProcedure::new(record_type, RecordProcedureKind::Predicate)))
+}
+
+/// The index among all the fields of the own field at the index given to the procedure.
+fn field_index(name: &str, args: &[Term]) -> Result<(RecordTypeDef, usize), Error> {
+    let record_type = expect_record_type(name, &args[0])?;
+    let index = expect_count(name, &args[1])?;
+    match record_type.own_field_index(index) {
+        Some(index) => Ok((record_type, index)),
+        None => Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("The record type {} has no own field at the index {index}.", record_type.name())))
+    }
+}
+
+fn record_accessor(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (record_type, index) = field_index("record-accessor", args)?;
+    Ok(Term::from(RecordProcedure::new(record_type, RecordProcedureKind::Accessor(index))))
+}
+
+fn record_mutator(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let (record_type, index) = field_index("record-mutator", args)?;
+    let (field, mutable) = record_type.field(index);
+    if !mutable {
+        return Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("The field '{field}' of the record type {} is immutable.", record_type.name())))
+    }
+    Ok(Term::from(RecordProcedure::new(record_type, RecordProcedureKind::Mutator(index))))
+}
+
+fn is_record(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::Record(ref record) if !record.record_type().is_opaque()).into())
+}
+
+fn record_rtd(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    match args[0].value {
+        TermValue::Record(ref record) if !record.record_type().is_opaque() => Ok(Term::from(record.record_type().clone())),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'record-rtd' expects a record which isn't opaque, but got the {} {value}.", value.type_name())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::tests::eval;
+
+    const POINT: &str = "($define point (make-record-type-descriptor 'point' #f #f #f #f \
+        (vector (list 'mutable' 'x') (list 'immutable' 'y')))) \
+        ($define make-point (record-constructor (make-record-constructor-descriptor point #f #f))) \
+        ($define point? (record-predicate point)) \
+        ($define point-x (record-accessor point 0)) \
+        ($define point-y (record-accessor point 1)) \
+        ($define point-x-set! (record-mutator point 0)) ";
+
+    #[test]
+    fn procedural_records() {
+        let text = |src: &str| eval(&format!("{POINT}{src}")).unwrap().to_string();
+        assert_eq!(text("($define p (make-point 1 2)) (point-x-set! p 10) (list (point? p) (point-x p) (point-y p))"), "(#t 10 2)");
+        assert_eq!(text("(list (point? 1) (record-type-descriptor? point) (record-type-descriptor? 1))"), "(#f #t #f)");
+        assert_eq!(text("(make-point 1 2)"), "#<record point 1 2>");
+        assert_eq!(text("(list point point-x (type-of point))"),
+            "(#<record-type-descriptor point> #<procedure point-x> record-type-descriptor)");
+        assert_eq!(text("($define p (make-point 1 2)) ($define q p) (point-x-set! q 3) (list (point-x p) (eq? p q) (eqv? p (make-point 3 2)))"),
+            "(3 #t #f)");
+        assert_eq!(text("(list (record? (make-point 1 2)) (record? 1) (eq? (record-rtd (make-point 1 2)) point))"), "(#t #f #t)");
+
+        // A subtype takes the fields of its parent first, and the raw constructor given to a
+        // protocol takes them separately from its own ones.
+        let sub = "($define point3 (make-record-type-descriptor 'point3' point #f #t #f (vector (list 'mutable' 'z')))) \
+            ($define point3-cd (make-record-constructor-descriptor point3 \
+                (make-record-constructor-descriptor point #f #f) \
+                ($lambda (p) ($lambda (x y z) ((p x y) (* z 2)))))) \
+            ($define make-point3 (record-constructor point3-cd)) \
+            ($define p (make-point3 1 2 3)) ";
+        assert_eq!(text(&format!("{sub}(list (point? p) ((record-predicate point3) p) (point-x p) ((record-accessor point3 0) p) ((record-predicate point3) (make-point 1 2)))")),
+            "(#t #t 1 6 #f)");
+        let protocol = "($define make-origin (record-constructor (make-record-constructor-descriptor point #f \
+            ($lambda (new) ($lambda () (new 0 0)))))) ";
+        assert_eq!(text(&format!("{protocol}(make-origin)")), "#<record point 0 0>");
+
+        let kind = |src: &str| eval(&format!("{POINT}{src}")).unwrap_err().kind();
+        assert_eq!(kind("(make-point 1)"), ErrorKind::ArityMismatch);
+        assert_eq!(kind("(point-x 1)"), ErrorKind::TypeMismatch);
+        assert_eq!(kind("(record-mutator point 1)"), ErrorKind::InvalidArgument);
+        assert_eq!(kind("(record-accessor point 2)"), ErrorKind::InvalidArgument);
+        assert_eq!(kind("(make-record-type-descriptor 'p' #f #f #f #f (vector (list 'const' 'x')))"), ErrorKind::InvalidArgument);
+        assert_eq!(kind("(make-record-type-descriptor 'p' #f 'uid' #f #f (vector))"), ErrorKind::InvalidArgument);
+        assert_eq!(kind("(make-record-type-descriptor 'p' 1 #f #f #f (vector))"), ErrorKind::TypeMismatch);
+        assert_eq!(kind(&format!("{sub}(make-record-type-descriptor 'p' point3 #f #f #f (vector))")), ErrorKind::InvalidArgument);
+        assert_eq!(kind("(make-record-constructor-descriptor point (make-record-constructor-descriptor point #f #f) #f)"),
+            ErrorKind::InvalidArgument);
+        let opaque = "(record-constructor (make-record-constructor-descriptor \
+            (make-record-type-descriptor 'secret' #f #f #f #t (vector)) #f #f))";
+        assert_eq!(text(&format!("(list (record? ({opaque})) (type-of ({opaque})))")), "(#f record)");
+        assert_eq!(kind(&format!("(record-rtd ({opaque}))")), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,445 @@
+use std::collections::LinkedList;
+
+use crate::error::{Error, ErrorKind};
+use super::super::context::Context;
+use super::super::term::{Term, TermValue};
+use super::list::elements;
+use super::{Arity::*, Builtin, allocation_failed, expect_count, expect_int, expect_string};
+
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("char-foldcase", Exactly(1), "The character with its case folded.", char_foldcase),
+    Builtin::new("char-alphabetic?", Exactly(1), "Whether the character is alphabetic.", is_alphabetic),
+    Builtin::new("char-numeric?", Exactly(1), "Whether the character is numeric.", is_numeric),
+    Builtin::new("char-whitespace?", Exactly(1), "Whether the character is whitespace.", is_whitespace),
+    Builtin::new("make-string", Between(1, 2), "A new string of the count copies of the character, or of NUL.", make_string),
+    Builtin::new("string", AtLeast(0), "A new string of the characters.", string),
+    Builtin::new("list->string", Exactly(1), "A new string of the characters in the list.", list_to_string),
+    Builtin::new("string->list", Between(1, 3), "A new list of the characters of the string, between the start and the end.", string_to_list),
+    Builtin::new("string-contains", Exactly(2), "The index of the first occurrence of the second string in the first one, or `#f`.", string_contains),
+    Builtin::new("string-contains-right", Exactly(2), "The index of the last occurrence of the second string in the first one, or `#f`.", string_contains_right),
+    Builtin::new("string-copy", Between(1, 3), "A new string of the characters of the string between the start and the end.", string_copy),
+    Builtin::new("string-foldcase", Exactly(1), "The string with the case of every character folded, for comparing it regardless of case.", string_foldcase),
+    Builtin::new("string-index", Between(2, 4), "The index of the first character satisfying the predicate, between the start and the end, or `#f`.", string_index),
+    Builtin::new("string-index-right", Between(2, 4), "The index of the last character satisfying the predicate, between the start and the end, or `#f`.", string_index_right),
+    Builtin::new("string-pad", Between(2, 3), "The string padded on the left with the character, or spaces, or truncated from the left, to the length.", string_pad),
+    Builtin::new("string-pad-right", Between(2, 3), "The string padded on the right with the character, or spaces, or truncated from the right, to the length.", string_pad_right),
+    Builtin::new("string-replace", Exactly(4), "The first string with its characters from the start to the end replaced by the second.", string_replace),
+    Builtin::new("string-reverse", Between(1, 3), "The string with its characters between the start and the end in reverse order.", string_reverse),
+    Builtin::new("string-skip", Between(2, 4), "The index of the first character not satisfying the predicate, between the start and the end, or `#f`.", string_skip),
+];
+
+pub(super) fn expect_char(name: &str, arg: &Term) -> Result<char, Error> {
+    match arg.value {
+        TermValue::Char(ch) => Ok(ch),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a character, but got the {} {value}.", value.type_name())))
+    }
+}
+
+fn classify(name: &str, args: &[Term], class: fn(char) -> bool) -> Result<Term, Error> {
+    Ok(class(expect_char(name, &args[0])?).into())
+}
+
+fn is_alphabetic(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    classify("char-alphabetic?", args, char::is_alphabetic)
+}
+
+fn is_numeric(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    classify("char-numeric?", args, char::is_numeric)
+}
+
+fn is_whitespace(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    classify("char-whitespace?", args, char::is_whitespace)
+}
+
+/// The simple case folding of the character, which is a single character.
+#[cfg(feature = "unicode-case")]
+fn fold_char(ch: char) -> char {
+    unicode_case_mapping::case_folded(ch).and_then(|folded| char::from_u32(folded.get())).unwrap_or(ch)
+}
+
+/// The lowercase of the character when it is a single one, which is its folding for all but
+/// a few scripts.
+#[cfg(not(feature = "unicode-case"))]
+fn fold_char(ch: char) -> char {
+    let mut lower = ch.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => ch
+    }
+}
+
+/// The full case folding of the character, which expands the ligatures and sharp s that
+/// have no single folded character.
+fn fold_full(ch: char) -> &'static str {
+    match ch {
+        'ß' | 'ẞ' => "ss",
+        'ŉ' => "ʼn",
+        'İ' => "i\u{307}",
+        'ﬀ' => "ff",
+        'ﬁ' => "fi",
+        'ﬂ' => "fl",
+        'ﬃ' => "ffi",
+        'ﬄ' => "ffl",
+        'ﬅ' | 'ﬆ' => "st",
+        _ => ""
+    }
+}
+
+fn char_foldcase(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let ch = expect_char("char-foldcase", &args[0])?;
+    Ok(Term::from(fold_char(ch)))
+}
+
+fn string_foldcase(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let s = expect_string("string-foldcase", &args[0])?;
+    let mut folded = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match fold_full(ch) {
+            "" => folded.push(fold_char(ch)),
+            full => folded.push_str(full)
+        }
+    }
+    Ok(Term::string(folded))
+}
+
+/// The range given by the optional start and end arguments, which default to the whole string
+/// or vector. Both are counted in characters rather than bytes for a string.
+pub(super) fn expect_range(name: &str, args: &mut dyn Iterator<Item = &Term>, len: usize) -> Result<(usize, usize), Error> {
+    let start = args.next().map_or(Ok(0), |arg| expect_int(name, arg))?;
+    let end = args.next().map_or(Ok(len as i64), |arg| expect_int(name, arg))?;
+    if 0 <= start && start <= end && end <= len as i64 {
+        Ok((start as usize, end as usize))
+    } else {
+        Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("'{name}' expects a range within 0 to {len}, but got {start} to {end}.")))
+    }
+}
+
+/// The index of the first character in range, or the last one `from_right`, for which
+/// the predicate holds as `wanted`.
+fn search(name: &str, args: &[Term], ctx: &mut Context, wanted: bool, from_right: bool) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let chars: Vec<char> = expect_string(name, args.next().unwrap())?.chars().collect();
+    let predicate = args.next().unwrap();
+    let (start, end) = expect_range(name, &mut args, chars.len())?;
+    let indices: Box<dyn Iterator<Item = usize>> = if from_right {
+        Box::new((start..end).rev())
+    } else {
+        Box::new(start..end)
+    };
+    for i in indices {
+        let argument = Term::from(chars[i]);
+        if ctx.apply(predicate.clone(), LinkedList::from([argument]))?.is_false() != wanted {
+            return Ok(Term::int(i as i64))
+        }
+    }
+    Ok(Term::boolean(false))
+}
+
+fn string_index(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    search("string-index", args, ctx, true, false)
+}
+
+fn string_index_right(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    search("string-index-right", args, ctx, true, true)
+}
+
+fn string_skip(ctx: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    search("string-skip", args, ctx, false, false)
+}
+
+fn make_string(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let count = expect_count("make-string", &args[0])?;
+    let fill = args.get(1).map_or(Ok('\0'), |arg| expect_char("make-string", arg))?;
+    let mut s = String::new();
+    count.checked_mul(fill.len_utf8())
+        .and_then(|bytes| s.try_reserve_exact(bytes).ok())
+        .ok_or_else(|| allocation_failed("make-string", count))?;
+    s.extend(std::iter::repeat_n(fill, count));
+    Ok(Term::string(s))
+}
+
+fn string(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let chars = args.iter()
+        .map(|arg| expect_char("string", arg))
+        .collect::<Result<String, Error>>()?;
+    Ok(Term::string(chars))
+}
+
+fn string_copy(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let chars: Vec<char> = expect_string("string-copy", args.next().unwrap())?.chars().collect();
+    let (start, end) = expect_range("string-copy", &mut args, chars.len())?;
+    Ok(Term::string(chars[start..end].iter().collect::<String>()))
+}
+
+fn list_to_string(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let chars = elements("list->string", args[0].clone())?.iter()
+        .map(|element| expect_char("list->string", element))
+        .collect::<Result<String, Error>>()?;
+    Ok(Term::string(chars))
+}
+
+fn string_to_list(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let chars: Vec<char> = expect_string("string->list", args.next().unwrap())?.chars().collect();
+    let (start, end) = expect_range("string->list", &mut args, chars.len())?;
+    Ok(chars[start..end].iter().rev()
+        .fold(Term::nil(), |tail, &ch| Term::cons(Term::from(ch), tail)))
+}
+
+/// The index in characters of the first occurrence of the needle, or the last one `from_right`,
+/// where occurrences may overlap, and an empty needle occurs at either end.
+fn contains(name: &str, args: &[Term], from_right: bool) -> Result<Term, Error> {
+    let haystack: Vec<char> = expect_string(name, &args[0])?.chars().collect();
+    let needle: Vec<char> = expect_string(name, &args[1])?.chars().collect();
+    if needle.len() > haystack.len() { return Ok(Term::boolean(false)) }
+    let mut starts = 0..=haystack.len() - needle.len();
+    let found = if from_right {
+        starts.rfind(|&i| haystack[i..].starts_with(&needle))
+    } else {
+        starts.find(|&i| haystack[i..].starts_with(&needle))
+    };
+    Ok(found.map_or(Term::boolean(false), |i| Term::int(i as i64)))
+}
+
+fn string_contains(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    contains("string-contains", args, false)
+}
+
+fn string_contains_right(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    contains("string-contains-right", args, true)
+}
+
+/// The characters outside the range are kept in place, and the characters are Unicode scalar
+/// values, so that a character written with several bytes stays whole.
+fn string_reverse(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let mut chars: Vec<char> = expect_string("string-reverse", args.next().unwrap())?.chars().collect();
+    let (start, end) = expect_range("string-reverse", &mut args, chars.len())?;
+    chars[start..end].reverse();
+    Ok(Term::string(chars.into_iter().collect::<String>()))
+}
+
+/// Pad or truncate the string to the length, on the left or the right, keeping the characters
+/// on the other side.
+fn pad(name: &str, args: &[Term], left: bool) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let s = expect_string(name, args.next().unwrap())?;
+    let width = expect_count(name, args.next().unwrap())?;
+    let fill = args.next().map_or(Ok(' '), |arg| expect_char(name, arg))?;
+    let len = s.chars().count();
+    match (left, len >= width) {
+        (true, true) => return Ok(Term::string(s.chars().skip(len - width).collect::<String>())),
+        (false, true) => return Ok(Term::string(s.chars().take(width).collect::<String>())),
+        _ => ()
+    }
+    let mut padded = String::new();
+    (width - len).checked_mul(fill.len_utf8())
+        .and_then(|bytes| bytes.checked_add(s.len()))
+        .and_then(|bytes| padded.try_reserve_exact(bytes).ok())
+        .ok_or_else(|| allocation_failed(name, width))?;
+    let padding = std::iter::repeat_n(fill, width - len);
+    if left { padded.extend(padding.chain(s.chars())) } else { padded.extend(s.chars().chain(padding)) }
+    Ok(Term::string(padded))
+}
+
+fn string_pad(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    pad("string-pad", args, true)
+}
+
+fn string_pad_right(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    pad("string-pad-right", args, false)
+}
+
+fn string_replace(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let chars: Vec<char> = expect_string("string-replace", args.next().unwrap())?.chars().collect();
+    let replacement = expect_string("string-replace", args.next().unwrap())?;
+    let (start, end) = expect_range("string-replace", &mut args, chars.len())?;
+    let replaced: String = chars[..start].iter().copied()
+        .chain(replacement.chars())
+        .chain(chars[end..].iter().copied())
+        .collect();
+    Ok(Term::string(replaced))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::super::term::TermValue;
+    use super::super::list::elements;
+    use super::super::tests::eval;
+
+    #[test]
+    fn string_search() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(string-index "12ab3" char-alphabetic?)"#), "2");
+        assert_eq!(text(r#"(string-index "abc" char-numeric?)"#), "#f");
+        assert_eq!(text(r#"(string-index "" char-numeric?)"#), "#f");
+        assert_eq!(text(r#"(string-index "a b" char-whitespace?)"#), "1");
+        assert_eq!(text(r#"(string-index "a1b2c3" char-numeric? 2)"#), "3");
+        assert_eq!(text(r#"(string-index "a1b2c3" char-numeric? 2 3)"#), "#f");
+        assert_eq!(text(r#"(string-index "a1b2c3" char-numeric? 0 6)"#), "1");
+        assert_eq!(text(r#"(string-index-right "a1b2c3" char-numeric?)"#), "5");
+        assert_eq!(text(r#"(string-index-right "a1b2c3" char-numeric? 0 5)"#), "3");
+        assert_eq!(text(r#"(string-index-right "abc" char-numeric?)"#), "#f");
+        assert_eq!(text(r#"(string-skip "   x " char-whitespace?)"#), "3");
+        assert_eq!(text(r#"(string-skip "   " char-whitespace?)"#), "#f");
+        assert_eq!(text(r#"(string-skip "ab1" char-alphabetic? 1)"#), "2");
+        // The indices count characters rather than bytes.
+        assert_eq!(text(r#"(string-index "日本語2" char-numeric?)"#), "3");
+        assert_eq!(text(r#"(string-skip "ñandú!" char-alphabetic?)"#), "5");
+        assert_eq!(text(r#"(string-index "x" ($lambda (c) (equal? c #\x)))"#), "0");
+
+        for src in [r#"(string-index "abc" char-numeric? 2 1)"#, r#"(string-index "abc" char-numeric? 0 4)"#,
+            r#"(string-skip "abc" char-numeric? -1)"#] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidArgument, "{src}");
+        }
+        assert_eq!(eval("(string-index 1 char-numeric?)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(char-numeric? "1")"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn string_constructors() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r"(make-string 3 #\a)"), r#""aaa""#);
+        assert_eq!(text("(make-string 2)"), "\"\0\0\"");
+        assert_eq!(text(r"(equal? (make-string 2) (make-string 2 #\nul))"), "#t");
+        assert_eq!(text(r"(make-string 2 #\alarm)"), "\"\u{7}\u{7}\"");
+        assert_eq!(text(r"(make-string 0 #\a)"), r#""""#);
+        assert_eq!(text(r"(make-string 2 #\é)"), r#""éé""#);
+        assert_eq!(text(r"(string #\h #\i)"), r#""hi""#);
+        assert_eq!(text("(string)"), r#""""#);
+        assert_eq!(text(r#"(string-copy "hello")"#), r#""hello""#);
+        assert_eq!(text(r#"(string-copy "hello" 1 3)"#), r#""el""#);
+        assert_eq!(text(r#"(string-copy "hello" 3)"#), r#""lo""#);
+        assert_eq!(text(r#"(string-copy "日本語" 1 2)"#), r#""本""#);
+
+        assert_eq!(eval(r"(make-string -1 #\a)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(make-string 9223372036854775807)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval(r"(make-string 100000000000000 #\a)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval(r"(make-string 1.0 #\a)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(make-string 2 'a')").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r"(string #\h 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(string-copy "hello" 3 6)"#).unwrap_err().kind(), ErrorKind::InvalidArgument);
+    }
+
+    #[test]
+    fn string_list_conversion() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(string->list "hello")"#), r"(#\h #\e #\l #\l #\o)");
+        assert_eq!(text(r#"(list->string (string->list "hello"))"#), r#""hello""#);
+        let list = eval(r#"(string->list "hé😀")"#).unwrap();
+        assert_eq!(elements("test", list).unwrap().into_iter().map(|ch| ch.value).collect::<Vec<_>>(),
+            vec![TermValue::Char('h'), TermValue::Char('é'), TermValue::Char('😀')]);
+        // The characters are Unicode scalar values.
+        assert_eq!(text(r#"(string->list "日本語" 1)"#), r"(#\本 #\語)");
+        assert_eq!(text(r#"(string->list "hello" 1 3)"#), r"(#\e #\l)");
+        assert_eq!(text(r#"(string->list "hello" 2 2)"#), "()");
+        assert_eq!(text(r#"(string->list "")"#), "()");
+        assert_eq!(text("(list->string ())"), r#""""#);
+        assert_eq!(text(r"(list->string (list #\ñ #\😀 #\space #\x41))"), r#""ñ😀 A""#);
+        assert_eq!(format!("{:#}", eval(r#"(car (string->list "λ"))"#).unwrap()), "λ");
+
+        assert_eq!(eval(r"(list->string (list #\a 1))").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(list->string "ab")"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(list->string (list "a"))"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(string->list "abc" 2 1)"#).unwrap_err().kind(), ErrorKind::InvalidArgument);
+    }
+
+    #[test]
+    fn string_contains_and_reverse() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(string-contains "abcabc" "bc")"#), "1");
+        assert_eq!(text(r#"(string-contains-right "abcabc" "bc")"#), "4");
+        assert_eq!(text(r#"(string-contains-right "abcabc" "x")"#), "#f");
+        assert_eq!(text(r#"(string-contains-right "ab" "abc")"#), "#f");
+        // Overlapping occurrences are all found.
+        assert_eq!(text(r#"(string-contains-right "aaaa" "aa")"#), "2");
+        assert_eq!(text(r#"(string-contains "aaaa" "aa")"#), "0");
+        assert_eq!(text(r#"(string-contains-right "abc" "")"#), "3");
+        assert_eq!(text(r#"(string-contains "" "")"#), "0");
+        assert_eq!(text(r#"(string-contains-right "日本語の日本" "日本")"#), "4");
+
+        assert_eq!(text(r#"(string-reverse "hello")"#), r#""olleh""#);
+        assert_eq!(text(r#"(string-reverse "résumé")"#), r#""émusér""#);
+        assert_eq!(text(r#"(string-reverse "a😀b🎉")"#), r#""🎉b😀a""#);
+        assert_eq!(text(r#"(string-reverse "")"#), r#""""#);
+        assert_eq!(text(r#"(string-reverse "abcdef" 1 4)"#), r#""adcbef""#);
+        assert_eq!(text(r#"(string-reverse "abcdef" 3)"#), r#""abcfed""#);
+        assert_eq!(text(r#"(string-reverse "日本語" 1 1)"#), r#""日本語""#);
+
+        assert_eq!(eval(r#"(string-reverse "abc" 2 5)"#).unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval(r#"(string-contains-right "abc" 1)"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn string_padding() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(string-pad "42" 5)"#), r#""   42""#);
+        assert_eq!(text(r#"(string-pad-right "42" 5)"#), r#""42   ""#);
+        assert_eq!(text(r#"(string-pad "12345" 3)"#), r#""345""#);
+        assert_eq!(text(r#"(string-pad-right "12345" 3)"#), r#""123""#);
+        assert_eq!(text(r#"(string-pad "abc" 3)"#), r#""abc""#);
+        assert_eq!(text(r#"(string-pad "7" 3 #\0)"#), r#""007""#);
+        assert_eq!(text(r#"(string-pad-right "ab" 4 #\.)"#), r#""ab..""#);
+        assert_eq!(text(r#"(string-pad "abc" 0)"#), r#""""#);
+        assert_eq!(text(r#"(string-pad-right "" 0)"#), r#""""#);
+        assert_eq!(text(r#"(string-pad "日本" 3 #\・)"#), r#""・日本""#);
+        assert_eq!(text(r#"(string-pad-right "日本語" 2)"#), r#""日本""#);
+
+        // A width there isn't enough memory for is an error rather than an aborted allocation.
+        for src in [r#"(string-pad "a" -1)"#,
+            r#"(string-pad "a" 9223372036854775807)"#, r#"(string-pad-right "a" 100000000000000 #\・)"#] {
+            assert_eq!(eval(src).unwrap_err().kind(), ErrorKind::InvalidArgument, "{src}");
+        }
+        assert_eq!(eval(r#"(string-pad "a" 1.5)"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(string-pad-right "a" 2 "b")"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn string_replacement() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(string-replace "The quick fox" "red" 4 9)"#), r#""The red fox""#);
+        assert_eq!(text(r#"(string-replace "a-c" "bbb" 1 2)"#), r#""abbbc""#);
+        assert_eq!(text(r#"(string-replace "ac" "b" 1 1)"#), r#""abc""#);
+        assert_eq!(text(r#"(string-replace "ac" "b" 2 2)"#), r#""acb""#);
+        assert_eq!(text(r#"(string-replace "abc" "xyz" 0 3)"#), r#""xyz""#);
+        assert_eq!(text(r#"(string-replace "abc" "" 0 3)"#), r#""""#);
+        assert_eq!(text(r#"(string-replace "日本語" "本" 2 3)"#), r#""日本本""#);
+
+        let message = |src: &str| eval(src).unwrap_err().message().clone();
+        assert_eq!(message(r#"(string-replace "abc" "x" -1 2)"#),
+            "'string-replace' expects a range within 0 to 3, but got -1 to 2.");
+        assert_eq!(message(r#"(string-replace "abc" "x" 0 4)"#),
+            "'string-replace' expects a range within 0 to 3, but got 0 to 4.");
+        assert_eq!(message(r#"(string-replace "abc" "x" 2 1)"#),
+            "'string-replace' expects a range within 0 to 3, but got 2 to 1.");
+        assert_eq!(eval(r#"(string-replace "abc" "x" 4 4)"#).unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval(r#"(string-replace "abc" 1 0 1)"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(string-replace "abc" "x" 0)"#).unwrap_err().kind(), ErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn case_folding() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(char-foldcase #\A)"#), r#"#\a"#);
+        assert_eq!(text(r#"(char-foldcase #\a)"#), r#"#\a"#);
+        assert_eq!(text(r#"(char-foldcase #\1)"#), r#"#\1"#);
+        assert_eq!(text(r#"(char-foldcase #\Σ)"#), r#"#\σ"#);
+        assert_eq!(text(r#"(char-foldcase #\ß)"#), r#"#\ß"#);
+        assert_eq!(text(r#"(string-foldcase "Straße")"#), r#""strasse""#);
+        assert_eq!(text(r#"(string-foldcase "Hello, World!")"#), r#""hello, world!""#);
+        assert_eq!(text(r#"(string-foldcase "ﬁle")"#), r#""file""#);
+        assert_eq!(text(r#"(string-foldcase "")"#), r#""""#);
+        assert_eq!(text(r#"(equal? (string-foldcase "STRASSE") (string-foldcase "straße"))"#), "#t");
+        #[cfg(feature = "unicode-case")]
+        assert_eq!(text(r#"(char-foldcase #\ſ)"#), r#"#\s"#);
+
+        assert_eq!(eval(r#"(char-foldcase "a")"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(char-foldcase 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval("(string-foldcase 1)").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::error::{Error, ErrorKind};
+use super::super::context::Context;
+use super::super::term::{Term, TermValue, UnitValue, VectorValue};
+use super::string::{expect_char, expect_range};
+use super::{Arity::*, Builtin, allocation_failed, expect_count, expect_string};
+
+// Vectors are always mutable, whereas pairs are only with the `mutable-pairs` feature.
+pub(super) const BUILTINS: &[Builtin] = &[
+    Builtin::new("vector", AtLeast(0), "A new vector of the arguments.", vector),
+    Builtin::new("make-vector", Between(1, 2), "A new vector of the count copies of the value, or of `#f`.", make_vector),
+    Builtin::new("vector?", Exactly(1), "Whether the value is a vector.", is_vector),
+    Builtin::new("vector-length", Exactly(1), "The number of elements of the vector.", vector_length),
+    Builtin::new("vector-ref", Exactly(2), "The element of the vector at the index.", vector_ref),
+    Builtin::new("vector-set!", Exactly(3), "Replace the element of the vector at the index.", vector_set),
+    Builtin::new("vector-fill!", Between(2, 4), "Replace the elements of the vector between the start and the end with the value.", vector_fill),
+    Builtin::new("vector->string", Between(1, 3), "A new string of the characters of the vector, between the start and the end.", vector_to_string),
+    Builtin::new("string->vector", Between(1, 3), "A new vector of the characters of the string, between the start and the end.", string_to_vector),
+];
+
+fn expect_vector<'a>(name: &str, arg: &'a Term) -> Result<&'a VectorValue, Error> {
+    match arg.value {
+        TermValue::Vector(ref vector) => Ok(vector),
+        ref value => Err(Error::new(ErrorKind::TypeMismatch)
+            .with_message(format!("'{name}' expects a vector, but got the {} {value}.", value.type_name())))
+    }
+}
+
+pub(super) fn expect_index(name: &str, arg: &Term, len: usize) -> Result<usize, Error> {
+    match expect_count(name, arg)? {
+        i if i < len => Ok(i),
+        i => Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message(format!("'{name}' expects an index below {len}, but got {i}.")))
+    }
+}
+
+fn vector_of(elements: Vec<Term>) -> Term {
+    Term::from(VectorValue::new(elements))
+}
+
+fn vector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(vector_of(args.to_vec()))
+}
+
+fn make_vector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let count = expect_count("make-vector", &args[0])?;
+    let fill = args.get(1).cloned().unwrap_or(Term::boolean(false));
+    let mut elements = vec![];
+    elements.try_reserve_exact(count).map_err(|_| allocation_failed("make-vector", count))?;
+    elements.resize(count, fill);
+    Ok(vector_of(elements))
+}
+
+fn is_vector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(matches!(args[0].value, TermValue::Vector(_)).into())
+}
+
+fn vector_length(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    Ok(Term::int(expect_vector("vector-length", &args[0])?.borrow().len() as i64))
+}
+
+fn vector_ref(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let vector = expect_vector("vector-ref", &args[0])?.borrow();
+    let index = expect_index("vector-ref", &args[1], vector.len())?;
+    Ok(vector[index].clone())
+}
+
+fn vector_set(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut vector = expect_vector("vector-set!", &args[0])?.borrow_mut();
+    let index = expect_index("vector-set!", &args[1], vector.len())?;
+    vector[index] = args[2].clone();
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn vector_fill(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let mut vector = expect_vector("vector-fill!", args.next().unwrap())?.borrow_mut();
+    let fill = args.next().unwrap();
+    let (start, end) = expect_range("vector-fill!", &mut args, vector.len())?;
+    vector[start..end].fill(fill.clone());
+    Ok(Term::from(UnitValue::Ignore))
+}
+
+fn vector_to_string(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let vector = expect_vector("vector->string", args.next().unwrap())?.borrow();
+    let (start, end) = expect_range("vector->string", &mut args, vector.len())?;
+    let chars = vector[start..end].iter()
+        .map(|element| expect_char("vector->string", element))
+        .collect::<Result<String, Error>>()?;
+    Ok(Term::string(chars))
+}
+
+fn string_to_vector(_: &mut Context, args: &[Term]) -> Result<Term, Error> {
+    let mut args = args.iter();
+    let chars: Vec<char> = expect_string("string->vector", args.next().unwrap())?.chars().collect();
+    let (start, end) = expect_range("string->vector", &mut args, chars.len())?;
+    Ok(vector_of(chars[start..end].iter().map(|&ch| Term::from(ch)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use super::super::tests::eval;
+
+    #[test]
+    fn vector_basics() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("(vector 1 'a' (list 2 3))"), r#"#(1 "a" (2 3))"#);
+        assert_eq!(text("(vector)"), "#()");
+        assert_eq!(text("(make-vector 3 0)"), "#(0 0 0)");
+        assert_eq!(text("(vector-length (make-vector 4))"), "4");
+        // A count there isn't enough memory for is an error rather than an aborted allocation.
+        assert_eq!(eval("(make-vector 9223372036854775807)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(make-vector 100000000000000 0)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(text("(vector? (vector))"), "#t");
+        assert_eq!(text("(vector? (list))"), "#f");
+        assert_eq!(text("(vector-ref (vector 1 2 3) 2)"), "3");
+        // A vector is changed in place, for every alias of it.
+        assert_eq!(text("($define v (vector 1 2 3)) ($define w v) (vector-set! w 0 'x') v"), r#"#("x" 2 3)"#);
+        assert_eq!(text("(equal? (vector 1 (vector 2)) (vector 1 (vector 2)))"), "#t");
+        assert_eq!(text("(eqv? (vector 1) (vector 1))"), "#f");
+
+        assert_eq!(eval("(vector-ref (vector 1 2) 2)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(vector-set! (vector) -1 0)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(vector-length (list 1))").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn vector_string_conversion() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text(r#"(string->vector "héllo")"#), r#"#(#\h #\é #\l #\l #\o)"#);
+        assert_eq!(text(r#"(string->vector "hello" 1 3)"#), r#"#(#\e #\l)"#);
+        assert_eq!(text(r#"($define v (string->vector "cat")) (vector-set! v 0 #\b) (vector->string v)"#), r#""bat""#);
+        assert_eq!(text(r#"(vector->string (vector #\a #\b #\c #\d) 1)"#), r#""bcd""#);
+        assert_eq!(text(r#"(vector->string (vector #\a #\b #\c #\d) 1 3)"#), r#""bc""#);
+        assert_eq!(text(r#"(vector->string (vector #\space #\x3bb))"#), r#"" λ""#);
+        assert_eq!(text("(vector->string (vector))"), r#""""#);
+
+        assert_eq!(eval(r#"(vector->string (vector #\a 1))"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(vector->string (vector "a"))"#).unwrap_err().kind(), ErrorKind::TypeMismatch);
+        assert_eq!(eval(r#"(string->vector "abc" 2 1)"#).unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval(r#"(string->vector "abc" 0 4)"#).unwrap_err().kind(), ErrorKind::InvalidArgument);
+    }
+
+    #[test]
+    fn vector_filling() {
+        let text = |src: &str| eval(src).unwrap().to_string();
+        assert_eq!(text("($define v (make-vector 3 0)) (vector-fill! v 7) v"), "#(7 7 7)");
+        // The elements outside the range are left as they were.
+        assert_eq!(text("($define v (vector 1 2 3 4 5)) (vector-fill! v 0 1 3) v"), "#(1 0 0 4 5)");
+        assert_eq!(text("($define v (vector 1 2 3)) (vector-fill! v 0 2) v"), "#(1 2 0)");
+        assert_eq!(text("($define v (vector 1 2 3)) (vector-fill! v 0 1 1) v"), "#(1 2 3)");
+
+        assert_eq!(eval("(vector-fill! (vector 1 2) 0 1 3)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(vector-fill! (vector 1 2) 0 -1)").unwrap_err().kind(), ErrorKind::InvalidArgument);
+        assert_eq!(eval("(vector-fill! (vector 1 2) 0 'a')").unwrap_err().kind(), ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,257 @@
+use std::cell::RefCell;
+use std::collections::LinkedList;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::error::{Error, ErrorKind};
+use crate::syntax::Symbol;
+use super::term::{Term, TermValue, UnitValue};
+
+/// A type of record made by `make-record-type-descriptor`, whose fields follow those of its parent.
+#[derive(Clone)]
+pub struct RecordTypeDef(Rc<RecordTypeData>);
+
+struct RecordTypeData {
+    name: Symbol,
+    parent: Option<RecordTypeDef>,
+    /// No type may inherit from a sealed one.
+    sealed: bool,
+    /// The records of an opaque type aren't recognized by `record?`.
+    opaque: bool,
+    /// The names of the fields of the parent followed by the own ones, and whether each is mutable.
+    fields: Vec<(Symbol, bool)>
+}
+
+impl RecordTypeDef {
+    pub fn new(name: Symbol, parent: Option<RecordTypeDef>, sealed: bool, opaque: bool, own_fields: Vec<(Symbol, bool)>) -> Self {
+        let mut fields = parent.as_ref().map_or_else(Vec::new, |parent| parent.0.fields.clone());
+        fields.extend(own_fields);
+        // A type inherits the opacity of its parent.
+        let opaque = opaque || parent.as_ref().is_some_and(RecordTypeDef::is_opaque);
+        Self(Rc::new(RecordTypeData { name, parent, sealed, opaque, fields }))
+    }
+
+    pub fn name(&self) -> &Symbol {
+        &self.0.name
+    }
+
+    pub fn parent(&self) -> Option<&RecordTypeDef> {
+        self.0.parent.as_ref()
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.0.sealed
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.0.opaque
+    }
+
+    /// The number of fields of the type, including those of its ancestors.
+    pub fn field_count(&self) -> usize {
+        self.0.fields.len()
+    }
+
+    /// The index among all the fields of the own field at the index, or `None` if there is none.
+    pub fn own_field_index(&self, index: usize) -> Option<usize> {
+        let inherited = self.parent().map_or(0, RecordTypeDef::field_count);
+        (inherited + index < self.field_count()).then_some(inherited + index)
+    }
+
+    pub fn field(&self, index: usize) -> &(Symbol, bool) {
+        &self.0.fields[index]
+    }
+
+    /// Whether the type is this one, or inherits from it.
+    pub fn is_subtype_of(&self, other: &RecordTypeDef) -> bool {
+        let mut current = Some(self);
+        while let Some(record_type) = current {
+            if record_type == other { return true }
+            current = record_type.parent();
+        }
+        false
+    }
+
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl PartialEq for RecordTypeDef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Debug for RecordTypeDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<record-type-descriptor {}>", self.0.name)
+    }
+}
+
+/// How the records of a type are constructed, made by `make-record-constructor-descriptor`.
+#[derive(Clone)]
+pub struct RecordConstructorDescriptor(Rc<ConstructorData>);
+
+struct ConstructorData {
+    record_type: RecordTypeDef,
+    /// The procedure given the raw constructor and returning the constructor of the records,
+    /// or `None` for a constructor taking every field in order.
+    protocol: Option<Term>
+}
+
+impl RecordConstructorDescriptor {
+    /// Only the default protocol is supported for the parent, so that the raw constructor of
+    /// a subtype can take the fields of its parent in order, and the parent is only checked.
+    pub fn new(record_type: RecordTypeDef, parent: Option<RecordConstructorDescriptor>, protocol: Option<Term>) -> Result<Self, Error> {
+        let invalid = |message: String| Err(Error::new(ErrorKind::InvalidArgument).with_message(message));
+        match (record_type.parent(), &parent) {
+            (None, Some(_)) => return invalid(format!(
+                "The record type {} has no parent, so its constructor descriptor can't have one.", record_type.name())),
+            (Some(expected), Some(parent)) if parent.record_type() != expected => return invalid(format!(
+                "The parent constructor descriptor is for {}, but the parent of {} is {}.",
+                parent.record_type().name(), record_type.name(), expected.name())),
+            (_, Some(parent)) if parent.0.protocol.is_some() => return invalid(format!(
+                "The parent constructor descriptor of {} has a protocol, which isn't supported.", record_type.name())),
+            _ => ()
+        }
+        Ok(Self(Rc::new(ConstructorData { record_type, protocol })))
+    }
+
+    pub fn record_type(&self) -> &RecordTypeDef {
+        &self.0.record_type
+    }
+
+    pub fn protocol(&self) -> Option<&Term> {
+        self.0.protocol.as_ref()
+    }
+
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl PartialEq for RecordConstructorDescriptor {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Debug for RecordConstructorDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<record-constructor-descriptor {}>", self.0.record_type.name())
+    }
+}
+
+/// An instance of a record type, whose fields are shared by every alias of it.
+#[derive(Clone)]
+pub struct Record {
+    record_type: RecordTypeDef,
+    fields: Rc<RefCell<Vec<Term>>>
+}
+
+impl Record {
+    pub fn record_type(&self) -> &RecordTypeDef {
+        &self.record_type
+    }
+
+    pub fn fields(&self) -> Vec<Term> {
+        self.fields.borrow().clone()
+    }
+
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.fields) as usize
+    }
+}
+
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.fields, &other.fields)
+    }
+}
+
+impl Debug for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<record {}>", self.record_type.name())
+    }
+}
+
+/// A procedure derived from a record type by `record-constructor` and the like.
+#[derive(Clone, PartialEq)]
+pub struct RecordProcedure {
+    record_type: RecordTypeDef,
+    kind: RecordProcedureKind
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordProcedureKind {
+    /// Make a record from the values of the fields after the ones already given.
+    Constructor(Vec<Term>),
+    /// Take the values of the fields of the parent, returning the constructor of the rest.
+    Inherit,
+    Predicate,
+    Accessor(usize),
+    Mutator(usize)
+}
+
+impl RecordProcedure {
+    pub fn new(record_type: RecordTypeDef, kind: RecordProcedureKind) -> Self {
+        Self { record_type, kind }
+    }
+
+    fn name(&self) -> String {
+        let name = self.record_type.name();
+        match self.kind {
+            RecordProcedureKind::Constructor(_) | RecordProcedureKind::Inherit => format!("make-{name}"),
+            RecordProcedureKind::Predicate => format!("{name}?"),
+            RecordProcedureKind::Accessor(index) => format!("{name}-{}", self.record_type.field(index).0),
+            RecordProcedureKind::Mutator(index) => format!("{name}-{}-set!", self.record_type.field(index).0)
+        }
+    }
+
+    pub fn call(&self, args: LinkedList<Term>) -> Result<Term, Error> {
+        let arity = match self.kind {
+            RecordProcedureKind::Constructor(ref given) => self.record_type.field_count() - given.len(),
+            RecordProcedureKind::Inherit => self.record_type.parent().map_or(0, RecordTypeDef::field_count),
+            RecordProcedureKind::Predicate | RecordProcedureKind::Accessor(_) => 1,
+            RecordProcedureKind::Mutator(_) => 2
+        };
+        if args.len() != arity {
+            return Err(Error::new(ErrorKind::ArityMismatch)
+                .with_message(format!("'{}' expects {arity} argument(s), but got {}.", self.name(), args.len())))
+        }
+        let mut args = args.into_iter();
+        match self.kind {
+            RecordProcedureKind::Constructor(ref given) => Ok(Term::from(Record {
+                record_type: self.record_type.clone(),
+                fields: Rc::new(RefCell::new(given.iter().cloned().chain(args).collect()))
+            })),
+            RecordProcedureKind::Inherit => Ok(Term::from(
+                RecordProcedure::new(self.record_type.clone(), RecordProcedureKind::Constructor(args.collect())))),
+            RecordProcedureKind::Predicate => Ok(Term::boolean(matches!(args.next().unwrap().value,
+                TermValue::Record(ref record) if record.record_type.is_subtype_of(&self.record_type)))),
+            RecordProcedureKind::Accessor(index) => Ok(self.expect_record(&args.next().unwrap())?.fields.borrow()[index].clone()),
+            RecordProcedureKind::Mutator(index) => {
+                let record = self.expect_record(&args.next().unwrap())?;
+                record.fields.borrow_mut()[index] = args.next().unwrap();
+                Ok(Term::from(UnitValue::Ignore))
+            }
+        }
+    }
+
+    fn expect_record(&self, arg: &Term) -> Result<Record, Error> {
+        match arg.value {
+            TermValue::Record(ref record) if record.record_type.is_subtype_of(&self.record_type) => Ok(record.clone()),
+            ref value => Err(Error::new(ErrorKind::TypeMismatch)
+                .with_message(format!("'{}' expects a record of the type {}, but got the {} {value}.",
+                    self.name(), self.record_type.name(), value.type_name())))
+        }
+    }
+}
+
+impl Debug for RecordProcedure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#<procedure {}>", self.name())
+    }
+}
+
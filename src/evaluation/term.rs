@@ -1,12 +1,18 @@
 use std::cell::RefCell;
-use std::collections::LinkedList;
+use std::collections::{HashSet, LinkedList};
+use std::rc::Rc;
 
 use crate::error::{Error, ErrorKind};
-use crate::syntax::Symbol;
+use crate::if_or;
+use crate::syntax::{write_char_literal, write_string_literal, Node, Symbol};
 
-use super::combiner::NativeFn;
+use super::combiner::{CaseLambda, Lambda, NativeFn, Parameter};
+use super::condition::{ConditionProcedure, ConditionType};
+use super::hash_table::{HashTable, Map};
+use super::port::Port;
+use super::record::{Record, RecordConstructorDescriptor, RecordProcedure, RecordTypeDef};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Term {
     has_value: bool,
     pub(crate) sub_terms: LinkedList<Term>,
@@ -14,14 +20,75 @@ pub struct Term {
     pub(crate) value_ref: RefCell<TermValue>
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TermValue {
     Bool(BooleanValue),
+    Bytevector(BytevectorValue),
+    CaseLambda(CaseLambda),
+    /// A Unicode scalar value, read as `#\a`, `#\space` or `#\x3bb`.
+    Char(char),
+    /// A condition of a type made by `define-condition-type`, with a value for each field.
+    Condition(Rc<ConditionData>),
+    ConditionProcedure(ConditionProcedure),
+    ConditionType(ConditionType),
+    /// A condition made by `error`, or for an error raised by the interpreter itself.
+    ErrorObject(Rc<ErrorObjectData>),
+    /// The end of the input, returned by reads once there is nothing left.
+    Eof,
     Int(i64),
+    Float(f64),
+    HashTable(HashTable),
+    Lambda(Lambda),
+    Map(Map),
+    Nil,
+    Pair(PairValue),
+    Parameter(Parameter),
+    Port(Port),
     PrimitiveFn(NativeFn),
+    /// An exact number which isn't an integer.
+    Rational(Ratio),
+    Record(Record),
+    RecordConstructorDescriptor(RecordConstructorDescriptor),
+    RecordProcedure(RecordProcedure),
+    RecordTypeDescriptor(RecordTypeDef),
     Str(String),
     Sym(Symbol),
     Unit(UnitValue),
+    Values(MultipleValues),
+    Vector(VectorValue),
+}
+
+impl TermValue {
+    /// The name of the type of the value, as returned by `type-of`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TermValue::Bool(_) => "boolean",
+            TermValue::Bytevector(_) => "bytevector",
+            TermValue::Char(_) => "char",
+            TermValue::Condition(_) => "condition",
+            TermValue::ConditionType(_) => "condition-type",
+            TermValue::ErrorObject(_) => "error-object",
+            TermValue::Eof => "eof-object",
+            TermValue::Int(_) => "int",
+            TermValue::Float(_) => "float",
+            TermValue::HashTable(_) => "hash-table",
+            TermValue::CaseLambda(_) | TermValue::ConditionProcedure(_) | TermValue::Lambda(_)
+                | TermValue::Parameter(_) | TermValue::PrimitiveFn(_) | TermValue::RecordProcedure(_) => "procedure",
+            TermValue::Map(_) => "map",
+            TermValue::Nil => "nil",
+            TermValue::Pair(_) => "pair",
+            TermValue::Port(_) => "port",
+            TermValue::Rational(_) => "rational",
+            TermValue::Record(_) => "record",
+            TermValue::RecordConstructorDescriptor(_) => "record-constructor-descriptor",
+            TermValue::RecordTypeDescriptor(_) => "record-type-descriptor",
+            TermValue::Str(_) => "string",
+            TermValue::Sym(_) => "symbol",
+            TermValue::Unit(_) => "unspecified",
+            TermValue::Values(_) => "values",
+            TermValue::Vector(_) => "vector"
+        }
+    }
 }
 
 impl Term {
@@ -34,13 +101,100 @@ impl Term {
         }
     }
 
+    pub fn int(value: i64) -> Self { value.into() }
+
+    pub fn boolean(value: bool) -> Self { value.into() }
+
+    pub fn string<S: Into<String>>(value: S) -> Self { value.into().into() }
+
+    /// A reference to the binding of the symbol, which has no value until it's resolved.
+    pub fn symbol<S: Into<Symbol>>(value: S) -> Self {
+        let mut term = Term::new();
+        term.value = TermValue::Sym(value.into());
+        term
+    }
+
+    /// The empty list.
+    pub fn nil() -> Self {
+        let mut term = Term::new();
+        term.has_value = true;
+        term.value = TermValue::Nil;
+        term
+    }
+
+    pub fn error_object<S: Into<String>>(message: S, irritants: Vec<Term>) -> Self {
+        let mut term = Term::new();
+        term.has_value = true;
+        term.value = TermValue::ErrorObject(Rc::new(ErrorObjectData { message: message.into(), irritants }));
+        term
+    }
+
+    pub fn condition(condition_type: ConditionType, fields: Vec<Term>) -> Self {
+        let mut term = Term::new();
+        term.has_value = true;
+        term.value = TermValue::Condition(Rc::new(ConditionData { condition_type, fields }));
+        term
+    }
+
+    pub fn eof() -> Self {
+        let mut term = Term::new();
+        term.has_value = true;
+        term.value = TermValue::Eof;
+        term
+    }
+
+    /// The node as data rather than code, where lists are chains of pairs and symbols are
+    /// values rather than references to bindings.
+    pub fn datum(node: Node) -> Self {
+        match node {
+            Node::List(nodes, _) => nodes.into_iter().rev()
+                .fold(Term::nil(), |tail, head| Term::cons(Term::datum(head), tail)),
+            Node::Symbol(symbol) => symbol.into(),
+            Node::Map(entries) => Term::from(Map::new(entries.into_iter()
+                .map(|(key, value)| (Term::datum(key), Term::datum(value))))
+                .expect("The keys of a map literal are data, which can always be told apart.")),
+            node => node.into()
+        }
+    }
+
+    pub fn cons(car: Term, cdr: Term) -> Self {
+        PairValue::new(RefCell::new((car, cdr))).into()
+    }
+
+    /// A branch made of the given terms, which is evaluated as a combination.
+    pub fn list(terms: Vec<Term>) -> Self {
+        let mut term = Term::new();
+        term.sub_terms = terms.into_iter().collect();
+        term
+    }
+
+    pub fn has_value(&self) -> bool {
+        self.has_value
+    }
+
+    /// Mark the term as reduced to its value, dropping any sub-terms left over.
+    pub(crate) fn set_has_value(&mut self) {
+        self.sub_terms.clear();
+        self.has_value = true;
+    }
+
+    /// Whether the term is a combination still to be reduced, rather than a value.
     pub fn is_branch(&self) -> bool {
-        !self.sub_terms.is_empty()
+        !self.has_value && !self.sub_terms.is_empty()
     }
 
     pub fn len(&self) -> usize {
         self.sub_terms.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.sub_terms.is_empty()
+    }
+
+    /// Only `#f` is false, and any other value counts as true.
+    pub fn is_false(&self) -> bool {
+        matches!(self.value, TermValue::Bool(false))
+    }
 }
 
 impl Default for Term {
@@ -49,16 +203,196 @@ impl Default for Term {
     }
 }
 
+/// A term is written as it would be read, or with `{:#}` as `display` writes it, where
+/// the strings within it are written as their contents.
 impl std::fmt::Display for Term {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if !self.has_value {
-            write!(f, "{:?}", self.sub_terms)
-        } else {
-            write!(f, "{:?}", self.value)
+        write_pieces(f, Piece::Term(self))
+    }
+}
+
+impl std::fmt::Display for TermValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_pieces(f, Piece::Value(self))
+    }
+}
+
+/// What is left to write of a term, kept on an explicit stack so that deep lists can't
+/// overflow the call stack.
+enum Piece<'a> {
+    Term(&'a Term),
+    Value(&'a TermValue),
+    Owned(Term),
+    /// The rest of a list, following the car of the pair.
+    Cdr(PairValue),
+    Text(&'static str),
+    /// The end of the pair, vector or record at the address, which is no longer being written.
+    Leave(usize)
+}
+
+/// A pair, vector or record met again while it is still being written is part of a cycle,
+/// which is written as `...` rather than followed forever.
+fn write_pieces(f: &mut std::fmt::Formatter<'_>, first: Piece) -> std::fmt::Result {
+    let mut stack = vec![first];
+    // The addresses of the pairs, vectors and records being written.
+    let mut open = HashSet::new();
+    while let Some(piece) = stack.pop() {
+        match piece {
+            Piece::Term(term) if term.is_branch() => {
+                f.write_str("(")?;
+                stack.push(Piece::Text(")"));
+                push_elements(&mut stack, term.sub_terms.iter().map(Piece::Term));
+            }
+            Piece::Owned(term) if term.is_branch() => {
+                f.write_str("(")?;
+                stack.push(Piece::Text(")"));
+                push_elements(&mut stack, term.sub_terms.into_iter().map(Piece::Owned));
+            }
+            Piece::Term(term) => write_value(f, &term.value, &mut stack, &mut open)?,
+            Piece::Value(value) => write_value(f, value, &mut stack, &mut open)?,
+            Piece::Owned(term) => write_value(f, &term.value, &mut stack, &mut open)?,
+            Piece::Cdr(pair) => {
+                let cdr = pair.borrow().1.clone();
+                match cdr.value {
+                    TermValue::Nil => (),
+                    TermValue::Pair(ref next) if open.contains(&next.address()) => f.write_str(" . ...")?,
+                    TermValue::Pair(ref next) => {
+                        f.write_str(" ")?;
+                        open.insert(next.address());
+                        stack.push(Piece::Leave(next.address()));
+                        stack.push(Piece::Cdr(next.clone()));
+                        stack.push(Piece::Owned(next.borrow().0.clone()));
+                    }
+                    _ => {
+                        f.write_str(" . ")?;
+                        stack.push(Piece::Owned(cdr));
+                    }
+                }
+            }
+            Piece::Text(text) => f.write_str(text)?,
+            Piece::Leave(address) => { open.remove(&address); }
+        }
+    }
+    Ok(())
+}
+
+/// Start writing the compound value at the address, unless it is already being written.
+fn enter(f: &mut std::fmt::Formatter<'_>, address: usize, stack: &mut Vec<Piece>, open: &mut HashSet<usize>) -> Result<bool, std::fmt::Error> {
+    if !open.insert(address) {
+        f.write_str("...")?;
+        return Ok(false)
+    }
+    stack.push(Piece::Leave(address));
+    Ok(true)
+}
+
+/// Push the elements of a list to be written in order, separated by spaces.
+fn push_elements<'a, I>(stack: &mut Vec<Piece<'a>>, elements: I)
+    where I: DoubleEndedIterator<Item = Piece<'a>> {
+    for (i, element) in elements.rev().enumerate() {
+        if i > 0 { stack.push(Piece::Text(" ")) }
+        stack.push(element);
+    }
+}
+
+/// Write an atom right away, or push the parts of a compound value.
+fn write_value(f: &mut std::fmt::Formatter<'_>, value: &TermValue, stack: &mut Vec<Piece>, open: &mut HashSet<usize>) -> std::fmt::Result {
+    match value {
+        TermValue::Bool(b) => f.write_str(if *b { "#t" } else { "#f" }),
+        TermValue::Bytevector(bytes) => {
+            let bytes: Vec<String> = bytes.borrow().iter().map(u8::to_string).collect();
+            write!(f, "#u8({})", bytes.join(" "))
+        }
+        TermValue::ErrorObject(object) => {
+            stack.push(Piece::Text(">"));
+            for irritant in object.irritants.iter().rev() {
+                stack.push(Piece::Owned(irritant.clone()));
+                stack.push(Piece::Text(" "));
+            }
+            f.write_str("#<error ")?;
+            write_string_literal(f, &object.message)
+        }
+        TermValue::Condition(condition) => {
+            let ConditionData { condition_type, fields } = condition.as_ref();
+            stack.push(Piece::Text(">"));
+            for field in fields.iter().rev() {
+                stack.push(Piece::Owned(field.clone()));
+                stack.push(Piece::Text(" "));
+            }
+            write!(f, "#<condition {}", condition_type.name())
+        }
+        TermValue::CaseLambda(procedure) => write!(f, "{procedure:?}"),
+        TermValue::Char(ch) if f.alternate() => write!(f, "{ch}"),
+        TermValue::Char(ch) => write_char_literal(f, *ch),
+        TermValue::ConditionProcedure(procedure) => write!(f, "{procedure:?}"),
+        TermValue::ConditionType(condition_type) => write!(f, "{condition_type:?}"),
+        TermValue::Eof => f.write_str("#<eof>"),
+        TermValue::Int(n) => write!(f, "{n}"),
+        TermValue::Float(x) => f.write_str(&format_float(*x)),
+        TermValue::Rational(ratio) => write!(f, "{}/{}", ratio.numerator(), ratio.denominator()),
+        TermValue::HashTable(table) => write!(f, "{table:?}"),
+        TermValue::Lambda(lambda) => write!(f, "{lambda:?}"),
+        TermValue::Map(map) => {
+            stack.push(Piece::Text("}"));
+            let entries = map.entries().into_iter().map(|(key, value)| [key, value]);
+            push_elements(stack, entries.flatten().collect::<Vec<_>>().into_iter().map(Piece::Owned));
+            f.write_str("{")
+        }
+        TermValue::Nil => f.write_str("()"),
+        TermValue::Pair(pair) => {
+            if !enter(f, pair.address(), stack, open)? { return Ok(()) }
+            stack.push(Piece::Text(")"));
+            stack.push(Piece::Cdr(pair.clone()));
+            stack.push(Piece::Owned(pair.borrow().0.clone()));
+            f.write_str("(")
+        }
+        TermValue::Parameter(parameter) => write!(f, "{parameter:?}"),
+        TermValue::Port(port) => match (port.is_binary(), port.is_input()) {
+            (false, true) => f.write_str("#<input-port>"),
+            (false, false) => f.write_str("#<output-port>"),
+            (true, true) => f.write_str("#<binary-input-port>"),
+            (true, false) => f.write_str("#<binary-output-port>")
+        },
+        TermValue::PrimitiveFn(_) => f.write_str("#<builtin>"),
+        TermValue::Record(record) => {
+            if !enter(f, record.id(), stack, open)? { return Ok(()) }
+            stack.push(Piece::Text(">"));
+            for field in record.fields().into_iter().rev() {
+                stack.push(Piece::Owned(field));
+                stack.push(Piece::Text(" "));
+            }
+            write!(f, "#<record {}", record.record_type().name())
+        }
+        TermValue::RecordConstructorDescriptor(descriptor) => write!(f, "{descriptor:?}"),
+        TermValue::RecordProcedure(procedure) => write!(f, "{procedure:?}"),
+        TermValue::RecordTypeDescriptor(record_type) => write!(f, "{record_type:?}"),
+        TermValue::Str(s) if f.alternate() => f.write_str(s),
+        TermValue::Str(s) => write_string_literal(f, s),
+        TermValue::Sym(symbol) => write!(f, "{symbol}"),
+        TermValue::Unit(_) => f.write_str("#<unspecified>"),
+        TermValue::Values(MultipleValues(values)) => {
+            push_elements(stack, values.iter().cloned().map(Piece::Owned));
+            Ok(())
+        }
+        TermValue::Vector(vector) => {
+            if !enter(f, vector.address(), stack, open)? { return Ok(()) }
+            stack.push(Piece::Text(")"));
+            push_elements(stack, vector.borrow().iter().cloned().map(Piece::Owned));
+            f.write_str("#(")
         }
     }
 }
 
+/// Format a float so that it reads back as a float, like `1.0`, `+inf.0` or `+nan.0`.
+pub(crate) fn format_float(x: f64) -> String {
+    match x {
+        x if x.is_nan() => "+nan.0".to_string(),
+        f64::INFINITY => "+inf.0".to_string(),
+        f64::NEG_INFINITY => "-inf.0".to_string(),
+        x => format!("{x:?}")
+    }
+}
+
 pub trait Access<T> {
     fn access(&self) -> &T;
 }
@@ -132,14 +466,282 @@ macro_rules! impl_access {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnitValue {
-    Ignore
+    Ignore,
+    /// The value of a name bound by `($define name)` until it is defined, which is an error to use.
+    Unassigned
 }
 
 type BooleanValue = bool;
 
+/// The type of a condition and the values of its fields, shared by every alias of it.
+#[derive(Debug, PartialEq)]
+pub struct ConditionData {
+    pub condition_type: ConditionType,
+    pub fields: Vec<Term>
+}
+
+/// The message and the irritants of an error object, shared by every alias of it.
+#[derive(Debug, PartialEq)]
+pub struct ErrorObjectData {
+    pub message: String,
+    pub irritants: Vec<Term>
+}
+
+/// The car and the cdr of a pair, shared by every alias of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairValue(Rc<RefCell<(Term, Term)>>);
+
+impl PairValue {
+    pub fn new(pair: RefCell<(Term, Term)>) -> Self {
+        Self(Rc::new(pair))
+    }
+
+    /// The address of the pair, which is the same for every alias of it.
+    pub fn address(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl std::ops::Deref for PairValue {
+    type Target = Rc<RefCell<(Term, Term)>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Empty the pairs and vectors only the terms refer to in a loop rather than dropping them
+/// recursively, so that dropping a long or deeply nested list or vector doesn't overflow the stack.
+fn drop_iteratively(mut pending: Vec<Term>) {
+    while let Some(term) = pending.pop() {
+        match term.value {
+            TermValue::Pair(ref pair) if Rc::strong_count(&pair.0) == 1 => {
+                let (car, cdr) = std::mem::take(&mut *pair.0.borrow_mut());
+                pending.extend([car, cdr]);
+            }
+            TermValue::Vector(ref vector) if Rc::strong_count(&vector.0) == 1 =>
+                pending.append(&mut vector.0.borrow_mut()),
+            _ => ()
+        }
+    }
+}
+
+impl Drop for PairValue {
+    fn drop(&mut self) {
+        if Rc::strong_count(&self.0) != 1 { return }
+        let (car, cdr) = std::mem::take(&mut *self.0.borrow_mut());
+        drop_iteratively(vec![car, cdr]);
+    }
+}
+
+/// An exact ratio of integers in lowest terms, whose denominator is greater than 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numerator: i64,
+    denominator: i64
+}
+
+impl Ratio {
+    /// The exact number `numerator / denominator` in lowest terms, which is an integer if the
+    /// denominator divides the numerator, or `None` if it doesn't fit into 64 bits.
+    pub fn reduce(numerator: i128, denominator: i128) -> Option<Term> {
+        assert_ne!(denominator, 0, "The denominator is non-zero.");
+        let (mut a, mut b) = (numerator.unsigned_abs(), denominator.unsigned_abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        let magnitude = i64::try_from(numerator.unsigned_abs() / a).ok()?;
+        let numerator = if_or!((numerator < 0) != (denominator < 0), -magnitude, magnitude);
+        let denominator = i64::try_from(denominator.unsigned_abs() / a).ok()?;
+        Some(if_or!(denominator == 1, Term::from(numerator), Term::from(Ratio { numerator, denominator })))
+    }
+
+    pub fn numerator(self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(self) -> i64 {
+        self.denominator
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+/// The elements of a vector, shared by every alias of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorValue(Rc<RefCell<Vec<Term>>>);
+
+impl VectorValue {
+    pub fn new(elements: Vec<Term>) -> Self {
+        Self(Rc::new(RefCell::new(elements)))
+    }
+
+    /// The address of the vector, which is the same for every alias of it.
+    pub fn address(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+}
+
+impl std::ops::Deref for VectorValue {
+    type Target = Rc<RefCell<Vec<Term>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for VectorValue {
+    fn drop(&mut self) {
+        if Rc::strong_count(&self.0) != 1 { return }
+        drop_iteratively(std::mem::take(&mut *self.0.borrow_mut()));
+    }
+}
+
+/// The bytes of a bytevector, shared by every alias of it.
+pub type BytevectorValue = Rc<RefCell<Vec<u8>>>;
+
+/// The results of a `values` call, other than a single value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipleValues(pub Vec<Term>);
+
 impl_access!(BooleanValue, Bool);
+impl_access!(BytevectorValue, Bytevector);
+impl_access!(char, Char);
 impl_access!(i64, Int);
+impl_access!(f64, Float);
+impl_access!(Ratio, Rational);
+impl_access!(PairValue, Pair);
 impl_access!(NativeFn, PrimitiveFn);
+impl_access!(Parameter, Parameter);
+impl_access!(ConditionType, ConditionType);
+impl_access!(ConditionProcedure, ConditionProcedure);
+impl_access!(HashTable, HashTable);
+impl_access!(Map, Map);
+impl_access!(Record, Record);
+impl_access!(RecordConstructorDescriptor, RecordConstructorDescriptor);
+impl_access!(RecordProcedure, RecordProcedure);
+impl_access!(RecordTypeDef, RecordTypeDescriptor);
+impl_access!(Port, Port);
+impl_access!(Lambda, Lambda);
+impl_access!(CaseLambda, CaseLambda);
 impl_access!(UnitValue, Unit);
 impl_access!(String, Str);
 impl_access!(Symbol, Sym);
+impl_access!(MultipleValues, Values);
+impl_access!(VectorValue, Vector);
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::evaluation::{Context, Env};
+    use crate::syntax::Symbol;
+    use super::super::combiner::{Lambda, NativeFn};
+    use super::{MultipleValues, Term, TermAccess, TermValue, TryAccess, UnitValue, VectorValue};
+
+    #[test]
+    fn term_constructors() {
+        let int = Term::int(42);
+        assert!(int.has_value() && !int.is_branch());
+        assert_eq!((&int as &dyn TermAccess<i64>).try_access().unwrap(), &42);
+        assert!((&int as &dyn TermAccess<bool>).try_access().is_err());
+
+        let boolean = Term::boolean(false);
+        assert!(boolean.is_false());
+        assert_eq!((&boolean as &dyn TermAccess<bool>).try_access().unwrap(), &false);
+
+        let string = Term::string("text");
+        assert_eq!(TryAccess::<String>::try_access(&string).unwrap(), "text");
+        assert!((&string as &dyn TermAccess<Symbol>).try_access().is_err());
+
+        let symbol = Term::symbol("gcd");
+        assert!(!symbol.has_value() && !symbol.is_branch());
+        assert_eq!(TryAccess::<Symbol>::try_access(&symbol).unwrap(), &Symbol::from("gcd"));
+
+        let list = Term::list(vec![Term::symbol("gcd"), Term::int(4), Term::int(6)]);
+        assert!(!list.has_value() && list.is_branch());
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.sub_terms.front(), Some(&symbol));
+        assert!(!Term::list(vec![]).is_branch());
+    }
+
+    #[test]
+    fn term_display() {
+        assert_eq!(Term::boolean(true).to_string(), "#t");
+        assert_eq!(Term::boolean(false).to_string(), "#f");
+        assert_eq!(Term::int(-42).to_string(), "-42");
+        assert_eq!(Term::from(1.0).to_string(), "1.0");
+        assert_eq!(Term::from(f64::NEG_INFINITY).to_string(), "-inf.0");
+        assert_eq!(Term::string("say \"hi\"\n").to_string(), r#""say \"hi\"\n""#);
+        assert_eq!(Term::symbol("gcd").to_string(), "gcd");
+        assert_eq!(Term::nil().to_string(), "()");
+        assert_eq!(Term::from(UnitValue::Ignore).to_string(), "#<unspecified>");
+        assert_eq!(Term::from(NativeFn::new(|_: &mut Context, _: &[Term]| Ok(Term::nil()))).to_string(), "#<builtin>");
+        let env = Rc::new(RefCell::new(Env::new()));
+        let formals = Term::list(vec![Term::symbol("a"), Term::symbol("b")]);
+        assert_eq!(Term::from(Lambda::new(&formals, vec![Term::symbol("a")], env).unwrap()).to_string(), "#<lambda (a b)>");
+        assert_eq!(Term::from(MultipleValues(vec![Term::int(1), Term::string("a")])).to_string(), r#"1 "a""#);
+        assert_eq!(TermValue::Sym(Symbol::from("x")).to_string(), "x");
+
+        assert_eq!(Term::cons(Term::int(1), Term::int(2)).to_string(), "(1 . 2)");
+        let list = Term::cons(Term::int(1), Term::cons(Term::nil(), Term::cons(Term::int(3), Term::nil())));
+        assert_eq!(list.to_string(), "(1 () 3)");
+        let nested = Term::cons(list.clone(), Term::cons(Term::cons(list, Term::int(4)), Term::nil()));
+        assert_eq!(nested.to_string(), "((1 () 3) ((1 () 3) . 4))");
+
+        let branch = Term::list(vec![Term::symbol("gcd"), Term::list(vec![Term::symbol("f"), Term::int(4)]), Term::int(6)]);
+        assert_eq!(branch.to_string(), "(gcd (f 4) 6)");
+
+        // A deep list is written without recursing on it.
+        let deep = (0..100_000).fold(Term::nil(), |list, _| Term::cons(list, Term::nil()));
+        let text = deep.to_string();
+        assert!(text.starts_with("((((") && text.len() == 200_002);
+        drop(deep);
+    }
+
+    #[test]
+    fn display_cycles() {
+        // A cycle through the cdrs, and one through a car.
+        let list = Term::cons(Term::int(1), Term::cons(Term::int(2), Term::nil()));
+        let TermValue::Pair(ref pair) = list.value else { unreachable!() };
+        let TermValue::Pair(second) = pair.borrow().1.value.clone() else { unreachable!() };
+        second.borrow_mut().1 = list.clone();
+        assert_eq!(list.to_string(), "(1 2 . ...)");
+        pair.borrow_mut().0 = list.clone();
+        assert_eq!(list.to_string(), "(... 2 . ...)");
+
+        // A value shared without a cycle is written in full each time.
+        let shared = Term::cons(Term::int(1), Term::nil());
+        let twice = Term::cons(shared.clone(), Term::cons(shared, Term::nil()));
+        assert_eq!(twice.to_string(), "((1) (1))");
+        // Break the cycles, so that the pairs are dropped.
+        pair.borrow_mut().0 = Term::nil();
+        second.borrow_mut().1 = Term::nil();
+    }
+
+    #[test]
+    fn drop_long_lists_and_vectors() {
+        // Neither a long list nor one nested through its cars overflows the stack when dropped.
+        let long = (0..1_000_000).fold(Term::nil(), |list, n| Term::cons(Term::int(n), list));
+        drop(long);
+        let deep = (0..1_000_000).fold(Term::nil(), |list, _| Term::cons(list, Term::nil()));
+        drop(deep);
+        // Nor do nested vectors, or lists and vectors nested in each other.
+        let deep = (0..1_000_000).fold(Term::nil(), |vector, _| Term::from(VectorValue::new(vec![vector])));
+        drop(deep);
+        let deep = (0..1_000_000).fold(Term::nil(), |term, n| match n % 2 {
+            0 => Term::from(VectorValue::new(vec![term])),
+            _ => Term::cons(term, Term::nil())
+        });
+        drop(deep);
+
+        // A pair still shared is left as it is.
+        let shared = Term::cons(Term::int(1), Term::cons(Term::int(2), Term::nil()));
+        let tail = match shared.value { TermValue::Pair(ref pair) => pair.borrow().1.clone(), _ => unreachable!() };
+        drop(shared);
+        assert_eq!(tail, Term::cons(Term::int(2), Term::nil()));
+    }
+}
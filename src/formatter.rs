@@ -0,0 +1,122 @@
+use crate::syntax::Node;
+
+/// A style of writing the abstract syntax tree of a script, for the `ast` target.
+pub trait AstFormatter {
+    /// Format the tree of a script, which is the list of its top-level forms.
+    fn format(&self, tree: &Node) -> String;
+}
+
+/// The whole tree on a single line, as the literals were written.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactFormatter;
+
+impl AstFormatter for CompactFormatter {
+    fn format(&self, tree: &Node) -> String {
+        tree.to_string()
+    }
+}
+
+/// Each top-level form on its own lines, breaking the lists which don't fit in the width.
+#[derive(Debug, Clone, Copy)]
+pub struct PrettyFormatter {
+    pub width: usize
+}
+
+impl PrettyFormatter {
+    pub const DEFAULT_WIDTH: usize = 80;
+
+    /// A list that doesn't fit is broken after its head, with the rest of its elements
+    /// on lines of their own, indented under it.
+    fn write_node(&self, out: &mut String, node: &Node, column: usize) {
+        let flat = node.to_string();
+        match node {
+            Node::List(nodes, bracket) if column + flat.chars().count() > self.width && nodes.len() > 1 => {
+                out.push(bracket.open());
+                self.write_node(out, &nodes[0], column + 1);
+                for node in &nodes[1..] {
+                    out.push('\n');
+                    out.push_str(&" ".repeat(column + 2));
+                    self.write_node(out, node, column + 2);
+                }
+                out.push(bracket.close());
+            }
+            _ => out.push_str(&flat)
+        }
+    }
+}
+
+impl Default for PrettyFormatter {
+    fn default() -> Self {
+        Self { width: Self::DEFAULT_WIDTH }
+    }
+}
+
+impl AstFormatter for PrettyFormatter {
+    fn format(&self, tree: &Node) -> String {
+        let mut out = String::new();
+        for form in tree.as_ref() {
+            self.write_node(&mut out, form, 0);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Each top-level form on a line of its own, with every literal written in a single way,
+/// so that the same program is always formatted to the same text, as by `Node::to_sexpr`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CanonicalFormatter;
+
+impl AstFormatter for CanonicalFormatter {
+    fn format(&self, tree: &Node) -> String {
+        let mut out = String::new();
+        for form in tree.as_ref() {
+            out.push_str(&form.to_sexpr());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{SrcInfo, SyntacticParser};
+    use crate::share;
+    use crate::syntax::Node;
+    use super::{AstFormatter, CanonicalFormatter, CompactFormatter, PrettyFormatter};
+
+    fn parse(src: &str) -> Node {
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test", src)));
+        parser.try_parse().unwrap();
+        parser.tree()
+    }
+
+    #[test]
+    fn format_modes() {
+        let src = "($define (f x)   [gcd x 0x10])\n(display   'say \"hi\"')  (f 1.50)";
+        let tree = parse(src);
+
+        assert_eq!(CompactFormatter.format(&tree),
+            r#"(($define (f x) [gcd x 0x10]) (display 'say "hi"') (f 1.50))"#);
+        assert_eq!(PrettyFormatter { width: 20 }.format(&tree),
+            "($define\n  (f x)\n  [gcd x 0x10])\n(display 'say \"hi\"')\n(f 1.50)\n");
+        assert_eq!(PrettyFormatter::default().format(&tree),
+            "($define (f x) [gcd x 0x10])\n(display 'say \"hi\"')\n(f 1.50)\n");
+        let canonical = CanonicalFormatter.format(&tree);
+        assert_eq!(canonical, "($define (f x) (gcd x 16))\n(display \"say \\\"hi\\\"\")\n(f 1.5)\n");
+
+        // The canonical form is the same on every run, and for every spelling of the program.
+        assert_eq!(CanonicalFormatter.format(&parse(src)), canonical);
+        let respelled = parse("($define [f x] (gcd x 16)) (display 'say \"hi\"') (f 15e-1)");
+        assert_eq!(CanonicalFormatter.format(&respelled), canonical);
+        assert_eq!(CanonicalFormatter.format(&parse(&canonical)), canonical);
+
+        // A line break in a string is escaped, so that each form still takes a single line.
+        let multiline = parse("(display \"a\nb\\\\c\") (display 'it''s')");
+        let canonical = CanonicalFormatter.format(&multiline);
+        assert_eq!(canonical, "(display \"a\\nb\\\\c\")\n(display \"it\\'\\'s\")\n");
+        let reparsed = parse(&canonical);
+        assert_eq!(CanonicalFormatter.format(&reparsed), canonical);
+        assert!(matches!(&reparsed.as_ref()[0].as_ref()[1], Node::String { value, .. } if value == "a\nb\\c"));
+    }
+}
@@ -1,63 +1,547 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::rc::Rc;
 
-use ariadne::Source;
-
+use crate::error::{Error, ErrorKind, ReportStyle};
 use crate::parser::*;
-use crate::evaluation::Context;
+use crate::evaluation::{Context, Sink, Source, Term, TermValue, UnitValue};
+use crate::line_editor::{line_editor, LineEditor};
+use crate::syntax::{Node, NodeSpan, SymbolCase};
+
+/// The meta-commands of the REPL, with their descriptions.
+const META_COMMANDS: &[(&str, &str)] = &[
+    (":help", "List the meta-commands."),
+    (":load file", "Evaluate the file."),
+    (":env", "List the bindings made since the start, or the last reset."),
+    (":trace name", "Write every application of the combiner bound to the name."),
+    (":expand expr", "Show the expression with all its macros expanded."),
+    (":reset", "Reset the environment to its initial state."),
+    (":history", "List the lines entered so far."),
+    (":time on|off", "Write how long each form took to evaluate, or stop writing it."),
+];
 
 #[derive(Debug)]
 pub struct Interpreter {
     interactive: bool,
     root_ctx: Context,
-    src: Rc<RefCell<SrcInfo>>
+    src: Rc<RefCell<SrcInfo>>,
+    report_style: ReportStyle,
+    history: Vec<String>,
+    /// Whether the duration of evaluating each form is written after its value, as set by `:time`.
+    timing: bool,
+    /// Whether the reader of the output has gone away, like `head` in a pipe, so that
+    /// the REPL should stop rather than keep writing into nothing.
+    output_closed: Cell<bool>
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         let src_info = SrcInfo::new("", "");
         let rc = Rc::new(RefCell::new(src_info));
-        Self { interactive: true, root_ctx: Context::new(rc.clone()), src: rc.clone() }
+        Self {
+            interactive: true,
+            root_ctx: Context::new(rc.clone()),
+            src: rc.clone(),
+            report_style: ReportStyle::default(),
+            history: vec![],
+            timing: false,
+            output_closed: Cell::new(false)
+        }
+    }
+
+    pub fn with_output(mut self, output: Sink) -> Self {
+        self.root_ctx = self.root_ctx.with_output(output);
+        self
+    }
+
+    /// Redirect the input and output of the evaluation, which default to stdin and stdout,
+    /// as for an application embedding the interpreter.
+    pub fn with_io<R: Read + 'static, W: Write + 'static>(mut self, input: R, output: W) -> Self {
+        self.root_ctx = self.root_ctx.with_input(Source::new(BufReader::new(input))).with_output(Sink::new(output));
+        self
+    }
+
+    /// Warn about the bindings which shadow outer ones, before evaluating each source.
+    pub fn with_shadowing_warnings(mut self, enabled: bool) -> Self {
+        self.root_ctx = self.root_ctx.with_shadowing_warnings(enabled);
+        self
+    }
+
+    /// Search the directories in order for the files loaded by a relative path.
+    pub fn with_include_paths(mut self, paths: Vec<std::path::PathBuf>) -> Self {
+        self.root_ctx = self.root_ctx.with_include_paths(paths);
+        self
+    }
+
+    /// Fold the symbols read to lowercase, or keep them apart by their case, which is the default.
+    pub fn with_symbol_case(mut self, symbol_case: SymbolCase) -> Self {
+        self.root_ctx = self.root_ctx.with_symbol_case(symbol_case);
+        self
+    }
+
+    pub fn with_report_style(mut self, style: ReportStyle) -> Self {
+        self.report_style = style;
+        self
     }
 
     pub fn read(&mut self, unit: &mut String) {
-        let mut parser = SyntacticParser::new(self.src.clone());
         self.src.borrow_mut().text = core::mem::take(unit);
-        let _ = parser.try_parse().is_err_and(|err| {
-            err.report
-                .unwrap()
-                .finish()
-                .print((self.src.borrow().id.clone(), Source::from(&self.src.borrow().text)))
-                .unwrap();
-            true
-        });
-        let _ = self.root_ctx.eval(parser.reset().into())
-            .is_err_and(|err| {
-                err.report
-                    .unwrap()
-                    .finish()
-                    .print((self.src.borrow().id.clone(), Source::from(&self.src.borrow().text)))
-                    .unwrap();
+        if let Err(err) = self.eval_source() {
+            self.print_error(err);
             if !self.interactive { std::process::exit(1); }
-            false
-        });
+        }
+    }
+
+    /// Evaluate a program given inline, rather than from a file or the prompt.
+    pub fn eval_inline(&mut self, program: &str) -> Result<(), Error> {
+        self.interactive = false;
+        *self.src.borrow_mut() = SrcInfo::new("<eval>", program);
+        self.eval_source()
+    }
+
+    /// Evaluate a program for an embedder, returning the value of its last form or the first
+    /// error, without printing either. The bindings are kept for the following calls.
+    pub fn eval_str(&mut self, id: &str, src: &str) -> Result<Term, Error> {
+        *self.src.borrow_mut() = SrcInfo::new(id, src);
+        let forms = self.parse_source()?;
+        self.eval_forms(forms, |_, _, _| {})
+    }
+
+    /// Take the warnings of the programs given to `eval_str` since the last call.
+    pub fn take_warnings(&self) -> Vec<Error> {
+        self.root_ctx.take_warnings()
+    }
+
+    /// Parse the current source, and evaluate the top-level forms in order.
+    fn eval_source(&mut self) -> Result<(), Error> {
+        let forms = self.parse_source()?;
+        for warning in self.root_ctx.take_warnings() { self.print_error(warning) }
+        self.eval_forms(forms, |repl, value, elapsed| {
+            // The prompt echoes the value of each form, unless it has none.
+            if repl.interactive && !matches!(value.value, TermValue::Unit(_)) {
+                repl.write_output(format_args!("{value}\n"));
+            }
+            if repl.timing { repl.write_output(format_args!("Time: {elapsed:?}\n")) }
+        })?;
+        Ok(())
+    }
+
+    /// Parse the current source into its top-level forms, each with its span, and check them
+    /// for the warnings.
+    fn parse_source(&mut self) -> Result<Vec<(Node, NodeSpan)>, Error> {
+        let mut parser = SyntacticParser::new(self.src.clone()).with_symbol_case(self.root_ctx.symbol_case());
+        parser.try_parse()?;
+        self.root_ctx.check_source(&self.src.borrow());
+        let spans = parser.spans().to_vec();
+        Ok(parser.reset().as_ref().iter().cloned().zip(spans).collect())
+    }
+
+    /// Evaluate the forms in order, passing each value and how long it took to `each`,
+    /// and return the value of the last one.
+    fn eval_forms<F: FnMut(&Self, &Term, std::time::Duration)>(&mut self, forms: Vec<(Node, NodeSpan)>, mut each: F)
+        -> Result<Term, Error> {
+        let mut value = Term::from(UnitValue::Ignore);
+        for (form, span) in forms {
+            let node = self.root_ctx.macroexpand_all(form.clone())?;
+            let start = std::time::Instant::now();
+            value = self.root_ctx.eval_to_value(node.into())
+                .map_err(|err| self.locate_not_callable(err, &form, &span))?;
+            each(self, &value, start.elapsed());
+        }
+        Ok(value)
+    }
+
+    /// Point an error of applying a value which isn't a procedure at the head of the combination
+    /// which applied it, as the terms being evaluated don't know where they were written.
+    fn locate_not_callable(&self, err: Error, form: &Node, span: &NodeSpan) -> Error {
+        if err.kind() != ErrorKind::NotCallable || err.report.is_some() { return err }
+        // A head which can't be told from the source is taken to be the head of the form.
+        let head = self.not_callable_head(form, span)
+            .or_else(|| span.get(&[0]))
+            .unwrap_or(span).span.clone();
+        err.with_span(head.clone())
+            .return_error(&self.src.borrow(), (0, 0, head.start).into(), "This is not a procedure.".to_string())
+    }
+
+    /// The span of the head of the first combination to be applied in the node, whose head is
+    /// a literal or names a global value which isn't a procedure.
+    fn not_callable_head<'a>(&self, node: &Node, span: &'a NodeSpan) -> Option<&'a NodeSpan> {
+        let Node::List(list, _) = node else { return None };
+        // The operands are reduced before the combination is applied.
+        let inner = list.iter().zip(&span.children)
+            .find_map(|(child, child_span)| self.not_callable_head(child, child_span));
+        if inner.is_some() { return inner }
+        let callable = match list.first()? {
+            Node::Symbol(name) => self.root_ctx.env.borrow().lookup(name.as_ref())
+                .is_none_or(|bound| bound.value.type_name() == "procedure"),
+            Node::List(sub_list, _) => !sub_list.is_empty(),
+            _ => false
+        };
+        if callable { None } else { span.children.first() }
+    }
+
+    /// Whether the reader of the output has gone away, as found when writing a prompt, a value or a report.
+    pub fn is_output_closed(&self) -> bool {
+        self.output_closed.get()
+    }
+
+    /// Print the report of an error, or of a warning.
+    pub fn print_error(&self, err: Error) {
+        // Errors raised during evaluation may not carry a report yet, so they are reported
+        // at the start of the source.
+        let written = err.write_report(&self.src.borrow(), &self.report_style, self.root_ctx.output().clone());
+        self.check_written(written);
     }
 
-    // TODO: Add history
     pub fn run_interactive(&mut self) -> ! {
-        use std::io::{*, Write};
+        match line_editor() {
+            Some(editor) => self.repl_editor(editor),
+            None => {
+                // The lines are read from the input of the evaluation, so that `read-line` shares it.
+                let input = self.root_ctx.input().clone();
+                self.repl_lines(std::iter::from_fn(|| input.read_line().transpose()))
+            }
+        }
+        std::process::exit(0)
+    }
+
+    /// Read and evaluate the input line by line, until it ends or `exit` is entered.
+    pub fn repl<R: BufRead>(&mut self, input: R) {
+        self.repl_lines(input.lines())
+    }
+
+    fn repl_lines<I: Iterator<Item = std::io::Result<String>>>(&mut self, mut lines: I) {
+        self.repl_with(|repl, unit| {
+            let (prompt, indent) = prompt(unit);
+            repl.write_output(format_args!("{prompt}{indent}"));
+            if repl.output_closed.get() { return None }
+            lines.next()?.ok()
+        })
+    }
+
+    /// Read the lines by the editor, which highlights the matching delimiters and starts
+    /// the continuation lines with their indentation.
+    fn repl_editor(&mut self, mut editor: LineEditor) {
+        self.repl_with(|repl, unit| {
+            if repl.output_closed.get() { return None }
+            let (prompt, indent) = prompt(unit);
+            if let Some(helper) = editor.helper() { helper.set_unit(unit) }
+            let line = editor.readline_with_initial(prompt, (&indent, "")).ok()?;
+            let _ = editor.add_history_entry(line.as_str());
+            Some(line)
+        })
+    }
+
+    /// Evaluate the units made of the lines read, each given the lines of the unit so far.
+    fn repl_with<F: FnMut(&Self, &str) -> Option<String>>(&mut self, mut read_line: F) {
         self.interactive = true;
         self.src.borrow_mut().id = "<stdin>".to_string();
+        let mut unit = String::new();
         loop {
-            let mut line = String::new();
-            print!("> "); // Print prompt
-            stdout().flush().unwrap();
-            stdin().read_line(&mut line).unwrap();
-            line = line.trim().into();
+            let Some(line) = read_line(self, &unit) else { return };
+            if unit.is_empty() {
+                let line = line.trim();
+                if line.is_empty() { continue }
+                if line == "exit" { return }
+                if let Some(meta_command) = line.strip_prefix(':') {
+                    self.history.push(line.to_string());
+                    let (cmd, arg) = meta_command.split_once(char::is_whitespace).unwrap_or((meta_command, ""));
+                    self.handle_meta_command(cmd, arg.trim());
+                    continue
+                }
+            } else {
+                unit.push('\n');
+            }
+            unit.push_str(&line);
+            if LexicalParser::count_balance(&unit) > 0 { continue }
+
+            self.history.push(unit.trim().to_string());
+            self.read(&mut unit);
+        }
+    }
+
+    /// Run a REPL meta-command, which is entered as `:cmd arg`.
+    pub fn handle_meta_command(&mut self, cmd: &str, arg: &str) {
+        match (cmd, arg) {
+            ("help", _) => {
+                for (usage, description) in META_COMMANDS {
+                    self.write_output(format_args!("  {usage:<14} {description}\n"));
+                }
+            }
+            ("load", "") | ("trace", "") | ("expand", "") =>
+                self.write_output(format_args!("Error: ':{cmd}' requires an argument.\n")),
+            ("load", path) => {
+                let path = std::path::Path::new(path);
+                let resolved = self.root_ctx.resolve_path(path).unwrap_or_else(|_| path.to_path_buf());
+                // The source is only kept here to render the reports of the errors.
+                let src = SrcInfo::from_file(&resolved).unwrap_or_else(|_| SrcInfo::new(path.display().to_string(), String::new()));
+                let errors: Vec<Error> = self.root_ctx.eval_file_incremental(path).filter_map(Result::err).collect();
+                let src = core::mem::replace(&mut *self.src.borrow_mut(), src);
+                for err in self.root_ctx.take_warnings().into_iter().chain(errors) { self.print_error(err) }
+                *self.src.borrow_mut() = src;
+            }
+            ("env", _) => {
+                let changes = self.root_ctx.initial_env().diff(&self.root_ctx.env.borrow());
+                if changes.is_empty() { self.write_output(format_args!("No bindings.\n")) }
+                for (name, _, value) in changes {
+                    match value {
+                        Some(value) => self.write_output(format_args!("{name} = {value}\n")),
+                        None => self.write_output(format_args!("{name} (unbound)\n"))
+                    }
+                }
+            }
+            ("trace", name) => {
+                self.root_ctx.trace(name);
+                self.write_output(format_args!("Tracing '{name}'.\n"));
+            }
+            ("expand", expr) => {
+                self.src.borrow_mut().text = expr.to_string();
+                let mut parser = SyntacticParser::new(self.src.clone()).with_symbol_case(self.root_ctx.symbol_case());
+                let expanded = parser.try_parse().and_then(|_| {
+                    parser.reset().as_ref().iter()
+                        .map(|form| self.root_ctx.macroexpand_all(form.clone()))
+                        .collect::<Result<Vec<Node>, Error>>()
+                });
+                match expanded {
+                    Ok(forms) => for form in forms { self.write_output(format_args!("{form}\n")) },
+                    Err(err) => self.print_error(err)
+                }
+            }
+            ("reset", _) => {
+                // The settings the interpreter was made with, like the include paths, are kept.
+                self.root_ctx = self.root_ctx.reset();
+                self.write_output(format_args!("The environment has been reset.\n"));
+            }
+            ("time", "on") | ("time", "off") => {
+                self.timing = arg == "on";
+                self.write_output(format_args!("Timing is {arg}.\n"));
+            }
+            ("time", _) => self.write_output(format_args!("Error: ':time' expects 'on' or 'off'.\n")),
+            ("history", _) => {
+                for (i, line) in self.history.iter().enumerate() {
+                    self.write_output(format_args!("{:>4}  {line}\n", i + 1));
+                }
+            }
+            _ => self.write_output(format_args!(
+                "Error: Unknown meta-command ':{cmd}'. Enter ':help' for the available ones.\n"))
+        }
+    }
+
+    fn write_output(&self, args: std::fmt::Arguments) {
+        let mut output = self.root_ctx.output().clone();
+        self.check_written(output.write_fmt(args).and_then(|_| output.flush()));
+    }
+
+    /// Other failures to write are ignored, as there is nowhere left to report them.
+    fn check_written(&self, result: std::io::Result<()>) {
+        if result.is_err_and(|err| err.kind() == std::io::ErrorKind::BrokenPipe) {
+            self.output_closed.set(true)
+        }
+    }
+}
+
+/// The prompt of the next line, and its indentation. A unit whose delimiters are still open
+/// continues on the next line, which is indented as deep as the delimiters left open.
+fn prompt(unit: &str) -> (&'static str, String) {
+    if unit.is_empty() { return ("> ", String::new()) }
+    (".. ", continuation_indent(LexicalParser::count_balance(unit)))
+}
+
+/// The indentation of a continuation line, by the depth of the delimiters left open.
+pub fn continuation_indent(depth: i32) -> String {
+    "  ".repeat(depth.max(0) as usize)
+}
 
-            if line == "exit" { std::process::exit(0) }
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::{Sink, Term, UnitValue};
+    use crate::test_support::{Buffer, ClosedPipe};
+    use super::Interpreter;
 
-            self.read(&mut line);
+    /// Run the REPL over the input, returning everything written to the output.
+    fn repl(input: &str) -> String {
+        let buffer = Buffer::default();
+        let mut instance = Interpreter::new().with_output(Sink::new(buffer.clone()));
+        instance.repl(input.as_bytes());
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        output
+    }
+
+    #[test]
+    fn repl_meta_commands() {
+        let help = repl(":help");
+        for usage in [":help", ":load file", ":env", ":trace name", ":expand expr", ":reset", ":history", ":time on|off"] {
+            assert!(help.contains(usage), "'{usage}' is not listed.");
         }
+
+        assert!(repl(":env").contains("No bindings."));
+        assert!(repl("($define x 42)\n:env").contains("x = 42"));
+
+        let trace = repl(":trace gcd\n(gcd 12 (gcd 18 27))");
+        assert!(trace.contains("Tracing 'gcd'."));
+        assert!(trace.contains("[trace] (gcd 18 27)\n[trace] gcd => 9"));
+        assert!(trace.contains("[trace] (gcd 12 9)\n[trace] gcd => 3"));
+
+        assert!(repl(":expand (receive (a) 1 a)")
+            .contains("(call-with-values ($lambda () 1) ($lambda (a) a))"));
+
+        let reset = repl("($define x 1)\n:reset\n:env");
+        assert!(reset.contains("The environment has been reset.") && reset.ends_with("No bindings.\n> "));
+
+        // The include paths and the shadowing warnings are kept by the reset.
+        let dir = std::env::temp_dir().join(format!("thesis-reset-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("util.ths"), "($define util 42) ($define (f util) util)").unwrap();
+        let buffer = Buffer::default();
+        let mut instance = Interpreter::new().with_output(Sink::new(buffer.clone()))
+            .with_include_paths(vec![dir.clone()]).with_shadowing_warnings(true);
+        instance.repl("($define x 1)\n:reset\n:load util.ths\nutil".as_bytes());
+        std::fs::remove_dir_all(&dir).unwrap();
+        let output = String::from_utf8(buffer.0.take()).unwrap();
+        assert!(output.contains("This 'util' hides it.") && output.ends_with("> 42\n> "), "{output}");
+
+        assert!(repl("(gcd 4 6)\n\n:history").contains("   1  (gcd 4 6)\n   2  :history"));
+        assert!(repl(":frobnicate").contains("Unknown meta-command ':frobnicate'"));
+        assert!(repl(":load").contains("':load' requires an argument."));
+        assert!(repl("exit\n:help").ends_with("> "));
+
+        assert!(repl("(gcd 12 18)\n(display 1)\n").starts_with("> 6\n> 1> "));
+        let multiline = repl("($define (f x)\n  (gcd x\n 6))\n(display (f 4))");
+        assert!(multiline.starts_with("> ..   ..     > 2"));
+        assert!(repl("($define x 1)\n:history").contains("   1  ($define x 1)"));
+    }
+
+    #[test]
+    fn repl_time() {
+        let time_lines = |output: &str| output.lines().filter(|line| line.contains("Time: ")).count();
+        assert_eq!(time_lines(&repl("(gcd 12 18)\n($define x 1)")), 0);
+
+        let output = repl(":time on\n(gcd 12 18)\n($define x 1)\n(gcd x 2))\n:time off\n(gcd 4 6)");
+        assert!(output.contains("Timing is on.") && output.contains("Timing is off."));
+        // Each form evaluated while it's on is timed, with a value or not, but not a source which fails to parse.
+        assert!(output.contains("> 6\nTime: "));
+        assert_eq!(time_lines(&output), 2);
+        assert!(output.ends_with("> 2\n> "));
+        assert!(repl(":time").contains("':time' expects 'on' or 'off'."));
+    }
+
+    #[test]
+    fn interpreter_with_io() {
+        let buffer = Buffer::default();
+        let input = std::io::Cursor::new("Ada\r\nLovelace\n".as_bytes());
+        let mut instance = Interpreter::new().with_io(input, buffer.clone());
+        instance.eval_inline(r#"
+            (display (read-line))
+            (display " ")
+            (display (read-line (current-input-port)) (current-output-port))
+            (display (eof-object? (read-line)))
+            (display (input-port? (current-input-port)))
+            (display (output-port? (current-input-port)))"#).unwrap();
+        assert_eq!(String::from_utf8(buffer.0.borrow().clone()).unwrap(), "Ada Lovelace#t#t#f");
+        let err = instance.eval_inline("(read-line (current-output-port))").unwrap_err();
+        assert_eq!(err.message(), "'read-line' expects an input port.");
+    }
+
+    #[test]
+    fn continuation_indent() {
+        assert_eq!(super::continuation_indent(0), "");
+        assert_eq!(super::continuation_indent(1), "  ");
+        assert_eq!(super::continuation_indent(3), "      ");
+        assert_eq!(super::continuation_indent(-1), "");
+    }
+
+    #[test]
+    fn repl_load() {
+        let path = std::env::temp_dir().join(format!("thesis-load-{}.ths", std::process::id()));
+        std::fs::write(&path, "($define loaded (gcd 12 18)) missing ($define after 1)").unwrap();
+        let output = repl(&format!(":load {}\n:env", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(output.contains("loaded = 6") && output.contains("after = 1"));
+        assert!(output.contains("Failed to resolve 'missing'."));
+        assert!(repl(":load /nonexistent/thesis.ths").contains("Failed to read"));
+    }
+
+    #[test]
+    fn interpreter_shadowing_warnings() {
+        let program = "($define x 1) (display (($lambda (x) x) 2))";
+        let run = |enabled: bool| {
+            let buffer = Buffer::default();
+            let mut instance = Interpreter::new().with_io(std::io::empty(), buffer.clone())
+                .with_shadowing_warnings(enabled);
+            instance.eval_inline(program).unwrap();
+            String::from_utf8(buffer.0.take()).unwrap()
+        };
+        let warned = run(true);
+        assert_eq!(warned.matches("[W01]").count(), 1);
+        assert!(warned.contains("The outer 'x' is bound here.") && warned.ends_with('2'));
+        assert_eq!(run(false), "2");
+    }
+
+    #[test]
+    fn interpreter_eval_str() {
+        let buffer = Buffer::default();
+        let mut instance = Interpreter::new().with_io(std::io::empty(), buffer.clone());
+        assert_eq!(instance.eval_str("first", "($define x 20)").unwrap(), Term::from(UnitValue::Ignore));
+        assert_eq!(instance.eval_str("second", "($define y 12) (gcd x y)").unwrap(), Term::int(4));
+        let err = instance.eval_str("third", "(car x)").unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::TypeMismatch);
+        assert!(instance.eval_str("fourth", "(gcd x").is_err());
+        assert_eq!(instance.eval_str("fifth", "").unwrap(), Term::from(UnitValue::Ignore));
+        // Neither the values nor the errors are printed.
+        assert!(buffer.0.borrow().is_empty());
+    }
+
+    #[test]
+    fn interpreter_not_callable_span() {
+        let mut instance = Interpreter::new().with_io(std::io::empty(), Buffer::default());
+        let span = |instance: &mut Interpreter, src: &str| instance.eval_str("test", src).unwrap_err().span().clone();
+        // The error points at the head of the combination which was applied.
+        assert_eq!(span(&mut instance, "(5 1 2)"), 1..2);
+        assert_eq!(span(&mut instance, "(display 1) (list 1 (\"f\" 2))"), 21..24);
+        assert_eq!(span(&mut instance, "($define x 5) (display (x 1))"), 24..25);
+        // A head which is only known while evaluating is taken to be the head of the form.
+        assert_eq!(span(&mut instance, "(($lambda (f) (f)) 1)"), 1..18);
+    }
+
+    #[test]
+    fn interpreter_symbol_case() {
+        use crate::syntax::SymbolCase;
+        let buffer = Buffer::default();
+        let mut instance = Interpreter::new().with_io(std::io::empty(), buffer.clone())
+            .with_symbol_case(SymbolCase::Insensitive);
+        // The bindings are the same by any case, and so are the macros and special forms.
+        assert_eq!(instance.eval_str("first", "($DEFINE (Square X) (* x X)) (square 3)").unwrap(), Term::int(9));
+        assert_eq!(instance.eval_str("second", "(LET ((Answer 42)) ANSWER)").unwrap(), Term::int(42));
+        // The symbols are still written as they were spelled.
+        let symbols = instance.eval_str("third", r#"(read "(Foo foo FOO)")"#).unwrap();
+        assert_eq!(symbols.to_string(), "(Foo foo FOO)");
+        assert_eq!(instance.eval_str("fourth", r#"(eq? (car (read "Foo")) (car (read "foo")))"#).unwrap_err().kind(),
+            crate::error::ErrorKind::TypeMismatch);
+        assert_eq!(instance.eval_str("fifth", r#"(eq? (read "Foo") (read "foo"))"#).unwrap(), Term::boolean(true));
+        assert_eq!(instance.eval_str("sixth", "Missing").unwrap_err().message(), "Failed to resolve 'Missing'.");
+        instance.eval_inline("(Display (READ \"Mixed\"))").unwrap();
+        assert_eq!(String::from_utf8(buffer.0.take()).unwrap(), "Mixed");
+
+        // Without the policy, the case tells symbols apart.
+        let mut instance = Interpreter::new().with_io(std::io::empty(), Buffer::default());
+        let err = instance.eval_str("first", "($define Foo 1) foo").unwrap_err();
+        assert_eq!(err.message(), "Failed to resolve 'foo'.");
+        assert_eq!(instance.eval_str("second", r#"(eq? (read "Foo") (read "foo"))"#).unwrap(), Term::boolean(false));
+    }
+
+    #[test]
+    fn repl_broken_pipe() {
+        // The REPL stops at the first prompt, instead of reading the rest of the input.
+        let input = "(display 1)\n".repeat(100);
+        let mut instance = Interpreter::new().with_output(Sink::new(ClosedPipe(std::io::ErrorKind::BrokenPipe)));
+        instance.repl(input.as_bytes());
+        assert!(instance.history.is_empty());
+        assert!(instance.output_closed.get());
     }
 }
@@ -0,0 +1,26 @@
+//! The interpreter of Thesis, for an application to embed, and for the `thesis` command to run.
+//!
+//! An [`Interpreter`] evaluates programs given as strings by [`Interpreter::eval_str`], keeping
+//! its bindings from one program to the next.
+//!
+//! ```
+//! let mut interpreter = thesis_interpreter::Interpreter::new();
+//! interpreter.eval_str("<setup>", "($define x 6)").unwrap();
+//! assert_eq!(interpreter.eval_str("<main>", "(* x 7)").unwrap().to_string(), "42");
+//! ```
+
+pub mod command;
+pub mod error;
+pub mod formatter;
+mod macros;
+pub mod parser;
+pub mod syntax;
+pub mod evaluation;
+pub mod interpreter;
+mod line_editor;
+#[cfg(test)]
+mod test_support;
+
+pub use error::{Error, ErrorKind};
+pub use evaluation::Term;
+pub use interpreter::Interpreter;
@@ -0,0 +1,104 @@
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::io::IsTerminal;
+
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+
+use crate::parser::matching_opener;
+
+pub type LineEditor = Editor<ReplHelper, DefaultHistory>;
+
+/// A line editor for the prompt, or `None` if the input or the output isn't a terminal, or
+/// the terminal can't be set up for editing, so that the REPL reads plain lines instead.
+pub fn line_editor() -> Option<LineEditor> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() { return None }
+    let mut editor = Editor::new().ok()?;
+    editor.set_helper(Some(ReplHelper::default()));
+    Some(editor)
+}
+
+/// Highlights the opener matching the closer at the cursor, while a line is edited.
+#[derive(Default)]
+pub struct ReplHelper {
+    /// The lines entered before the edited one, whose delimiters are still open.
+    unit: RefCell<String>,
+    /// The byte offset in the edited line of the opener highlighted.
+    opener: Cell<Option<usize>>
+}
+
+impl ReplHelper {
+    pub fn set_unit(&self, unit: &str) {
+        *self.unit.borrow_mut() = unit.to_string()
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        match self.opener.get().filter(|&i| line.is_char_boundary(i + 1)) {
+            Some(i) => Cow::Owned(format!("{}\x1b[1;7m{}\x1b[0m{}", &line[..i], &line[i..i + 1], &line[i + 1..])),
+            None => Cow::Borrowed(line)
+        }
+    }
+
+    fn highlight_char(&self, line: &str, pos: usize, forced: bool) -> bool {
+        // The highlight is cleared once the line is entered.
+        if forced { return self.opener.take().is_some() }
+        self.opener.set(opener_to_highlight(&self.unit.borrow(), line, pos));
+        self.opener.get().is_some()
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// The byte offset in the edited line of the opener matching the closer at the cursor, or else
+/// the one just before it, where the line continues the unit.
+///
+/// The opener is only found in the edited line, but the unit is read with it so that
+/// a string left open on an earlier line is taken into account.
+pub fn opener_to_highlight(unit: &str, line: &str, pos: usize) -> Option<usize> {
+    let prefix = if unit.is_empty() { String::new() } else { format!("{unit}\n") };
+    let offset = prefix.chars().count();
+    let cursor = line[..pos].chars().count();
+    let closer = [Some(cursor), cursor.checked_sub(1)].into_iter().flatten()
+        .find(|&i| matches!(line.chars().nth(i), Some(')' | ']' | '}')))?;
+    let opener = matching_opener(&(prefix + line), offset + closer)?.checked_sub(offset)?;
+    line.char_indices().nth(opener).map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::opener_to_highlight;
+
+    #[test]
+    fn opener_to_highlight_at_cursor() {
+        // On the closer, or just after it.
+        assert_eq!(opener_to_highlight("", "(a (b) c)", 5), Some(3));
+        assert_eq!(opener_to_highlight("", "(a (b) c)", 6), Some(3));
+        assert_eq!(opener_to_highlight("", "(a (b) c)", 9), Some(0));
+        assert_eq!(opener_to_highlight("", "(a (b) c)", 2), None);
+        assert_eq!(opener_to_highlight("", "(a]", 3), None);
+        // The offset is in bytes, past the characters of several bytes.
+        assert_eq!(opener_to_highlight("", "(\"λ\" [x])", 8), Some(6));
+        // An opener on an earlier line isn't in the edited one.
+        assert_eq!(opener_to_highlight("($define (f x)", "  (g x))", 8), None);
+        assert_eq!(opener_to_highlight("($define (f x)", "  (g x))", 6), Some(2));
+        // The closer is in a string left open on an earlier line.
+        assert_eq!(opener_to_highlight("(display \"(", "a)\" (b))", 2), None);
+        assert_eq!(opener_to_highlight("(display \"(", "a)\" (b))", 6), Some(4));
+    }
+}
@@ -1,12 +1,27 @@
-mod command;
-mod error;
-mod macros;
-mod parser;
-mod syntax;
-mod evaluation;
-mod interpreter;
+use thesis_interpreter::{command, error, formatter, interpreter, parser, syntax};
+use thesis_interpreter::{if_or, seq, share};
+
+#[cfg(test)]
+mod test_support;
+
+/// Separates the directories given together to '--include', as in the PATH variable.
+const INCLUDE_PATH_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
 
 fn main() {
+    let app = command();
+    let args: Vec<String> = std::env::args().collect();
+    let map = match app.match_with(args[1..].to_vec()) {
+        Ok(map) => map,
+        Err(err) => seq!(app.print_error(&args[1..], err), return)
+    };
+    if let Err(err) = dispatch(&app, &map, std::io::stdout()) {
+        eprintln!("{}", err.message());
+        std::process::exit(1)
+    }
+}
+
+/// The command line of the interpreter.
+fn command() -> command::Command {
     use command::*;
     let mut app = Command::new("thesis", 
 r#"The prototype of Thesis interpreter."#);
@@ -28,67 +43,342 @@ r#"The prototype of Thesis interpreter."#);
     );
     app.add_arg(
         Arg::new("--target")
-            .parameterize(Parameter::Optional("\"ast\""))
-            .description("Specify the output target.")
+            .parameterize(Parameter::Optional("ast"))
+            .choices(&["ast", "json"])
+            .separated_by(',')
+            .description("Specify the output targets, separated by commas.")
+            .details(
+r#"The supported output targets are listed here. With several targets, '--output' names a directory.
+      - "ast": Output as a desugared abstract syntax tree (in list form).
+      - "json": Output the abstract syntax tree as JSON."#)
+    );
+    app.add_arg(
+        Arg::new("--ast-format")
+            .parameterize(Parameter::Optional("compact"))
+            .choices(&["compact", "pretty", "canonical"])
+            .description("Specify the style of the \"ast\" target.")
+            .details(
+r#"- "compact": Output the whole tree on a single line, as it was written.
+      - "pretty": Output each form indented on its own lines, within '--width' columns.
+      - "canonical": Output each form on a line in a single spelling, suitable for diffing."#)
+    );
+    app.add_arg(
+        Arg::new("--width")
+            .parameterize(Parameter::Optional("80"))
+            .description("Specify the line width of the \"pretty\" AST format.")
+    );
+    app.add_arg(
+        Arg::new("--eval")
+            .short_id('e')
+            .parameterize(Parameter::Required)
+            .description("Evaluate the given program instead of a script.")
+    );
+    app.add_arg(
+        Arg::new("--fold-case")
+            .description("Fold the symbols of the evaluated programs to lowercase, so that 'Foo' and 'foo' are the same.")
+    );
+    app.add_arg(
+        Arg::new("--include")
+            .short_id('I')
+            .parameterize(Parameter::Required)
+            .separated_by(INCLUDE_PATH_SEPARATOR)
+            .repeatable()
+            .description("Add a directory to search for the files loaded by a relative path.")
             .details(
-r#"The supported output targets are listed here. Note that only a work in progress target is support currently.
-      - "ast": Output as a desugared abstract syntax tree (in list form)."#)
+r#"The directories are searched in the order given, and then the current directory.
+      It may be repeated, or given several directories separated like in the PATH variable."#)
     );
     app.add_arg(
         Arg::new("script")
             .parameterize(Parameter::Optional("-")));
-    let args: Vec<String> = std::env::args().into_iter().collect();
-    let map = match app.match_with(args[1..].to_vec()) {
-        Ok(map) => map,
-        Err(err) => seq!(println!("{}", err), return)
-    };
-    
-    for (key, val) in &map {
-        match key.as_str() {
-            "help" => seq!(app.print_help(), break),
-            "version" => seq!(println!(env!("CARGO_PKG_VERSION")), break),
-            // In the future, the implementation will only
-            // evaluate the script without specifying '--output'.
-            "script" => {
-                if map.get("script").unwrap() == "-" {
-                    run_loop()
-                } else {
-                    execute_script(val, map.get("output")).unwrap()
-                }
-            },
-            "target" => match map.get("target").unwrap().as_str() {
-                "ast" => continue,
-                _ => panic!()
-            },
-            _ => {}
+    app
+}
+
+/// Act on the matches of the command line in a fixed order, whatever order they were given in:
+/// the interrupts first, then '--eval', then the targets and the output, and at last the script,
+/// which is run once.
+fn dispatch<W: std::io::Write>(app: &command::Command, map: &command::Matches, mut stdout: W)
+    -> Result<(), error::Error> {
+    use error::{Error, ErrorKind};
+    if map.contains_key("help") { return seq!(app.print_help(), Ok(())) }
+    if map.contains_key("version") {
+        return writeln!(stdout, env!("CARGO_PKG_VERSION")).map_err(|err| Error::new(ErrorKind::IoError)
+            .with_message(format!("Failed to write the output: {err}")))
+    }
+
+    let include = app.arg("--include").unwrap();
+    let include_paths: Vec<std::path::PathBuf> = map.get("include")
+        .map_or_else(Vec::new, |dirs| include.split_parameter(dirs).into_iter().map(Into::into).collect());
+    let symbol_case = if_or!(map.contains_key("fold-case"), syntax::SymbolCase::Insensitive, syntax::SymbolCase::Sensitive);
+    let script = &map["script"];
+    if let Some(program) = map.get("eval") {
+        if script != "-" {
+            return Err(Error::new(ErrorKind::InvalidArgument)
+                .with_message("'--eval' cannot be used together with a script.".to_string()))
         }
+        let mut instance = interpreter::Interpreter::new().with_include_paths(include_paths).with_symbol_case(symbol_case);
+        if let Err(err) = run_inline(&mut instance, program) {
+            instance.print_error(err);
+            // As with the script output, the reader of the output going away isn't a failure.
+            if !instance.is_output_closed() {
+                return Err(Error::new(ErrorKind::CommandFailed)
+                    .with_message("The program given to '--eval' failed.".to_string()))
+            }
+        }
+        return Ok(())
     }
+
+    // The script is written as the "ast" target unless '--target' says otherwise.
+    let targets = app.arg("--target").unwrap().split_parameter(map.get("target").map_or("ast", String::as_str));
+    let ast_format = map.get("ast-format").map_or("compact", String::as_str);
+    let width = map.get("width").map_or("80", String::as_str).parse().map_err(|_|
+        Error::new(ErrorKind::InvalidArgument).with_message("'--width' expects a number of columns.".to_string()))?;
+
+    if script == "-" { run_loop(include_paths, symbol_case) }
+    // In the future, the implementation will only
+    // evaluate the script without specifying '--output' or '--target'.
+    execute_script(script, &targets, map.get("output"), (ast_format, width), stdout)
 }
 
-fn run_loop() -> ! {
+fn run_loop(include_paths: Vec<std::path::PathBuf>, symbol_case: syntax::SymbolCase) -> ! {
     use interpreter::*;
-    let mut instance = Interpreter::new();
+    let mut instance = Interpreter::new().with_include_paths(include_paths).with_symbol_case(symbol_case);
     instance.run_interactive()
 }
 
-fn execute_script(path: &String, out: Option<&String>) -> Result<(), std::io::Error> {
-    use std::fs::*;
-    use std::io::Write;
+fn run_inline(instance: &mut interpreter::Interpreter, program: &str) -> Result<(), error::Error> {
+    instance.eval_inline(program)
+}
+
+/// The file extension of each output target, used when writing several targets into a directory.
+fn target_extension(target: &str) -> &'static str {
+    match target {
+        "ast" => "ast",
+        "json" => "json",
+        _ => unreachable!("The targets are checked by the command.")
+    }
+}
+
+fn render_target(target: &str, tree: &syntax::Node, formatter: &dyn formatter::AstFormatter) -> String {
+    match target {
+        "ast" => formatter.format(tree),
+        "json" => tree.to_json(),
+        _ => unreachable!("The targets are checked by the command.")
+    }
+}
+
+/// Write each target to `out`, which is a file for a single target and a directory otherwise,
+/// or to `stdout` one after another without `out`.
+///
+/// The `ast` target is written in the style named by `ast_format`, along with its line width.
+fn execute_script<W: std::io::Write>(path: &String, targets: &[&str], out: Option<&String>, ast_format: (&str, usize),
+    stdout: W) -> Result<(), error::Error> {
+    use formatter::*;
     use parser::*;
-    let input = std::fs::read(path);
-    let content = String::from_utf8(match input {
-        Ok(val) => val,
-        Err(err) => return Err(err),
-    }).unwrap_or_else(|err| {
-        panic!("{err}");
-    });
-    let mut parser = SyntacticParser::new(share!(SrcInfo::new(path, &content)));
-        parser.parse();
-    match out {
-        Some(out_path) => {
-            let mut file = File::create(out_path)?;
-            return write!(file, "{}", parser.tree())
-        },
-        None => Ok(())
+    let formatter: Box<dyn AstFormatter> = match ast_format {
+        ("pretty", width) => Box::new(PrettyFormatter { width }),
+        ("canonical", _) => Box::new(CanonicalFormatter),
+        _ => Box::new(CompactFormatter)
+    };
+    let content = read_script(path)?;
+    let src = share!(SrcInfo::new(path, &content));
+    let mut parser = SyntacticParser::new(src.clone());
+    if let Err(errors) = parser.try_parse_all() {
+        // Every malformed form is reported, rather than only the first one.
+        for err in &errors {
+            let mut report = vec![];
+            let _ = err.write_report(&src.borrow(), &error::ReportStyle::default(), &mut report);
+            eprint!("{}", String::from_utf8_lossy(&report));
+        }
+        return Err(error::Error::new(error::ErrorKind::InvalidSyntax)
+            .with_message(format!("'{path}' has {} syntax errors.", errors.len())))
+    }
+    let tree = parser.tree();
+    match write_targets(path, &tree, targets, out, formatter.as_ref(), stdout) {
+        // The reader of the output has gone away, like `head` in a pipe, which isn't a failure.
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|err| error::Error::new(error::ErrorKind::IoError)
+            .with_message(format!("Failed to write the output: {err}")))
+    }
+}
+
+fn read_script(path: &String) -> Result<String, error::Error> {
+    parser::SrcInfo::from_file(std::path::Path::new(path)).map(|src| src.text().to_string())
+}
+
+fn write_targets<W: std::io::Write>(path: &str, tree: &syntax::Node, targets: &[&str], out: Option<&String>,
+    formatter: &dyn formatter::AstFormatter, mut stdout: W) -> std::io::Result<()> {
+    use std::fs::*;
+    match (out, targets) {
+        (Some(out_path), [target]) => write(out_path, render_target(target, tree, formatter)),
+        (Some(out_dir), targets) => {
+            create_dir_all(out_dir)?;
+            let stem = std::path::Path::new(path).file_stem().unwrap_or_default();
+            for target in targets {
+                let file = std::path::Path::new(out_dir).join(stem).with_extension(target_extension(target));
+                write(file, render_target(target, tree, formatter))?;
+            }
+            Ok(())
+        }
+        (None, targets) => {
+            for target in targets {
+                let rendered = render_target(target, tree, formatter);
+                stdout.write_all(rendered.as_bytes())?;
+                if !rendered.ends_with('\n') { stdout.write_all(b"\n")? }
+            }
+            stdout.flush()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use thesis_interpreter::evaluation::Sink;
+    use thesis_interpreter::interpreter::Interpreter;
+    use crate::test_support::{Buffer, ClosedPipe};
+
+    #[test]
+    fn run_inline_program() {
+        let buffer = Buffer::default();
+        let mut instance = Interpreter::new().with_output(Sink::new(buffer.clone()));
+        super::run_inline(&mut instance, r#"(display "gcd=") (display (gcd 12 18))"#).unwrap();
+        assert_eq!(String::from_utf8(buffer.0.borrow().clone()).unwrap(), "gcd=6");
+        assert!(super::run_inline(&mut instance, "(gcd 1.5)").is_err());
+
+        // As with '--fold-case'.
+        let buffer = Buffer::default();
+        let mut instance = Interpreter::new().with_output(Sink::new(buffer.clone()))
+            .with_symbol_case(thesis_interpreter::syntax::SymbolCase::Insensitive);
+        super::run_inline(&mut instance, "($define Answer 42) (DISPLAY answer)").unwrap();
+        assert_eq!(String::from_utf8(buffer.0.borrow().clone()).unwrap(), "42");
+    }
+
+    #[test]
+    fn run_inline_broken_pipe() {
+        use std::io::ErrorKind;
+        // Writing the report of the failed write finds that the output is closed.
+        let mut instance = Interpreter::new().with_output(Sink::new(ClosedPipe(ErrorKind::BrokenPipe)));
+        let err = super::run_inline(&mut instance, "(display 1)").unwrap_err();
+        instance.print_error(err);
+        assert!(instance.is_output_closed());
+
+        let mut instance = Interpreter::new().with_output(Sink::new(ClosedPipe(ErrorKind::Other)));
+        let err = super::run_inline(&mut instance, "(display 1)").unwrap_err();
+        instance.print_error(err);
+        assert!(!instance.is_output_closed());
+    }
+
+    #[test]
+    fn execute_script_multiple_targets() {
+        let dir = std::env::temp_dir().join(format!("thesis-targets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("main.ths");
+        std::fs::write(&script, "(display \"hi\")").unwrap();
+        let out = dir.join("out");
+        let (script, out) = (script.to_string_lossy().to_string(), out.to_string_lossy().to_string());
+
+        super::execute_script(&script, &["ast", "json"], Some(&out), ("compact", 80), std::io::sink()).unwrap();
+        let read = |name: &str| std::fs::read_to_string(std::path::Path::new(&out).join(name)).unwrap();
+        assert_eq!(read("main.ast"), r#"((display "hi"))"#);
+        assert_eq!(read("main.json"), r#"[[{"symbol":"display"},"hi"]]"#);
+
+        // A single target is still written to the output as a file.
+        let single = dir.join("single.json").to_string_lossy().to_string();
+        super::execute_script(&script, &["json"], Some(&single), ("compact", 80), std::io::sink()).unwrap();
+        assert_eq!(std::fs::read_to_string(&single).unwrap(), read("main.json"));
+        super::execute_script(&script, &["ast"], Some(&single), ("canonical", 80), std::io::sink()).unwrap();
+        assert_eq!(std::fs::read_to_string(&single).unwrap(), "(display \"hi\")\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dispatch_script_targets() {
+        let dir = std::env::temp_dir().join(format!("thesis-dispatch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("script.ths").to_string_lossy().to_string();
+        let out = dir.join("out.txt").to_string_lossy().to_string();
+        std::fs::write(&script, "(display   \"hi\")").unwrap();
+        let app = super::command();
+        let dispatch = |args: &[&str]| {
+            let map = app.match_with(args.iter().map(|arg| arg.to_string()).collect()).unwrap();
+            let buffer = Buffer::default();
+            super::dispatch(&app, &map, buffer.clone()).map(|_| String::from_utf8(buffer.0.take()).unwrap())
+        };
+
+        // The script is written once as the target to the output, whatever order the flags come in.
+        assert_eq!(dispatch(&[&script, "--output", &out, "--target", "ast"]).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), r#"((display "hi"))"#);
+        std::fs::remove_file(&out).unwrap();
+        assert_eq!(dispatch(&["--target", "ast", "-o", &out, &script]).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), r#"((display "hi"))"#);
+        // The output alone takes the "ast" target, and the target alone is written to stdout.
+        assert_eq!(dispatch(&["--output", &out, "--ast-format", "canonical", &script]).unwrap(), "");
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "(display \"hi\")\n");
+        assert_eq!(dispatch(&[&script, "--target", "json"]).unwrap(), "[[{\"symbol\":\"display\"},\"hi\"]]\n");
+        // A bare script is written as the "ast" target to stdout, and a malformed one is reported.
+        assert_eq!(dispatch(&[&script]).unwrap(), "((display \"hi\"))\n");
+        let malformed = dir.join("malformed.ths").to_string_lossy().to_string();
+        std::fs::write(&malformed, "(a ]").unwrap();
+        assert_eq!(dispatch(&[&malformed]).unwrap_err().kind(), thesis_interpreter::ErrorKind::InvalidSyntax);
+        // A failed '--eval' is an error of the command, for 'main' to exit with.
+        assert_eq!(dispatch(&["--eval", "(car 1)"]).unwrap_err().kind(), thesis_interpreter::ErrorKind::CommandFailed);
+
+        // The interrupts come before anything else.
+        assert_eq!(dispatch(&[&script, "--target", "ast", "--version"]).unwrap(), concat!(env!("CARGO_PKG_VERSION"), "\n"));
+        assert_eq!(dispatch(&[&script, "--target", "ast", "--width", "x"]).unwrap_err().message(),
+            "'--width' expects a number of columns.");
+        assert_eq!(dispatch(&[&script, "--eval", "1"]).unwrap_err().message(),
+            "'--eval' cannot be used together with a script.");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn execute_script_invalid_utf8() {
+        use thesis_interpreter::ErrorKind;
+        let path = std::env::temp_dir().join(format!("thesis-invalid-{}.ths", std::process::id()));
+        std::fs::write(&path, b"(display \xff\xfe)").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let err = super::execute_script(&path, &["ast"], None, ("compact", 80), std::io::sink()).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), ErrorKind::EncodingError);
+        assert!(err.message().contains(&path));
+        assert!(err.message().contains("offset 9"));
+
+        // Every malformed form is counted, not only the first.
+        let path = std::env::temp_dir().join(format!("thesis-malformed-{}.ths", std::process::id()));
+        std::fs::write(&path, "(display 1))\n(display 2)\n(display 3))").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let err = super::execute_script(&path, &["ast"], None, ("compact", 80), std::io::sink()).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidSyntax);
+        assert!(err.message().ends_with("has 2 syntax errors."));
+
+        let missing = String::from("/nonexistent/thesis/script.ths");
+        assert_eq!(super::execute_script(&missing, &["ast"], None, ("compact", 80), std::io::sink()).unwrap_err().kind(), ErrorKind::IoError);
+    }
+
+    #[test]
+    fn execute_script_to_stdout() {
+        let path = std::env::temp_dir().join(format!("thesis-empty-{}.ths", std::process::id()));
+        std::fs::write(&path, "#;(display 1)\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let buffer = Buffer::default();
+        super::execute_script(&path, &["ast"], None, ("compact", 80), buffer.clone()).unwrap();
+        super::execute_script(&path, &["json"], None, ("compact", 80), buffer.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(String::from_utf8(buffer.0.take()).unwrap(), "()\n[]\n");
+    }
+
+    #[test]
+    fn execute_script_broken_pipe() {
+        use std::io::ErrorKind;
+        let path = std::env::temp_dir().join(format!("thesis-pipe-{}.ths", std::process::id()));
+        std::fs::write(&path, "(display 1)").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let result = super::execute_script(&path, &["ast"], None, ("compact", 80), ClosedPipe(ErrorKind::BrokenPipe));
+        let other = super::execute_script(&path, &["ast"], None, ("compact", 80), ClosedPipe(ErrorKind::Other));
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(other.unwrap_err().kind(), thesis_interpreter::ErrorKind::IoError);
     }
 }
@@ -1,12 +1,15 @@
 use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::BufRead;
 use std::fmt::Display;
+use std::path::Path;
 use std::process::exit;
 use std::rc::Rc;
-use ariadne::{Color, Fmt, Label, Source};
+use ariadne::{Color, Fmt, Label};
 
-use crate::error::{Error, ErrorKind};
+use crate::error::{Error, ErrorKind, ReportStyle};
 use crate::{if_or, seq};
-use crate::syntax::{Node, Symbol};
+use crate::syntax::{parse_char_literal, Bracket, Node, NodeSpan, Number, Symbol, SymbolCase};
 
 #[derive(Debug)]
 pub struct SrcInfo {
@@ -18,6 +21,18 @@ impl SrcInfo {
     pub fn new<S: Into<String>>(id: S, text: S) -> Self {
         Self { id: id.into(), text: text.into() }
     }
+
+    /// Read a source file, which must be valid UTF-8.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let input = std::fs::read(path).map_err(|err| Error::new(ErrorKind::IoError)
+            .with_message(format!("Failed to read '{}': {err}", path.display())))?;
+        let text = String::from_utf8(input).map_err(|err| Error::new(ErrorKind::EncodingError)
+            .with_message(format!("'{}' is not valid UTF-8: invalid byte sequence at offset {}.",
+                path.display(), err.utf8_error().valid_up_to())))?;
+        Ok(Self::new(path.display().to_string(), text))
+    }
+
+    pub fn text(&self) -> &str { &self.text }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -43,8 +58,8 @@ impl From<String> for Token {
     fn from(value: String) -> Self { Self(value) }
 }
 
-impl Into<String> for Token {
-    fn into(self) -> String { self.0 }
+impl From<Token> for String {
+    fn from(value: Token) -> Self { value.0 }
 }
 
 impl Token {
@@ -74,6 +89,50 @@ impl Token {
     }
 }
 
+/// Count the delimiters left open by the tokens, without building a tree.
+///
+/// A closer without any opener makes the count negative, while a closer that doesn't match
+/// the most recent opener is reported as `Err` with the index of the offending token.
+pub fn delimiter_balance(tokens: &[Token]) -> Result<i32, usize> {
+    let mut openers: Vec<&Token> = vec![];
+    let mut balance = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.as_ref() {
+            "(" | "[" | "{" => seq!(openers.push(token), balance += 1),
+            ")" | "]" | "}" => {
+                if let Some(opener) = openers.pop() {
+                    if !token.match_left_parentheses(&opener.0) { return Err(i) }
+                }
+                balance -= 1
+            }
+            _ => ()
+        }
+    }
+    Ok(balance)
+}
+
+/// The char index of the opener matched by the closer at the char index `closer`, ignoring
+/// the delimiters in strings, as for highlighting the opener while the cursor is on the closer.
+pub fn matching_opener(input: &str, closer: usize) -> Option<usize> {
+    let mut lexer = LexicalParser::new();
+    lexer.parse_str(input);
+    let mut openers = vec![];
+    for (pos, token) in lexer.results() {
+        // The lexer records each delimiter at its own position, counting from 1.
+        match token.as_ref() {
+            "(" | "[" | "{" => openers.push((pos.i() - 1, token)),
+            ")" | "]" | "}" => {
+                let opener = openers.pop();
+                if pos.i() - 1 == closer {
+                    return opener.filter(|(_, opener)| token.match_left_parentheses(&opener.0)).map(|(i, _)| i)
+                }
+            }
+            _ => ()
+        }
+    }
+    None
+}
+
 /// Representing the current position as (line, column, index) of a source parser.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SourcePos(usize, usize, usize);
@@ -94,6 +153,18 @@ impl From<(usize, usize, usize)> for SourcePos {
     fn from(value: (usize, usize, usize)) -> Self { Self(value.0, value.1, value.2) }
 }
 
+/// The state of a literate lexer, which only lexes the lines between the code fences
+/// `#+code` and `#-code`, and skips the prose around them.
+#[derive(Debug, Default)]
+struct Literate {
+    /// The line read so far, which is lexed or skipped once it's complete.
+    line: String,
+    /// The span of the fence opening the code block being lexed, if any.
+    open_fence: Option<std::ops::Range<usize>>,
+    /// The message and the span of the first fence found where it doesn't belong.
+    misplaced_fence: Option<(String, std::ops::Range<usize>)>
+}
+
 #[derive(Debug)]
 pub struct LexicalParser {
     buf: String,
@@ -102,12 +173,67 @@ pub struct LexicalParser {
     // 0 indicates initial state
     // 1 indicates parsing string literal
     // 2 indicates to unescape characters
-    parsing_context: usize
+    parsing_context: usize,
+    literate: Option<Literate>
+}
+
+impl Default for LexicalParser {
+    fn default() -> Self {
+        LexicalParser::new()
+    }
 }
 
 impl LexicalParser {
     pub fn new() -> Self {
-        Self { buf: "".to_string(), pos: (1, 1, 1).into(), results: vec![], parsing_context: 0 }
+        Self { buf: "".to_string(), pos: (1, 1, 1).into(), results: vec![], parsing_context: 0, literate: None }
+    }
+
+    /// A lexer for documents which are prose except for their fenced code blocks.
+    /// The positions of the tokens still count the prose, so they point into the document.
+    pub fn literate() -> Self {
+        Self { literate: Some(Literate::default()), ..Self::new() }
+    }
+
+    /// Check that every code fence is balanced, once the whole document is lexed.
+    pub fn check_fences(&self) -> Result<(), Error> {
+        let Some(ref literate) = self.literate else { return Ok(()) };
+        let (message, span) = match (&literate.misplaced_fence, &literate.open_fence) {
+            (Some((message, span)), _) => (message.clone(), span.clone()),
+            (None, Some(span)) => ("The code block is never closed with '#-code'.".to_string(), span.clone()),
+            (None, None) => return Ok(())
+        };
+        Err(Error::new(ErrorKind::InvalidSyntax).with_message(message).with_span(span))
+    }
+
+    /// Lex the complete line if it is in a code block, or skip it, keeping track of the fences.
+    fn end_literate_line(&mut self) {
+        let Some(mut literate) = self.literate.take() else { return };
+        let line = core::mem::take(&mut literate.line);
+        let start = self.pos.i() - 1 + line.chars().take_while(|ch| ch.is_whitespace()).count();
+        let span = start..(start + line.trim().chars().count());
+        let in_code = literate.open_fence.is_some();
+        match line.trim() {
+            "#+code" if !in_code => literate.open_fence = Some(span),
+            "#-code" if in_code => {
+                self.try_collect_buf();
+                literate.open_fence = None
+            }
+            fence @ ("#+code" | "#-code") => {
+                let problem = if_or!(in_code, "is inside a code block", "closes no code block");
+                literate.misplaced_fence.get_or_insert((format!("'{fence}' {problem}."), span));
+            }
+            _ if in_code => {
+                // The lexer is literal while the state is taken, so the line is lexed as is.
+                line.chars().for_each(|ch| self.parse_c(ch));
+                self.literate = Some(literate);
+                return
+            }
+            _ => ()
+        }
+        for ch in line.chars() {
+            if ch != '\n' { self.pos.next_col() } else { self.pos.next_ln() }
+        }
+        self.literate = Some(literate);
     }
 
     pub fn results(self) -> Vec<(SourcePos, Token)> {
@@ -119,7 +245,17 @@ impl LexicalParser {
     }
 
     pub fn parse_c(&mut self, ch: char) {
+        if let Some(ref mut literate) = self.literate {
+            literate.line.push(ch);
+            if ch == '\n' { self.end_literate_line() }
+            return
+        }
         match ch {
+            // An escaped character never ends the string.
+            ch if self.parsing_context == 2 => {
+                self.buf.push(ch);
+                self.parsing_context = 1;
+            }
             ch if self.parsing_context == 1 => {
                 self.buf.push(ch);
                 if ch == '\\' {
@@ -130,6 +266,8 @@ impl LexicalParser {
                     self.parsing_context = 0;
                 }
             },
+            // The character of a literal like `#\(` or `#\ ` is taken as it is.
+            ch if self.buf == "#\\" => self.buf.push(ch),
             '(' | '[' | '{' => {
                 self.push_token(String::from(ch).into());
             }
@@ -137,14 +275,17 @@ impl LexicalParser {
                 self.try_collect_buf();
                 self.push_token(String::from(ch).into())
             }
-            ',' | ';' => self.push_token(String::from(ch).into()),
+            ';' if self.buf == "#" => {
+                self.buf.clear();
+                self.push_token("#;".into())
+            }
+            ',' | ';' => {
+                self.try_collect_buf();
+                self.push_token(String::from(ch).into())
+            }
             '\'' | '"'=> {
                 self.buf.push(ch);
-                if self.parsing_context == 0 {
-                    self.parsing_context = 1;
-                } else if self.parsing_context == 2 {
-                    self.parsing_context = 1;
-                }
+                self.parsing_context = 1;
             },
             ch if ch.is_ascii_whitespace() || ch == '\x0B' => self.try_collect_buf(),
             ch => self.buf.push(ch)
@@ -153,8 +294,21 @@ impl LexicalParser {
         if ch != '\n' { self.pos.next_col() } else { self.pos.next_ln() }
     }
 
+    /// The net delimiter balance of the input: positive while delimiters are left open, and
+    /// negative when there are more closers than openers. Delimiters in strings don't count.
+    pub fn count_balance(input: &str) -> i32 {
+        let mut lexer = Self::new();
+        lexer.parse_str(input);
+        lexer.results.iter().fold(0, |balance, (_, token)| match token.as_ref() {
+            "(" | "[" | "{" => balance + 1,
+            ")" | "]" | "}" => balance - 1,
+            _ => balance
+        })
+    }
+
     pub fn parse_str(&mut self, source: &str) {
         for ch in source.chars() { self.parse_c(ch) }
+        self.end_literate_line();
         self.try_collect_buf();
     }
 
@@ -175,66 +329,217 @@ impl LexicalParser {
     }
 }
 
+/// The number of errors collected by `SyntacticParser::try_parse_all` before it gives up.
+pub const DEFAULT_MAX_ERRORS: usize = 100;
+
 pub struct SyntacticParser {
     src: Rc<RefCell<SrcInfo>>,
     tree: Node,
+    /// The tokens not yet consumed by `parse_one`, lexed on its first call.
+    pending: Option<VecDeque<(SourcePos, Token)>>,
+    /// The rest of a source read a line at a time by `parse_one`, with the lexer which keeps
+    /// its state from one line to the next.
+    reader: Option<(Box<dyn BufRead>, LexicalParser)>,
+    max_errors: usize,
+    symbol_case: SymbolCase,
+    /// The spans of the top-level forms of the tree.
+    spans: Vec<NodeSpan>
 }
 
 impl SyntacticParser {
     pub fn new(src: Rc<RefCell<SrcInfo>>) -> Self {
-        Self { src, tree: Node::List(vec![]) }
+        Self {
+            src,
+            tree: Node::list(vec![]),
+            pending: None,
+            reader: None,
+            max_errors: DEFAULT_MAX_ERRORS,
+            symbol_case: SymbolCase::default(),
+            spans: vec![]
+        }
+    }
+
+    /// A parser of the source read from `reader` only as `parse_one` needs it, whose lines
+    /// are appended to the text of `src` as they are read, so that the reports can show them.
+    pub fn from_reader<R: BufRead + 'static>(src: Rc<RefCell<SrcInfo>>, reader: R) -> Self {
+        Self { reader: Some((Box::new(reader), LexicalParser::new())), ..Self::new(src) }
+    }
+
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        seq!(self.max_errors = max_errors, self)
+    }
+
+    /// Normalize the symbols read by the policy.
+    pub fn with_symbol_case(mut self, symbol_case: SymbolCase) -> Self {
+        seq!(self.symbol_case = symbol_case, self)
     }
 
     fn first_quoted(s: &str) -> bool {
-        match s.chars().nth(0).unwrap() {
-            '\'' | '"' => true,
-            _ => false
-        }
+        matches!(s.chars().nth(0).unwrap(), '\'' | '"')
     }
 
     pub fn parse(&mut self) {
         let _ = self.try_parse().is_err_and(|err| {
-            let _ = err.report
-                .unwrap()
-                .finish()
-                .print((self.src.borrow().id.clone(), Source::from(&self.src.borrow().text)))
-                .unwrap();
+            err.print_report(&self.src.borrow(), &ReportStyle::default());
             exit(1);
         });
     }
 
     pub fn try_parse(&mut self) -> Result<(), Error> {
-        let mut nest: (i32, Vec<(SourcePos, String)>) = (0, vec![]); // (Nesting Depth, Parentheses Kind)
-        let mut current = &mut self.tree;
-
-        let src = self.src.borrow();
-
         let tokens = {
             let mut lexer = LexicalParser::new();
-            lexer.parse_str(&src.text);
+            lexer.parse_str(&self.src.borrow().text);
             lexer.results()
         };
+        self.parse_tokens(tokens)
+    }
+
+    /// Parse the next top-level form of the source, or return `None` once it is exhausted.
+    ///
+    /// The source is lexed as a whole on the first call, or a line at a time from the reader
+    /// until a form is complete, but only the tokens of one form are parsed on each call,
+    /// so a malformed form doesn't keep the following ones from being read.
+    pub fn parse_one(&mut self) -> Option<Result<Node, Error>> {
+        self.parse_one_spanned().map(|result| result.map(|(form, _)| form))
+    }
+
+    fn parse_one_spanned(&mut self) -> Option<Result<(Node, NodeSpan), Error>> {
+        let src = self.src.clone();
+        self.pending.get_or_insert_with(|| {
+            let mut lexer = LexicalParser::new();
+            lexer.parse_str(&src.borrow().text);
+            lexer.results().into()
+        });
+
+        // Take the tokens up to the end of the first datum that isn't commented out.
+        let mut form = vec![];
+        let (mut depth, mut skips) = (0, 0);
+        loop {
+            let Some((pos, token)) = self.pending.as_mut()?.pop_front() else {
+                match self.read_line() {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(err) => return Some(Err(err))
+                }
+            };
+            match token.as_ref() {
+                "#;" if depth == 0 => skips += 1,
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth -= 1,
+                _ => ()
+            }
+            let completed = depth <= 0 && token.as_ref() != "#;";
+            form.push((pos, token));
+            if completed {
+                if skips == 0 || depth < 0 { break }
+                skips -= 1;
+            }
+        }
+        if form.is_empty() { return None }
+
+        let tree = core::mem::replace(&mut self.tree, Node::list(vec![]));
+        let spans = core::mem::take(&mut self.spans);
+        let result = self.parse_tokens(form);
+        let parsed = core::mem::replace(&mut self.tree, tree);
+        let mut parsed_spans = core::mem::replace(&mut self.spans, spans);
+        match (result, parsed) {
+            (Err(err), _) => Some(Err(err)),
+            (Ok(()), Node::List(mut forms, _)) => forms.pop().zip(parsed_spans.pop()).map(Ok),
+            _ => unreachable!()
+        }
+    }
+
+    /// Lex the next line of the reader into the pending tokens, returning whether there was one.
+    /// The reading stops at the end of the source, or at the first line which can't be read.
+    fn read_line(&mut self) -> Result<bool, Error> {
+        let Some((mut reader, mut lexer)) = self.reader.take() else { return Ok(false) };
+        let mut bytes = vec![];
+        let line = reader.read_until(b'\n', &mut bytes)
+            .map_err(|err| Error::new(ErrorKind::IoError)
+                .with_message(format!("Failed to read '{}': {err}", self.src.borrow().id)))
+            .and_then(|_| String::from_utf8(bytes).map_err(|err| {
+                let src = self.src.borrow();
+                Error::new(ErrorKind::EncodingError)
+                    .with_message(format!("'{}' is not valid UTF-8: invalid byte sequence at offset {}.",
+                        src.id, src.text.len() + err.utf8_error().valid_up_to()))
+            }));
+        let line = line?;
+        if line.is_empty() {
+            lexer.try_collect_buf();
+        } else {
+            // The characters are lexed one by one, as a string may go on in the next line.
+            line.chars().for_each(|ch| lexer.parse_c(ch));
+            self.src.borrow_mut().text.push_str(&line);
+        }
+        let tokens = core::mem::take(&mut lexer.results);
+        self.pending.get_or_insert_with(VecDeque::new).extend(tokens);
+        if line.is_empty() { return Ok(false) }
+        self.reader = Some((reader, lexer));
+        Ok(true)
+    }
+
+    /// Parse every top-level form, collecting the error of each malformed one instead of
+    /// stopping at the first, and keeping the other forms in the tree.
+    ///
+    /// Once more than `max_errors` errors are found, the rest of the source is skipped, and
+    /// a final error noting so is collected in place of the extra one.
+    pub fn try_parse_all(&mut self) -> Result<(), Vec<Error>> {
+        let mut errors = vec![];
+        while let Some(result) = self.parse_one_spanned() {
+            match result {
+                Ok((form, span)) => {
+                    self.tree.push(form);
+                    self.spans.push(span);
+                }
+                Err(_) if errors.len() == self.max_errors => {
+                    errors.push(Error::new(ErrorKind::InvalidSyntax)
+                        .with_message(format!("Too many errors, stopped after the first {}.", self.max_errors)));
+                    break
+                }
+                Err(err) => errors.push(err)
+            }
+        }
+        if_or!(errors.is_empty(), Ok(()), Err(errors))
+    }
+
+    fn parse_tokens(&mut self, tokens: Vec<(SourcePos, Token)>) -> Result<(), Error> {
+        let mut nest: (i32, Vec<(SourcePos, String)>) = (0, vec![]); // (Nesting Depth, Parentheses Kind)
+        // The nesting depths (and positions) of datum comments still waiting for their datum.
+        let mut skips: Vec<(i32, SourcePos)> = vec![];
+        let mut current = &mut self.tree;
+        // The start of each open list with the spans of its elements so far, under the top level.
+        let mut spans: Vec<(usize, Vec<NodeSpan>)> = vec![(0, vec![])];
+
+        let src = self.src.borrow();
 
         for (pos, token) in tokens {
+            let span = Self::any_token_span(pos, &token);
+            let is_closer = matches!(token.as_ref(), ")" | "]" | "}");
             match token.0.as_str() {
+                "#;" => {
+                    skips.push((nest.0, pos));
+                    continue;
+                }
+                "," | ";" => return Err(Self::unexpected_separator(&src, pos, &token)),
                 "(" | "[" | "{" => {
                     nest.0 += 1;
                     nest.1.push((pos, token.0.to_string()));
-                    current = current.push(Node::List(vec![]));
+                    spans.push((span.start, vec![]));
+                    current = current.push(Node::List(vec![], Bracket::of(&token)));
+                    continue;
                 }
                 ")" | "]" | "}" => {
+                    if let Some(&(depth, skip_pos)) = skips.last() {
+                        if depth == nest.0 {
+                            return Err(Self::dangling_datum_comment(&src, skip_pos));
+                        }
+                    }
                     nest.0 -= 1;
                     let wrapped_last = nest.1.last();
-                    let last: &(SourcePos, String);
-                    match wrapped_last {
-                        Some(val) => last = val,
-                        None => return Err(Error::new(ErrorKind::InvalidSyntax)
-                        .with_message(
-                            format!("No corresponding '{}' can be found for '{token}'.",
-                            token.as_left_parentheses()))
-                        .with_span((pos.i()-1)..pos.i())
-                        .return_error(&src, pos, format!("Invalid '{token}' here.")))
-                    }
+                    let last = match wrapped_last {
+                        Some(val) => val,
+                        None => return Err(Self::stray_closer(&src, pos, &token))
+                    };
                     if !token.match_left_parentheses(&last.1) {
                         use Color::*;
                         return Err(Error::new(ErrorKind::InvalidSyntax)
@@ -256,35 +561,68 @@ impl SyntacticParser {
                             format!("Invalid closing '{}{}.", token.fg(Fixed(81)), "' here".fg(Red)).fg(Red).to_string()))
                     }
                     nest.1.pop();
+                    let (start, children) = spans.pop().unwrap();
+                    spans.last_mut().unwrap().1.push(NodeSpan { span: start..span.end, children });
                     current = &mut self.tree;
                     for _ in 0..nest.0 {
-                        if let Node::List(ref mut list) = current {
+                        if let Node::List(ref mut list, _) = current {
                             current = list.last_mut().unwrap();
                         }
                     }
+                    if token.as_ref() == "}" {
+                        let map = Self::map_literal(&src, pos, start..span.end, current.as_mut().pop().unwrap())?;
+                        current.push(map);
+                    }
                 },
                 s if Self::first_quoted(s) => {
-                    match Self::try_unquote(s) {
-                        Ok(unquoted) => current.push(Node::String(unquoted)),
-                        Err(err) => return Err(err)
-                    };
+                    let unquoted = Self::try_unquote(&src, pos, &token)?;
+                    current.push(Node::String { value: unquoted, raw: s.to_string() });
                 },
-                n if n.chars().nth(0).unwrap().is_digit(10) => {
-                    for ch in n.chars() {
-                        if !ch.is_digit(10) {
-                            return Err(Error::new(ErrorKind::InvalidSyntax))
-                        }
-                    }
-                    current.push(Node::Number(token.0));
+                "#t" | "#true" => { current.push(Node::Bool(true)); }
+                "#f" | "#false" => { current.push(Node::Bool(false)); }
+                c if c.starts_with("#\\") => match parse_char_literal(c) {
+                    Some(value) => { current.push(Node::Char { value, raw: c.to_string() }); }
+                    None => return Err(Error::new(ErrorKind::InvalidSyntax)
+                        .with_message(format!("Unknown character literal '{c}'."))
+                        .with_span(Self::token_span(pos, &token))
+                        .return_error(&src, pos, "Invalid character here.".to_string()))
+                },
+                n if Number::parse(n).is_some() => {
+                    current.push(Node::number(n).unwrap());
+                }
+                n if n.chars().nth(0).unwrap().is_ascii_digit() => {
+                    return Err(Error::new(ErrorKind::InvalidSyntax)
+                        .with_message(format!("Invalid number literal '{n}'."))
+                        .with_span(Self::token_span(pos, &token))
+                        .return_error(&src, pos, "Invalid number here.".to_string()))
                 }
                 _ => {
-                    let symbol = Symbol::try_from(token.0);
-                    current.push(Node::Symbol(symbol.unwrap_or_else(|err| panic!("{err}"))));
+                    match Symbol::try_from(token) {
+                        Ok(symbol) => current.push(Node::Symbol(symbol.with_case(self.symbol_case))),
+                        Err(err) => return Err(err
+                            .with_span(span)
+                            .return_error(&src, pos, "Invalid symbol here.".to_string()))
+                    };
                 }
             }
+            // Every other token that gets here is an atom.
+            if !is_closer {
+                spans.last_mut().unwrap().1.push(NodeSpan { span, children: vec![] });
+            }
+
+            // A datum has just been completed, so drop it if it is commented out.
+            if skips.last().is_some_and(|skip| skip.0 == nest.0) {
+                skips.pop();
+                current.as_mut().pop();
+                spans.last_mut().unwrap().1.pop();
+            }
+        }
+
+        if let Some(&(_, skip_pos)) = skips.last() {
+            return Err(Self::dangling_datum_comment(&src, skip_pos));
         }
 
-        Ok(if nest.0 != 0 {
+        if nest.0 != 0 {
             let last = nest.1.last().unwrap();
             return Err(Error::new(ErrorKind::InvalidSyntax)
                 .with_message(
@@ -292,15 +630,164 @@ impl SyntacticParser {
                 .with_span((last.0.i()-1)..last.0.i())
                 .return_error(&src, last.0,
                     format!("Single '{}' found here.", last.1.clone().fg(Color::Red))));
-        })
+        }
+        self.spans.extend(spans.pop().unwrap().1);
+        Ok(())
+    }
+
+    /// The elements of a list in braces taken as the keys and values of a map, of which there
+    /// must be an even number.
+    fn map_literal(src: &SrcInfo, pos: SourcePos, span: std::ops::Range<usize>, list: Node) -> Result<Node, Error> {
+        let Node::List(nodes, _) = list else { unreachable!("Braces always hold a list.") };
+        if nodes.len() % 2 != 0 {
+            return Err(Error::new(ErrorKind::InvalidSyntax)
+                .with_message(format!("A map literal needs a value for each key, but got {} elements.", nodes.len()))
+                .with_span(span)
+                .return_error(src, pos, "This map has a key without a value.".to_string()))
+        }
+        let mut nodes = nodes.into_iter();
+        let mut entries = vec![];
+        while let (Some(key), Some(value)) = (nodes.next(), nodes.next()) {
+            entries.push((key, value));
+        }
+        Ok(Node::Map(entries))
+    }
+
+    /// Neither `,` nor `;` has a meaning of its own, as there is no quasiquotation
+    /// and comments are written with `#;`.
+    fn separator_message(token: &Token) -> String {
+        match token.as_ref() {
+            "," => "Unexpected ','; unquoting is not supported outside a quasiquotation.".to_string(),
+            _ => "Unexpected ';'; use '#;' to comment out a datum.".to_string()
+        }
     }
 
-    pub fn try_unquote(s: &str) -> Result<String, Error> {
-        let first = s.chars().nth(0).unwrap();
-        let end = s.chars().last().unwrap();
-        if first == end {
-            Ok(s[1..s.len()-1].to_string())
-        } else { Err(Error::new(ErrorKind::InvalidSyntax)) }
+    fn unexpected_separator(src: &SrcInfo, pos: SourcePos, token: &Token) -> Error {
+        Error::new(ErrorKind::InvalidSyntax)
+            .with_message(Self::separator_message(token))
+            .with_span((pos.i()-1)..pos.i())
+            .return_error(src, pos, format!("Invalid '{token}' here."))
+    }
+
+    /// A closer is recorded at its own position, unlike the tokens buffered by the lexer,
+    /// so the span covers exactly the closer.
+    fn stray_closer(src: &SrcInfo, pos: SourcePos, token: &Token) -> Error {
+        let start = pos.i() - 1;
+        Error::new(ErrorKind::InvalidSyntax)
+            .with_message(format!("Unexpected '{token}', as there is no '{}' left to close.",
+                token.as_left_parentheses()))
+            .with_span(start..(start + 1))
+            .return_error(src, (pos.ln(), pos.col(), start).into(),
+                format!("This '{token}' has no matching '{}'.", token.as_left_parentheses()))
+    }
+
+    fn dangling_datum_comment(src: &SrcInfo, pos: SourcePos) -> Error {
+        Error::new(ErrorKind::InvalidSyntax)
+            .with_message("No datum follows the datum comment '#;'.".to_string())
+            .with_span((pos.i()-2)..pos.i())
+            .return_error(src, pos, "Datum comment here.".to_string())
+    }
+
+    /// The span of a buffered token, whose position is recorded at its end by the lexer.
+    pub(crate) fn token_span(pos: SourcePos, token: &Token) -> std::ops::Range<usize> {
+        let end = pos.i() - 1;
+        (end - token.as_ref().chars().count())..end
+    }
+
+    /// The span of any token, where a delimiter or separator is recorded by the lexer at its
+    /// own position rather than after it, along with the `#;` it ends.
+    fn any_token_span(pos: SourcePos, token: &Token) -> std::ops::Range<usize> {
+        match token.as_ref() {
+            "(" | "[" | "{" | ")" | "]" | "}" | "," | ";" => (pos.i() - 1)..pos.i(),
+            "#;" => (pos.i() - 2)..pos.i(),
+            _ => Self::token_span(pos, token)
+        }
+    }
+
+    /// The spans of every form parsed, atoms and lists alike, and whether each is a list or
+    /// a map. A list comes before its forms.
+    fn form_spans(&self) -> Vec<(std::ops::Range<usize>, bool)> {
+        let mut forms = vec![];
+        let mut pending: Vec<(&Node, &NodeSpan)> = self.tree.as_ref().iter().zip(&self.spans).collect();
+        while let Some((node, span)) = pending.pop() {
+            forms.push((span.span.clone(), matches!(node, Node::List(..) | Node::Map(_))));
+            pending.extend(node.children().into_iter().zip(&span.children));
+        }
+        forms
+    }
+
+    /// The span of the innermost form parsed which contains the char offset: the atom at the
+    /// offset, or else the list it is in, or `None` if it is outside every form. Like the
+    /// spans of `span_of`, it counts characters.
+    pub fn form_at(&self, offset: usize) -> Option<std::ops::Range<usize>> {
+        self.form_spans().into_iter()
+            .filter(|(span, _)| span.contains(&offset))
+            .min_by_key(|(span, _)| span.len())
+            .map(|(span, _)| span)
+    }
+
+    /// The span of the innermost list enclosing the form at the char offset, which is the
+    /// next selection to extend to from `form_at`, or `None` if the form is at the top level.
+    pub fn enclosing_form(&self, offset: usize) -> Option<std::ops::Range<usize>> {
+        let form = self.form_at(offset)?;
+        self.form_spans().into_iter()
+            .filter(|(span, is_list)| *is_list && *span != form
+                && span.start <= form.start && form.end <= span.end)
+            .min_by_key(|(span, _)| span.len())
+            .map(|(span, _)| span)
+    }
+
+    /// The contents of a string token, which starts and ends with the same quote. Quotes of
+    /// either kind toggle the lexer in and out of a string, so the token may contain both,
+    /// and the escapes `\n`, `\t`, `\r`, `\\`, `\"` and `\'` stand for the characters they
+    /// name, as `write` writes them.
+    fn try_unquote(src: &SrcInfo, pos: SourcePos, token: &Token) -> Result<String, Error> {
+        let s = token.as_ref();
+        let quote = s.chars().next().unwrap();
+        let inner = &s[quote.len_utf8()..];
+        let is_quote = |ch: char| ch == '"' || ch == '\'';
+        let mut value = String::new();
+        // The last quote which isn't escaped, and whether it ends the literal.
+        let mut last_quote = None;
+        let mut chars = inner.char_indices().peekable();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, 'r')) => value.push('\r'),
+                    Some((_, ch @ ('\\' | '"' | '\''))) => value.push(ch),
+                    Some((_, ch)) => {
+                        let start = Self::token_span(pos, token).start + inner[..i].chars().count() + 1;
+                        return Err(Error::new(ErrorKind::InvalidSyntax)
+                            .with_message(format!("Unknown escape '\\{ch}' in the string."))
+                            .with_span(start..start + 2)
+                            .return_error(src, pos, "Escape only n, t, r, \\, \" or ' in this string.".to_string()))
+                    }
+                    None => ()
+                },
+                ch => {
+                    if is_quote(ch) { last_quote = Some((i, ch, chars.peek().is_none())) }
+                    value.push(ch)
+                }
+            }
+        }
+        let (message, label) = match last_quote {
+            Some((_, last, true)) if last == quote => return Ok(seq!(value.pop(), value)),
+            Some((_, last, true)) => (
+                format!("The string opened with {quote} is closed with {last}."),
+                format!("Close this string with {quote} as well.")),
+            Some((i, last, false)) => (
+                format!("Unexpected '{}' right after the string.", &inner[i + last.len_utf8()..]),
+                "Separate this string from what follows it.".to_string()),
+            None => (
+                format!("No closing {quote} was found for the string."),
+                format!("This string needs a closing {quote}."))
+        };
+        Err(Error::new(ErrorKind::InvalidSyntax)
+            .with_message(message)
+            .with_span(Self::token_span(pos, token))
+            .return_error(src, pos, label))
     }
 
     // TODO: Update
@@ -313,7 +800,7 @@ impl SyntacticParser {
                 "(" | "[" | "{" => {
                     nest.0 += 1;
                     nest.1.push(token.0.to_string());
-                    current = current.push(Node::List(vec![]));
+                    current = current.push(Node::List(vec![], Bracket::of(&token)));
                 }
                 ")" | "]" | "}" => {
                     nest.0 -= 1;
@@ -327,21 +814,36 @@ impl SyntacticParser {
                     nest.1.pop();
                     current = &mut self.tree;
                     for _ in 0..nest.0 {
-                        if let Node::List(ref mut list) = current {
+                        if let Node::List(ref mut list, _) = current {
                             current = list.last_mut().unwrap();
                         }
                     }
                 }
+                "," | ";" => {
+                    panic!("{}", Error::new(ErrorKind::InvalidSyntax).with_message(Self::separator_message(&token)))
+                }
                 _ => {
-                    let symbol = Symbol::try_from(token);
-                    current.push(Node::Symbol(symbol.unwrap_or_else(|err| panic!("{err}"))));
+                    let symbol = Symbol::try_from(token).unwrap_or_else(|err| panic!("{err}"));
+                    current.push(Node::Symbol(symbol.with_case(self.symbol_case)));
                 }
             }
         }
     }
 
     pub fn reset(mut self) -> Node {
-        core::mem::replace(&mut self.tree, Node::List(vec![]))
+        core::mem::replace(&mut self.tree, Node::list(vec![]))
+    }
+
+    /// The spans of the top-level forms of the tree, in the same order.
+    pub fn spans(&self) -> &[NodeSpan] {
+        &self.spans
+    }
+
+    /// The span of the node reached from the tree by the path of indices, whose first index
+    /// picks the top-level form.
+    pub fn span_of(&self, path: &[usize]) -> Option<std::ops::Range<usize>> {
+        let (first, rest) = path.split_first()?;
+        self.spans.get(*first)?.get(rest).map(|span| span.span.clone())
     }
     
     pub fn tree(self) -> Node {
@@ -357,8 +859,8 @@ impl InfixTransformer {}
 
 #[cfg(test)]
 mod tests {
-    use crate::{share, syntax::Node};
-    use super::{SrcInfo, LexicalParser, SyntacticParser, Token};
+    use crate::{share, syntax::{Bracket, Node}};
+    use super::{delimiter_balance, SrcInfo, LexicalParser, SyntacticParser, Token};
 
     fn to_tokens(vector: Vec<&str>) -> Vec<Token> {
         vector.into_iter().map(|string| string.into()).collect()
@@ -375,6 +877,70 @@ mod tests {
         assert_eq!(*lexer.tokens(), to_tokens(vec!["(", "eval", "(", ")", ")", "(", "display", ")"]));
     }
 
+    #[test]
+    fn lexical_parse_literate() {
+        let document = "# Notes\nProse with (parens and \"quotes.\n#+code\n(gcd 4 6)\n  #-code\nMore prose)\n\
+            #+code\n(display 'a')\n#-code";
+        let mut lexer = LexicalParser::literate();
+        lexer.parse_str(document);
+        assert!(lexer.check_fences().is_ok());
+        assert_eq!(lexer.tokens(), to_tokens(vec!["(", "gcd", "4", "6", ")", "(", "display", "'a'", ")"]));
+        // The positions count the prose too, so they point into the document.
+        let (pos, token) = &lexer.results()[1];
+        assert_eq!(token.as_ref(), "gcd");
+        let start = document.find("gcd").unwrap();
+        assert_eq!(SyntacticParser::token_span(*pos, token), start..(start + 3));
+        assert_eq!(pos.ln(), 4);
+
+        for (document, span) in [("#+code\n(a)", 0..6), ("(a)\n  #-code\n", 6..12), ("#+code\n#+code\n#-code", 7..13)] {
+            let mut lexer = LexicalParser::literate();
+            lexer.parse_str(document);
+            let err = lexer.check_fences().unwrap_err();
+            assert_eq!(err.kind(), crate::error::ErrorKind::InvalidSyntax, "{document:?}");
+            assert_eq!(*err.span(), span, "{document:?}");
+        }
+        assert!(LexicalParser::new().check_fences().is_ok());
+    }
+
+    #[test]
+    fn delimiter_balance_of_tokens() {
+        let balance = |src: &str| {
+            let mut lexer = LexicalParser::new();
+            lexer.parse_str(src);
+            delimiter_balance(&lexer.tokens())
+        };
+        assert_eq!(balance("(a (b"), Ok(2));
+        assert_eq!(balance("(a b)"), Ok(0));
+        assert_eq!(balance("[a {b}]"), Ok(0));
+        assert_eq!(balance("a)"), Ok(-1));
+        assert_eq!(balance("(a ]"), Err(2));
+        assert_eq!(balance(""), Ok(0));
+    }
+
+    #[test]
+    fn lexical_count_balance() {
+        assert_eq!(LexicalParser::count_balance("(gcd [4] {6})"), 0);
+        assert_eq!(LexicalParser::count_balance("($define (f x)"), 1);
+        assert_eq!(LexicalParser::count_balance("(a (b\n(c"), 3);
+        assert_eq!(LexicalParser::count_balance("(a))]"), -2);
+        assert_eq!(LexicalParser::count_balance(""), 0);
+        assert_eq!(LexicalParser::count_balance(r#"(display "(((")"#), 0);
+        assert_eq!(LexicalParser::count_balance(r#"(display ")" "#), 1);
+        assert_eq!(LexicalParser::count_balance("(a #;(b c)"), 1);
+    }
+
+    #[test]
+    fn matching_opener_of_closer() {
+        use super::matching_opener;
+        assert_eq!(matching_opener("(a (b) [c])", 5), Some(3));
+        assert_eq!(matching_opener("(a (b) [c])", 9), Some(7));
+        assert_eq!(matching_opener("(a (b) [c])", 10), Some(0));
+        assert_eq!(matching_opener(r#"(display ")")"#, 12), Some(0));
+        assert_eq!(matching_opener("(a]", 2), None);
+        assert_eq!(matching_opener("a)", 1), None);
+        assert_eq!(matching_opener("(a)", 1), None);
+    }
+
     #[test]
     fn lexical_parse_literal() {
         let mut lexer: LexicalParser;
@@ -396,25 +962,26 @@ mod tests {
         use Node::*;
         let mut parser: SyntacticParser;
         
-        parser = SyntacticParser::new(share!(SrcInfo::new("test-1", "apply display +".into())));
+        parser = SyntacticParser::new(share!(SrcInfo::new("test-1", "apply display +")));
         parser.parse();
         assert_eq!(parser.tree(), 
-            List(vec![Symbol("apply".into()), Symbol("display".into()), Symbol("+".into())]));
+            Node::list(vec![Symbol("apply".into()), Symbol("display".into()), Symbol("+".into())]));
         
         parser = SyntacticParser::new(
             share!(SrcInfo::new(
                 "test-2",
-                "apply display (cons (list $if #t) [cons (list* #t #f) ()])".into()
+                "apply display (cons (list $if #t) [cons (list* #t #f) ()])"
             ))
         );
         parser.parse();
         assert_eq!(parser.tree(),
-            List(vec!["apply".into(), "display".into(), 
-                List(vec!["cons".into(), 
-                    List(vec!["list".into(), "$if".into(), "#t".into()]),
-                    List(vec!["cons".into(), 
-                        List(vec!["list*".into(), "#t".into(), "#f".into()]),
-                        List(vec![])]
+            Node::list(vec!["apply".into(), "display".into(), 
+                Node::list(vec!["cons".into(), 
+                    Node::list(vec!["list".into(), "$if".into(), true.into()]),
+                    Node::List(vec!["cons".into(), 
+                        Node::list(vec!["list*".into(), true.into(), false.into()]),
+                        Node::list(vec![])],
+                        Bracket::Square
                     )
                 ])        
             ])
@@ -423,7 +990,6 @@ mod tests {
 
     #[test]
     fn syntactic_parse_tokens() {
-        use Node::*;
         let mut parser;
 
         parser = SyntacticParser::new(
@@ -434,17 +1000,259 @@ mod tests {
         );
         parser.try_parse().unwrap();
         assert_eq!(parser.tree(),
-            List(vec!["apply".into(), "+".into(), 
-                List(vec!["list".into(), 1.into(), 2.into()])
+            Node::list(vec!["apply".into(), "+".into(), 
+                Node::list(vec!["list".into(), 1.into(), 2.into()])
             ])
         );
     }
 
     #[test]
-    fn syntactic_parse_parentheses_match() {
+    fn syntactic_parse_numbers() {
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-numbers", "1 -2 +3.5 .5e1 - -a")));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.tree(), Node::list(vec![
+            Node::number("1").unwrap(), Node::number("-2").unwrap(), Node::number("+3.5").unwrap(),
+            Node::number(".5e1").unwrap(),
+            "-".into(), "-a".into()
+        ]));
+
+        parser = SyntacticParser::new(share!(SrcInfo::new("test-numbers", "(1a)")));
+        assert!(parser.try_parse().is_err());
+        parser = SyntacticParser::new(share!(SrcInfo::new("test-numbers", "0x")));
+        assert!(parser.try_parse().is_err());
+    }
+
+    #[test]
+    fn syntactic_parse_raw_literals() {
+        use crate::evaluation::Term;
+        use crate::syntax::Number;
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-raw", r#"0x1F 1.50 -0b101 "text""#)));
+        parser.try_parse().unwrap();
+        let tree = parser.tree();
+        let nodes: &Vec<Node> = tree.as_ref();
+        assert_eq!(nodes[0], Node::Number { value: Number::Int(31), raw: "0x1F".to_string() });
+        assert_eq!(nodes[1], Node::Number { value: Number::Float(1.5), raw: "1.50".to_string() });
+        assert_eq!(nodes[2], Node::Number { value: Number::Int(-5), raw: "-0b101".to_string() });
+        assert_eq!(nodes[3], Node::String { value: "text".to_string(), raw: r#""text""#.to_string() });
+        // The formatting keeps the spelling, while the evaluation uses the value.
+        assert_eq!(tree.to_string(), r#"(0x1F 1.50 -0b101 "text")"#);
+        assert_eq!(Term::from(nodes[0].clone()), Term::int(31));
+    }
+
+    #[test]
+    fn syntactic_parse_stray_closer() {
+        use crate::error::ReportStyle;
+        let src = share!(SrcInfo::new("stray", "foo )"));
+        let err = SyntacticParser::new(src.clone()).try_parse().unwrap_err();
+        assert_eq!(*err.span(), 4..5);
+        assert_eq!(err.message(), "Unexpected ')', as there is no '(' left to close.");
+        let mut report = vec![];
+        err.write_report(&src.borrow(), &ReportStyle::default(), &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains("stray:1:5") && report.contains("This ')' has no matching '('."));
+
+        let err = SyntacticParser::new(share!(SrcInfo::new("stray", "(a)\n  bar]"))).try_parse().unwrap_err();
+        assert_eq!(*err.span(), 9..10);
+    }
+
+    #[test]
+    fn syntactic_parse_separators() {
+        use crate::error::ErrorKind;
+        for (text, span) in [(";", 0..1), (",", 0..1), ("(a , b)", 3..4), ("(a #;b ;)", 7..8)] {
+            let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-separators", text)));
+            let err = parser.try_parse().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidSyntax);
+            assert_eq!(*err.span(), span);
+        }
+        let result = std::panic::catch_unwind(|| {
+            SyntacticParser::new(share!(SrcInfo::new("", ""))).parse_untraced(vec![",".into()])
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn syntactic_parse_malformed_strings() {
+        use crate::error::{ErrorKind, ReportStyle};
+        let cases = [
+            (r#"(a "bc"#, 3..6, "No closing \" was found for the string.", "This string needs a closing \"."),
+            (r#"(a "b')"#, 3..6, "The string opened with \" is closed with '.", "Close this string with \" as well."),
+            (r#"(a "b'c)"#, 3..7, "Unexpected 'c' right after the string.", "Separate this string from what follows it."),
+            (r#"(a 'b'cd e)"#, 3..8, "Unexpected 'cd' right after the string.", "Separate this string from what follows it."),
+            ("\"", 0..1, "No closing \" was found for the string.", "This string needs a closing \"."),
+            (r#"(a "b\")"#, 3..8, "No closing \" was found for the string.", "This string needs a closing \"."),
+            (r#"(a "b\q")"#, 5..7, r"Unknown escape '\q' in the string.", "Escape only n, t, r, \\, \" or ' in this string."),
+        ];
+        for (text, span, message, label) in cases {
+            let src = share!(SrcInfo::new("malformed", text));
+            let err = SyntacticParser::new(src.clone()).try_parse().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidSyntax, "{text}");
+            assert_eq!(*err.span(), span, "{text}");
+            assert_eq!(err.message(), message, "{text}");
+            let mut report = vec![];
+            err.write_report(&src.borrow(), &ReportStyle::default(), &mut report).unwrap();
+            assert!(String::from_utf8(report).unwrap().contains(label), "{text}");
+            // Parsing one form at a time reports the same error.
+            let err = SyntacticParser::new(src).parse_one().unwrap().unwrap_err();
+            assert_eq!(*err.span(), span, "{text}");
+        }
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("quoted", r#"('say "hi"' "")"#)));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.tree().to_string(), r#"(('say "hi"' ""))"#);
+
+        // The escapes stand for the characters they name, and keep a quote from ending the string.
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("escaped", r#"("a\nb\t\\" 'it\'s' "\"")"#)));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.tree().as_ref()[0], Node::list(vec![
+            Node::String { value: "a\nb\t\\".to_string(), raw: r#""a\nb\t\\""#.to_string() },
+            Node::String { value: "it's".to_string(), raw: r#"'it\'s'"#.to_string() },
+            Node::String { value: "\"".to_string(), raw: r#""\"""#.to_string() }]));
+    }
+
+    #[test]
+    fn syntactic_parse_booleans() {
         use Node::*;
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-booleans", "(#t #f #true #false #tf)")));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.tree(), Node::list(vec![
+            Node::list(vec![Bool(true), Bool(false), Bool(true), Bool(false), "#tf".into()])
+        ]));
+
+        // Only the exact spellings are booleans, and the near misses are symbols.
+        let src = "#truer #fals #T #FALSE #талант";
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-near-booleans", src)));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.tree(), Node::list(src.split(' ').map(Node::from).collect()));
+    }
+
+    #[test]
+    fn syntactic_parse_characters() {
+        let src = r"(#\a #\( #\  #\; #\space #\nul #\x3bb #\Σ #\x)";
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-characters", src)));
+        parser.try_parse().unwrap();
+        let chars: Vec<char> = parser.tree().as_ref()[0].as_ref().iter().map(|node| match node {
+            Node::Char { value, .. } => *value,
+            node => panic!("{node:?} is not a character.")
+        }).collect();
+        assert_eq!(chars, vec!['a', '(', ' ', ';', ' ', '\0', 'λ', 'Σ', 'x']);
+        // The delimiter read as a character doesn't count.
+        assert_eq!(LexicalParser::count_balance(r"(#\) #\()"), 0);
+        assert_eq!(Node::character('\u{7}').to_string(), r"#\alarm");
+        assert_eq!(Node::character('\u{85}').to_string(), r"#\x85");
+
+        for src in [r"#\foo", r"#\xZZ", r"#\xd800", "#\\"] {
+            let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-invalid-character", src)));
+            assert_eq!(parser.try_parse().unwrap_err().kind(), crate::error::ErrorKind::InvalidSyntax, "{src}");
+        }
+    }
+
+    #[test]
+    fn syntactic_parse_invalid_symbol() {
+        use crate::error::ErrorKind;
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-invalid-symbol", "(a b\x01c)")));
+        let err = parser.try_parse().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(*err.span(), 3..6);
+        assert!(err.report.is_some());
+    }
+
+    #[test]
+    fn syntactic_parse_datum_comment() {
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-datum-comment", "(a #;(b c) d)")));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.tree(), Node::list(vec![Node::list(vec!["a".into(), "d".into()])]));
+
+        parser = SyntacticParser::new(share!(SrcInfo::new("test-datum-comment", "#; #; a (b) c #;[d (e)]")));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.tree(), Node::list(vec!["c".into()]));
+
+        parser = SyntacticParser::new(share!(SrcInfo::new("test-datum-comment", "(a #; )")));
+        assert!(parser.try_parse().is_err());
+        parser = SyntacticParser::new(share!(SrcInfo::new("test-datum-comment", "a #;")));
+        assert!(parser.try_parse().is_err());
+    }
+
+    #[test]
+    fn syntactic_form_selection() {
+        let parse = |src: &str| {
+            let mut parser = SyntacticParser::new(share!(SrcInfo::new("select", src)));
+            let _ = parser.try_parse_all();
+            parser
+        };
+        let parser = parse("(a (b c) d)");
+        let c = "(a (b c) d)".find('c').unwrap();
+        assert_eq!(parser.form_at(c), Some(6..7));
+        assert_eq!(parser.enclosing_form(c), Some(3..8));
+        assert_eq!(parser.enclosing_form(3), Some(0..11));
+        assert_eq!(parser.form_at(8), Some(0..11));
+        assert_eq!(parser.enclosing_form(0), None);
+        assert_eq!((parser.form_at(11), parser.form_at(100)), (None, None));
+
+        // The offsets count characters, as the spans do, past strings with delimiters in them.
+        let src = "[λ \"x)\" {ö y}]  z";
+        let parser = parse(src);
+        let chars: Vec<char> = src.chars().collect();
+        let text = |span: Option<std::ops::Range<usize>>| chars[span.unwrap()].iter().collect::<String>();
+        let offset = |ch: char| chars.iter().position(|&c| c == ch).unwrap();
+        assert_eq!(text(parser.enclosing_form(offset('y'))), "{ö y}");
+        assert_eq!(parser.form_at(offset('y')), parser.span_of(&[0, 2, 1]));
+        assert_eq!(text(parser.form_at(offset('x'))), "\"x)\"");
+        assert_eq!(text(parser.enclosing_form(offset('y') - 1)), "[λ \"x)\" {ö y}]");
+        assert_eq!(text(parser.form_at(chars.len() - 1)), "z");
+        assert_eq!(parser.form_at(chars.len() - 2), None);
+        // Only the forms parsed are selected, so a list left open has none.
+        let parser = parse("(a b) (c (d)");
+        assert_eq!((parser.form_at(1), parser.enclosing_form(1)), (Some(1..2), Some(0..5)));
+        assert_eq!((parser.form_at(7), parser.form_at(10)), (None, None));
+    }
+
+    #[test]
+    fn node_spans() {
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("spans", "(a b)")));
+        parser.try_parse().unwrap();
+        assert_eq!(parser.span_of(&[0]), Some(0..5));
+        assert_eq!(parser.span_of(&[0, 1]), Some(3..4));
+        assert_eq!((parser.span_of(&[0, 2]), parser.span_of(&[1])), (None, None));
+
+        // Commented out datums have no spans, and neither do forms which failed to parse.
+        let src = "#;(x) [f 'ö' #;y {g 1}] z (1x) w";
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("spans", src)));
+        assert!(parser.try_parse_all().is_err());
+        let text = |path: &[usize]| src.chars().collect::<Vec<_>>()[parser.span_of(path).unwrap()]
+            .iter().collect::<String>();
+        assert_eq!(parser.spans().len(), 3);
+        assert_eq!(text(&[0]), "[f 'ö' #;y {g 1}]");
+        assert_eq!((text(&[0, 1]), text(&[0, 2]), text(&[0, 2, 1])), ("'ö'".into(), "{g 1}".into(), "1".into()));
+        assert_eq!((text(&[1]), text(&[2])), ("z".into(), "w".into()));
+    }
+
+    #[test]
+    fn syntactic_parse_all() {
+        use crate::error::ErrorKind;
+
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-all", "(a) ] (b , c) 1x (d)")));
+        let errors = parser.try_parse_all().unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(parser.tree(), Node::list(vec![Node::list(vec!["a".into()]), Node::list(vec!["d".into()])]));
+
+        // Garbage yields one error per stray closer, up to the cap and a final note.
+        let garbage = ")".repeat(10_000);
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-all", garbage.as_str())));
+        let errors = parser.try_parse_all().unwrap_err();
+        assert_eq!(errors.len(), super::DEFAULT_MAX_ERRORS + 1);
+        assert!(errors[..super::DEFAULT_MAX_ERRORS].iter().all(|err| err.message().starts_with("Unexpected ')'")));
+        assert_eq!(errors.last().unwrap().kind(), ErrorKind::InvalidSyntax);
+        assert_eq!(errors.last().unwrap().message(), "Too many errors, stopped after the first 100.");
+
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-all", "] ] ]"))).with_max_errors(3);
+        assert_eq!(parser.try_parse_all().unwrap_err().len(), 3);
+        let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-all", "] ] ] ]"))).with_max_errors(3);
+        assert_eq!(parser.try_parse_all().unwrap_err()[3].message(), "Too many errors, stopped after the first 3.");
+    }
+
+    #[test]
+    fn syntactic_parse_parentheses_match() {
         let mut parser = SyntacticParser::new(share!(SrcInfo::new("test-1", "([{}])")));
         parser.parse();
-        assert_eq!(parser.tree(), List(vec![List(vec![List(vec![List(vec![])])])]));
+        assert_eq!(parser.tree(), Node::list(vec![Node::list(vec![Node::List(vec![Node::Map(vec![])], Bracket::Square)])]));
     }
 }
@@ -1,45 +1,99 @@
 use core::fmt::Display;
 
 use crate::error::{Error, ErrorKind};
-use crate::evaluation::Term;
+use crate::evaluation::{format_float, Term};
 use crate::parser::Token;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Symbol(String);
+/// How the symbols read from a source are told apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymbolCase {
+    /// `Foo` and `foo` are different symbols.
+    #[default]
+    Sensitive,
+    /// Symbols are folded to lowercase, so `Foo` and `foo` are the same symbol.
+    Insensitive
+}
+
+/// A name, which is compared by its normalized form but written as it was spelled.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    name: String,
+    /// The spelling in the source, where it differs from the name.
+    spelling: Option<String>
+}
 
 impl Symbol {
     /// Warning: The constructor does not include validation.
     /// For parsing, use `Symbol::try_form` instead.
     pub fn new<S: Into<String>>(value: S) -> Self {
-        Self(value.into())
+        Self { name: value.into(), spelling: None }
+    }
+
+    /// Normalize the name by the policy, keeping its spelling to be written.
+    pub fn with_case(self, case: SymbolCase) -> Self {
+        match case {
+            SymbolCase::Sensitive => self,
+            SymbolCase::Insensitive => {
+                let name = self.name.to_lowercase();
+                let spelling = self.spelling.or_else(|| (name != self.name).then_some(self.name));
+                Self { name, spelling }
+            }
+        }
     }
 
     pub fn validate_token(token: &Token) -> bool {
         for ch in token.as_ref().chars() {
-            if "()[]{}\x0b".contains(ch) || ch.is_ascii_whitespace() { return false; }
+            if "()[]{}".contains(ch) || ch.is_ascii_whitespace() || ch.is_control() { return false; }
         }
         true
     }
 }
 
+/// The normalized name, by which the symbol is looked up.
 impl AsRef<String> for Symbol {
     fn as_ref(&self) -> &String {
-        &self.0
+        &self.name
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+impl std::hash::Hash for Symbol {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state)
     }
 }
 
 impl Display for Symbol {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.spelling.as_ref().unwrap_or(&self.name))
     }
 }
 
 impl From<&str> for Symbol {
-    fn from(value: &str) -> Self { Self(value.to_string()) }
+    fn from(value: &str) -> Self { Self::new(value) }
 }
 
 impl From<String> for Symbol {
-    fn from(value: String) -> Self { Self(value) }
+    fn from(value: String) -> Self { Self::new(value) }
 }
 
 impl TryFrom<Token> for Symbol {
@@ -48,7 +102,7 @@ impl TryFrom<Token> for Symbol {
     /// Warning: The `try_from` is actually used to convert from
     /// a symbol token safely.
     fn try_from(token: Token) -> Result<Self, Self::Error> {
-        if Symbol::validate_token(&token) { Ok(Self(token.into())) }
+        if Symbol::validate_token(&token) { Ok(Self::new(token)) }
         else {
             // TODO: Adjust into builder form.
             Err(Error::from((
@@ -59,29 +113,381 @@ impl TryFrom<Token> for Symbol {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The value of a number literal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i64),
+    Float(f64)
+}
+
+impl Number {
+    /// Decimal integers and reals, optionally signed, like `42`, `-7`, `+1.5` or `2e10`,
+    /// and integers with a radix prefix, like `0x1F`, `0o17` or `-0b101`.
+    pub fn parse(raw: &str) -> Option<Number> {
+        let unsigned = raw.strip_prefix(['+', '-']).unwrap_or(raw);
+        let radix = match unsigned.get(..2) {
+            Some("0x" | "0X") => 16,
+            Some("0o" | "0O") => 8,
+            Some("0b" | "0B") => 2,
+            _ => 10
+        };
+        if radix != 10 {
+            let digits = &unsigned[2..];
+            if digits.is_empty() || digits.starts_with(['+', '-']) { return None }
+            let magnitude = i64::from_str_radix(digits, radix).ok()?;
+            return Some(Number::Int(if raw.starts_with('-') { -magnitude } else { magnitude }))
+        }
+        if !raw.chars().any(|ch| ch.is_ascii_digit()) || !raw.chars().all(|ch| "0123456789+-.eE".contains(ch)) {
+            return None
+        }
+        match raw.parse::<i64>() {
+            Ok(int) => Some(Number::Int(int)),
+            Err(_) => raw.parse::<f64>().ok().map(Number::Float)
+        }
+    }
+}
+
+/// The kind of the delimiters a list was written in, which are interchangeable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Bracket {
+    #[default]
+    Round,
+    Square,
+    Curly
+}
+
+impl Bracket {
+    /// The kind of an opening or closing delimiter token.
+    pub fn of(token: &Token) -> Self {
+        match token.as_ref() {
+            "[" | "]" => Bracket::Square,
+            "{" | "}" => Bracket::Curly,
+            _ => Bracket::Round
+        }
+    }
+
+    pub fn open(self) -> char {
+        match self {
+            Bracket::Round => '(',
+            Bracket::Square => '[',
+            Bracket::Curly => '{'
+        }
+    }
+
+    pub fn close(self) -> char {
+        match self {
+            Bracket::Round => ')',
+            Bracket::Square => ']',
+            Bracket::Curly => '}'
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Node {
-    List(Vec<Node>),
-    Number(String),
-    String(String),
+    /// The delimiters are kept, like the spelling of literals, so that the list can be
+    /// written as it was.
+    List(Vec<Node>, Bracket),
+    /// A map literal written in braces, like `{a 1 b 2}`, with each key and its value.
+    Map(Vec<(Node, Node)>),
+    Bool(bool),
+    /// The spelling of a literal is kept in `raw`, so that it can be formatted as written.
+    Number { value: Number, raw: String },
+    String { value: String, raw: String },
+    Char { value: char, raw: String },
     Symbol(Symbol)
 }
 
+/// Where a node was written in its source, kept beside the tree rather than in it, so that
+/// nodes built by macros and the nodes read from a source compare the same.
+///
+/// The span counts characters, like the spans of errors, and covers a list from its opener
+/// to its closer. The spans of the elements of a list are in `children`, in the same order,
+/// as are those of the keys and values of a map, each key followed by its value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeSpan {
+    pub span: std::ops::Range<usize>,
+    pub children: Vec<NodeSpan>
+}
+
+impl NodeSpan {
+    /// The span of the node reached by taking the element at each index of the path in turn.
+    pub fn get(&self, path: &[usize]) -> Option<&NodeSpan> {
+        path.iter().try_fold(self, |span, &index| span.children.get(index))
+    }
+}
+
 impl Node {
+    /// A list written in parentheses.
+    pub fn list(nodes: Vec<Node>) -> Node {
+        Node::List(nodes, Bracket::Round)
+    }
+
+    /// The number written as `raw`, if it is a number literal.
+    pub fn number(raw: &str) -> Option<Node> {
+        Number::parse(raw).map(|value| Node::Number { value, raw: raw.to_string() })
+    }
+
+    /// A string literal, written in double quotes.
+    pub fn string<S: Into<String>>(value: S) -> Node {
+        let value = value.into();
+        let mut raw = String::new();
+        let _ = write_string_literal(&mut raw, &value);
+        Node::String { raw, value }
+    }
+
+    /// A character literal, written by its name if it has one, like `#\space`.
+    pub fn character(value: char) -> Node {
+        let mut raw = String::new();
+        let _ = write_char_literal(&mut raw, value);
+        Node::Char { raw, value }
+    }
+
+    /// Push the node onto the list, returning the pushed node, or an error if this isn't a list.
+    pub fn try_push(&mut self, node: Node) -> Result<&mut Node, Error> {
+        match self {
+            Node::List(list, _) => {
+                list.push(node);
+                Ok(list.last_mut().unwrap())
+            }
+            _ => Err(Error::new(ErrorKind::TypeMismatch)
+                .with_message(format!("Cannot push onto '{self}', which is not a list.")))
+        }
+    }
+
     pub fn push(&mut self, node: Node) -> &mut Node{
         self.as_mut().push(node);
         match self {
-            Node::List(list) => list.last_mut().unwrap(),
+            Node::List(list, _) => list.last_mut().unwrap(),
             _ => panic!()
         }
     }
 
+    /// The children of a list or a map, where each key of a map is followed by its value.
+    pub(crate) fn children(&self) -> Vec<&Node> {
+        match self {
+            Node::List(list, _) => list.iter().collect(),
+            Node::Map(entries) => entries.iter().flat_map(|(key, value)| [key, value]).collect(),
+            _ => vec![]
+        }
+    }
+
+    /// The maximum nesting depth of the node. Leaves and empty lists are of depth 0,
+    /// and a non-empty list or map is one deeper than its deepest child.
+    pub fn depth(&self) -> usize {
+        // Use an explicit stack to prevent overflow on deeply nested inputs.
+        let mut stack = vec![(self, 0usize)];
+        let mut depth = 0;
+        while let Some((node, level)) = stack.pop() {
+            depth = depth.max(level);
+            stack.extend(node.children().into_iter().map(|child| (child, level + 1)));
+        }
+        depth
+    }
+
+    /// The kind of delimiters the list was written in, or `None` for other nodes.
+    pub fn bracket(&self) -> Option<Bracket> {
+        match self {
+            Node::List(_, bracket) => Some(*bracket),
+            _ => None
+        }
+    }
+
+    /// Write every list of the node in the same kind of delimiters, leaving maps in braces.
+    /// Lists can't be written in braces, which would read back as maps.
+    pub fn normalize_brackets(&mut self, bracket: Bracket) {
+        assert_ne!(bracket, Bracket::Curly, "Braces are the delimiters of maps.");
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::List(list, kind) => {
+                    *kind = bracket;
+                    stack.extend(list.iter_mut());
+                }
+                Node::Map(entries) => stack.extend(entries.iter_mut().flat_map(|(key, value)| [key, value])),
+                _ => ()
+            }
+        }
+    }
+
+    /// The total number of nodes, including both leaves and lists.
+    pub fn size(&self) -> usize {
+        let mut stack = vec![self];
+        let mut size = 0;
+        while let Some(node) = stack.pop() {
+            size += 1;
+            stack.extend(node.children());
+        }
+        size
+    }
+}
+
+impl Node {
+    /// Write the node in a single canonical spelling, which reads back as the same tree,
+    /// unlike `Display`, which writes the literals as they were written.
+    ///
+    /// Brackets and braces of lists are written as parentheses, numbers in decimal, and strings
+    /// in double quotes with their quotes, backslashes and line breaks escaped, so that each
+    /// form stays on one line. Maps stay in braces.
+    pub fn to_sexpr(&self) -> String {
+        let mut out = String::new();
+        self.write_sexpr(&mut out);
+        out
+    }
+
+    fn write_sexpr(&self, out: &mut String) {
+        match self {
+            Node::List(nodes, _) => {
+                out.push('(');
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 { out.push(' ') }
+                    node.write_sexpr(out);
+                }
+                out.push(')');
+            }
+            Node::Map(_) => {
+                out.push('{');
+                for (i, node) in self.children().into_iter().enumerate() {
+                    if i > 0 { out.push(' ') }
+                    node.write_sexpr(out);
+                }
+                out.push('}');
+            }
+            Node::Bool(b) => out.push_str(if *b { "#t" } else { "#f" }),
+            Node::Number { value: Number::Int(n), .. } => out.push_str(&n.to_string()),
+            Node::Number { value: Number::Float(x), .. } => out.push_str(&format_float(*x)),
+            // Writing to a string can't fail.
+            Node::String { value, .. } => { let _ = write_string_literal(out, value); }
+            Node::Char { value, .. } => { let _ = write_char_literal(out, *value); }
+            Node::Symbol(symbol) => out.push_str(symbol.as_ref())
+        }
+    }
+
+    /// Encode the node as JSON, where lists are arrays and symbols are objects like
+    /// `{"symbol": "name"}` to tell them from strings, as characters are like `{"char": "a"}`.
+    /// Maps are objects like `{"map": [[key, value]]}`, as their keys needn't be strings.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+
+    fn write_json(&self, json: &mut String) {
+        match self {
+            Node::List(nodes, _) => {
+                json.push('[');
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 { json.push(',') }
+                    node.write_json(json);
+                }
+                json.push(']');
+            }
+            Node::Map(entries) => {
+                json.push_str("{\"map\":[");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 { json.push(',') }
+                    json.push('[');
+                    key.write_json(json);
+                    json.push(',');
+                    value.write_json(json);
+                    json.push(']');
+                }
+                json.push_str("]}");
+            }
+            Node::Bool(b) => json.push_str(if *b { "true" } else { "false" }),
+            // Literals like `+1` or `.5` are not valid in JSON, so they are normalized.
+            Node::Number { value: Number::Int(int), .. } => json.push_str(&int.to_string()),
+            Node::Number { value: Number::Float(real), .. } if real.is_finite() =>
+                json.push_str(&format!("{real:?}")),
+            Node::Number { raw, .. } => write_json_string(json, raw),
+            Node::String { value, .. } => write_json_string(json, value),
+            Node::Char { value, .. } => {
+                json.push_str("{\"char\":");
+                write_json_string(json, &value.to_string());
+                json.push('}');
+            }
+            Node::Symbol(symbol) => {
+                json.push_str("{\"symbol\":");
+                write_json_string(json, symbol.as_ref());
+                json.push('}');
+            }
+        }
+    }
+}
+
+/// The names of the characters which are written by name, as `#\space` is. `#\nul` is read
+/// as well, but written as `#\null`.
+const CHARACTER_NAMES: &[(&str, char)] = &[
+    ("alarm", '\u{7}'),
+    ("backspace", '\u{8}'),
+    ("delete", '\u{7f}'),
+    ("escape", '\u{1b}'),
+    ("newline", '\n'),
+    ("null", '\0'),
+    ("nul", '\0'),
+    ("return", '\r'),
+    ("space", ' '),
+    ("tab", '\t')
+];
+
+/// The character of a literal like `#\a`, `#\space` or `#\x3bb`, or `None` if it isn't one.
+pub(crate) fn parse_char_literal(raw: &str) -> Option<char> {
+    let rest = raw.strip_prefix("#\\")?;
+    let mut chars = rest.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) { return Some(ch) }
+    if let Some(&(_, ch)) = CHARACTER_NAMES.iter().find(|(name, _)| *name == rest) { return Some(ch) }
+    let digits = rest.strip_prefix('x')?;
+    u32::from_str_radix(digits, 16).ok().filter(|_| !digits.starts_with('+')).and_then(char::from_u32)
+}
+
+/// Write the character as a literal which reads back as the same character: by its name if it
+/// has one, in hexadecimal if it is otherwise invisible, or else as itself.
+pub(crate) fn write_char_literal<W: std::fmt::Write>(out: &mut W, ch: char) -> std::fmt::Result {
+    match CHARACTER_NAMES.iter().find(|(_, named)| *named == ch) {
+        Some((name, _)) => write!(out, "#\\{name}"),
+        None if ch.is_control() || ch.is_whitespace() => write!(out, "#\\x{:x}", ch as u32),
+        None => write!(out, "#\\{ch}")
+    }
+}
+
+/// Write the string in double quotes, escaped as the reader unescapes it, so that it reads back
+/// as the same string.
+pub(crate) fn write_string_literal<W: std::fmt::Write>(out: &mut W, s: &str) -> std::fmt::Result {
+    out.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => out.write_str("\\\"")?,
+            '\'' => out.write_str("\\'")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            ch => out.write_char(ch)?
+        }
+    }
+    out.write_char('"')
+}
+
+fn write_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if ch.is_control() => json.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => json.push(ch)
+        }
+    }
+    json.push('"');
 }
 
 impl AsMut<Vec<Node>> for Node {
     fn as_mut(&mut self) -> &mut Vec<Node> {
         match self {
-            Node::List(list) => list,
+            Node::List(list, _) => list,
             _ => panic!()
         }
     }
@@ -91,7 +497,7 @@ impl AsMut<Vec<Node>> for Node {
 impl AsRef<Vec<Node>> for Node {
     fn as_ref(&self) -> &Vec<Node> {
         match self {
-            Self::List(vec) => vec,
+            Self::List(vec, _) => vec,
             _ => panic!()
         }
     }
@@ -101,19 +507,30 @@ impl Display for Node {
     // TODO: Ensure the safety of nested call to print lists of arbitrary depth.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Node::List(nodes) => {
-                if nodes.is_empty() { return write!(f, "()"); }
-
-                if nodes.len() == 1 { return write!(f, "({})", nodes[0]) }
-
-                write!(f, "({} ", nodes[0])?;
-                for node in nodes[1..nodes.len() - 1].iter() {
-                    write!(f, "{} ", node)?
+            Node::List(nodes, bracket) => {
+                // The alternate form writes every list in parentheses.
+                let bracket = if f.alternate() { Bracket::Round } else { *bracket };
+                write!(f, "{}", bracket.open())?;
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 { write!(f, " ")? }
+                    node.fmt(f)?
                 }
-                write!(f, "{})", nodes.last().unwrap())
+                write!(f, "{}", bracket.close())
             },
-            Node::Number(n) => write!(f, "{}", n),
-            Node::String(s) => write!(f, "{}", s),
+            Node::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 { write!(f, " ")? }
+                    key.fmt(f)?;
+                    write!(f, " ")?;
+                    value.fmt(f)?
+                }
+                write!(f, "}}")
+            }
+            Node::Bool(b) => write!(f, "{}", if *b { "#t" } else { "#f" }),
+            Node::Number { raw, .. } => write!(f, "{}", raw),
+            Node::String { raw, .. } => write!(f, "{}", raw),
+            Node::Char { raw, .. } => write!(f, "{}", raw),
             Node::Symbol(symbol) => write!(f, "{}", symbol)
         }
     }
@@ -125,16 +542,23 @@ impl From<&str> for Node {
     }
 }
 
+impl From<bool> for Node {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
 impl From<i64> for Node {
     fn from(value: i64) -> Self {
-        Self::Number(value.to_string())
+        Self::Number { value: Number::Int(value), raw: value.to_string() }
     }
 }
 
-impl Into<Term> for Node {
-    fn into(self) -> Term {
-        match self {
-            Node::List(mut list) => {
+impl From<Node> for Term {
+    fn from(value: Node) -> Self {
+        match value {
+            Node::List(list, _) if list.is_empty() => Term::nil(),
+            Node::List(mut list, _) => {
                 let mut term = Term::new();
                 term.sub_terms = {
                     let taken_vec = std::mem::take(&mut list);
@@ -142,14 +566,15 @@ impl Into<Term> for Node {
                 };
                 term
             },
-            Node::Number(n) => {
-                Term::from(n)
-            }
-            Node::String(s) => {
-                Term::from(s)
-            }
+            // A map literal is data, like a quoted datum, so neither its keys nor its values are evaluated.
+            node @ Node::Map(_) => Term::datum(node),
+            Node::Bool(b) => Term::from(b),
+            Node::Number { value: Number::Int(int), .. } => Term::from(int),
+            Node::Number { value: Number::Float(real), .. } => Term::from(real),
+            Node::String { value, .. } => Term::from(value),
+            Node::Char { value, .. } => Term::from(value),
             Node::Symbol(symbol) => {
-                Term::from(symbol)
+                Term::symbol(symbol)
             },
         }
     }
@@ -158,22 +583,124 @@ impl Into<Term> for Node {
 #[cfg(test)]
 mod tests {
     use crate::parser::Token;
-    use super::{Node, Symbol};
+    use super::{Bracket, Node, Symbol};
 
     #[test]
     fn node_to_string() {
         use Node::*;
-        assert_eq!(List(vec![Symbol("apply".into()), Symbol("+".into())]).to_string(), "(apply +)");
+        assert_eq!(Node::list(vec![Symbol("apply".into()), Symbol("+".into())]).to_string(), "(apply +)");
+    }
+
+    #[test]
+    fn node_to_json() {
+        use Node::*;
+        let node = Node::list(vec![
+            "display".into(), Node::string("a \"quoted\"\n"), Node::number("+1").unwrap(), Node::number(".5").unwrap(),
+            Bool(true), Node::list(vec![]), Node::character('a')
+        ]);
+        assert_eq!(node.to_json(),
+            r#"[{"symbol":"display"},"a \"quoted\"\n",1,0.5,true,[],{"char":"a"}]"#);
+    }
+
+    #[test]
+    fn node_depth_and_size() {
+        let empty = Node::list(vec![]);
+        assert_eq!((empty.depth(), empty.size()), (0, 1));
+        let nested = Node::list(vec!["a".into(), Node::list(vec!["b".into(), "c".into()])]);
+        assert_eq!((nested.depth(), nested.size()), (2, 5));
+        let leaf: Node = "a".into();
+        assert_eq!((leaf.depth(), leaf.size()), (0, 1));
+    }
+
+    #[test]
+    fn node_try_push() {
+        let mut list = Node::list(vec!["a".into()]);
+        let pushed = list.try_push(Node::list(vec![])).unwrap();
+        pushed.try_push("b".into()).unwrap();
+        assert_eq!(list.to_string(), "(a (b))");
+
+        let mut symbol: Node = "a".into();
+        let err = symbol.try_push("b".into()).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::TypeMismatch);
+        assert_eq!(err.message(), "Cannot push onto 'a', which is not a list.");
+        assert_eq!(symbol, "a".into());
+    }
+
+    #[test]
+    fn node_to_sexpr() {
+        use crate::parser::{SrcInfo, SyntacticParser};
+        use crate::share;
+        let parse = |src: &str| {
+            let mut parser = SyntacticParser::new(share!(SrcInfo::new("test", src)));
+            parser.try_parse().unwrap();
+            parser.tree()
+        };
+        let form = parse("[f {x 0x1F} +2.50 -0b11 #true 'say \"hi\"' \"ok\" () 1e3 #\\x41 #\\nul]").as_ref()[0].clone();
+        let sexpr = form.to_sexpr();
+        assert_eq!(sexpr, r#"(f {x 31} 2.5 -3 #t "say \"hi\"" "ok" () 1000.0 #\A #\null)"#);
+
+        // The canonical text reads back as an equal tree, which is written the same way again.
+        let reparsed = parse(&sexpr).as_ref()[0].clone();
+        assert_eq!(reparsed.to_sexpr(), sexpr);
+        assert_eq!(parse(&reparsed.to_sexpr()).as_ref()[0], reparsed);
+        assert_eq!(Node::from("x").to_sexpr(), "x");
+    }
+
+    #[test]
+    fn node_bracket() {
+        use crate::parser::{SrcInfo, SyntacticParser};
+        let mut parser = SyntacticParser::new(crate::share!(SrcInfo::new("test", "[a] {b (c)} d")));
+        parser.try_parse().unwrap();
+        let forms = parser.tree().as_ref().clone();
+        assert_eq!(forms[0].bracket().map(Bracket::open), Some('['));
+        // Braces make a map rather than a list.
+        assert_eq!(forms[1], Node::Map(vec![("b".into(), Node::list(vec!["c".into()]))]));
+        assert_eq!((forms[1].bracket(), forms[2].bracket()), (None, None));
+        assert_eq!(forms[0].to_string(), "[a]");
+        assert_eq!(forms[1].to_string(), "{b (c)}");
+        assert_eq!(format!("{:#}", forms[0]), "(a)");
+    }
+
+    #[test]
+    fn node_normalize_brackets() {
+        use crate::parser::{SrcInfo, SyntacticParser};
+        let mut parser = SyntacticParser::new(crate::share!(SrcInfo::new("test", "[a {b [c]}]")));
+        parser.try_parse().unwrap();
+        let mut node = parser.tree().as_ref()[0].clone();
+        assert!(matches!(node, Node::List(_, Bracket::Square)));
+        assert_eq!(node.to_string(), "[a {b [c]}]");
+        node.normalize_brackets(Bracket::Round);
+        assert_eq!(node, Node::list(vec!["a".into(), Node::Map(vec![("b".into(), Node::list(vec!["c".into()]))])]));
+        assert_eq!(node.to_string(), "(a {b (c)})");
+        node.normalize_brackets(Bracket::Square);
+        assert_eq!(node.to_string(), "[a {b [c]}]");
+    }
+
+    #[test]
+    fn node_depth_of_deep_nesting() {
+        let mut node = Node::list(vec![]);
+        for _ in 0..100_000 {
+            node = Node::list(vec![node]);
+        }
+        assert_eq!(node.depth(), 100_000);
+        assert_eq!(node.size(), 100_001);
+        // Dismantle iteratively, since the derived drop is recursive.
+        while let Node::List(mut list, _) = node {
+            match list.pop() {
+                Some(child) => node = child,
+                None => break
+            }
+        }
     }
 
     #[test]
     fn symbol_from_str(){
-        assert_eq!(Symbol::from("symbol"), Symbol("symbol".to_string()));
+        assert_eq!(Symbol::from("symbol"), Symbol { name: "symbol".to_string(), spelling: None });
     }
 
     #[test]
     fn symbol_from_string() {
-        assert_eq!(Symbol::from("symbol"), Symbol("symbol".to_string()));
+        assert_eq!(Symbol::from("symbol"), Symbol { name: "symbol".to_string(), spelling: None });
 
         let literal = "test-move".to_string();
         assert_eq!(Symbol::from(literal), Symbol::from("test-move"));
@@ -184,6 +711,17 @@ mod tests {
         assert_eq!(Symbol::new("symbol").to_string(), "symbol");
     }
 
+    #[test]
+    fn symbol_case() {
+        use super::SymbolCase::*;
+        let folded = Symbol::new("Foo").with_case(Insensitive);
+        assert_eq!((folded.as_ref().as_str(), folded.to_string()), ("foo", "Foo".to_string()));
+        assert_eq!(folded, Symbol::new("foo").with_case(Insensitive));
+        assert_eq!(folded.clone().with_case(Insensitive).to_string(), "Foo");
+        assert_ne!(Symbol::new("Foo").with_case(Sensitive), Symbol::new("foo"));
+        assert_eq!(Symbol::new("foo").with_case(Insensitive), Symbol { name: "foo".to_string(), spelling: None });
+    }
+
     #[test]
     fn symbol_try_from_token() {
         assert!(Symbol::try_from(Token::from("valid-token")).is_ok());
@@ -191,5 +729,6 @@ mod tests {
         assert!(Symbol::try_from(Token::from("(invalid-token)")).is_err());
         assert!(Symbol::try_from(Token::from("[invalid_token]")).is_err());
         assert!(Symbol::try_from(Token::from("{invalid token}")).is_err());
+        assert!(Symbol::try_from(Token::from("invalid\x07token")).is_err());
     }
 }
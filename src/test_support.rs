@@ -0,0 +1,24 @@
+//! The writers shared by the tests of the library and of the `thesis` command.
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+/// A writer collecting everything written to it, which is shared by its clones.
+#[derive(Clone, Default)]
+pub struct Buffer(pub Rc<RefCell<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> { self.0.borrow_mut().write(buf) }
+
+    fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}
+
+/// A writer failing every write with the error, like one whose reader has gone away.
+pub struct ClosedPipe(pub std::io::ErrorKind);
+
+impl Write for ClosedPipe {
+    fn write(&mut self, _: &[u8]) -> std::io::Result<usize> { Err(self.0.into()) }
+
+    fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}